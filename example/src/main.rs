@@ -45,7 +45,7 @@ async fn main() {
     bot.dispatch().await;
     let home_town_set_responses = bot.get_responses();
     // Bot sends two messages: confirmation and new menu
-    println!("Response: {}", home_town_set_responses.sent_messages.get(0).unwrap().text().unwrap());
+    println!("Response: {}", home_town_set_responses.sent_messages.first().unwrap().text().unwrap());
     last_message = home_town_set_responses.sent_messages.get(1).unwrap().clone();
     println!("Response: {}", last_message.text().unwrap());
     print_buttons(&last_message); // This should be the MAIN MENU
@@ -72,7 +72,7 @@ async fn main() {
     bot.update(MockMessageText::new().text("Lviv").from(user.clone()));
     bot.dispatch().await;
     let lviv_added_responses = bot.get_responses();
-    println!("Response: {}", lviv_added_responses.sent_messages.get(0).unwrap().text().unwrap());
+    println!("Response: {}", lviv_added_responses.sent_messages.first().unwrap().text().unwrap());
     last_message = lviv_added_responses.sent_messages.get(1).unwrap().clone(); // This is the "My Towns" menu again
     println!("Response: {}", last_message.text().unwrap());
     print_buttons(&last_message);
@@ -183,9 +183,17 @@ async fn main() {
     println!("\n--> User sends: 24");
     bot.update(MockMessageText::new().text("24").from(user.clone()));
     bot.dispatch().await;
+    last_message = bot.get_responses().sent_messages.last().unwrap().clone();
+    println!("Response: {}", last_message.text().unwrap());
+    print_buttons(&last_message);
+
+    // 20. USER: SENDS "every 30m cooldown 2h" (check frequency and cooldown)
+    println!("\n--> User sends: every 30m cooldown 2h");
+    bot.update(MockMessageText::new().text("every 30m cooldown 2h").from(user.clone()));
+    bot.dispatch().await;
     let alert_created_responses = bot.get_responses();
     // Bot sends two messages: confirmation and new menu
-    println!("Response: {}", alert_created_responses.sent_messages.get(0).unwrap().text().unwrap());
+    println!("Response: {}", alert_created_responses.sent_messages.first().unwrap().text().unwrap());
     last_message = alert_created_responses.sent_messages.get(1).unwrap().clone();
     println!("Response: {}", last_message.text().unwrap());
     print_buttons(&last_message);
@@ -229,10 +237,8 @@ fn cleanup_test_databases() {
         for entry in entries.flatten() {
             let path = entry.path();
             if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                if name.starts_with("test_weather_bot_data_") && path.is_dir() {
-                    if fs::remove_dir_all(&path).is_ok() {
-                        println!("Removed test database: {}", name);
-                    }
+                if name.starts_with("test_weather_bot_data_") && path.is_dir() && fs::remove_dir_all(&path).is_ok() {
+                    println!("Removed test database: {}", name);
                 }
             }
         }
@@ -1,7 +1,94 @@
-use crate::state::{WeatherAlert, AlertType};
-use crate::weather_api::{CurrentWeather, get_current_weather, get_forecast, ForecastResponse};
+use crate::state::{WeatherAlert, AlertState, AlertType, PrecipitationKind, SevereCategory, SwingDirection, WindSector};
+use crate::rules::{Metric, RuleExpr};
+use crate::weather_api::{AirQuality, CurrentWeather, get_current_weather, get_forecast, normalize_location, ForecastResponse, ForecastDay, Hour, WeatherWarning};
 use uuid::Uuid;
 
+/// Гистерезис: насколько значение должно уйти за порог обратно, чтобы алерт считался снятым
+const TEMPERATURE_HYSTERESIS_C: f32 = 1.0;
+const WIND_HYSTERESIS_KPH: f32 = 5.0;
+const HUMIDITY_HYSTERESIS_PCT: u32 = 3;
+const PRECIP_CHANCE_HYSTERESIS_PCT: u32 = 10;
+const PRECIP_MM_HYSTERESIS: f32 = 0.5;
+const UV_HYSTERESIS: f32 = 0.5;
+/// Пороги экстремальных значений для стандартного алерта
+const EXTREME_HEAT_C: f32 = 40.0;
+const EXTREME_COLD_C: f32 = -20.0;
+const STORM_WIND_KPH: f32 = 50.0;
+const PM2_5_HYSTERESIS: f32 = 5.0;
+const PRESSURE_HYSTERESIS_HPA: f32 = 0.5;
+const FROST_HYSTERESIS_C: f32 = 1.0;
+/// Температура воздуха (на высоте 2 м), при которой по умолчанию ожидаются заморозки на почве
+pub const DEFAULT_FROST_AIR_TEMP_C: f32 = 2.0;
+/// При точке росы ниже нуля почва выхолаживается сильнее - допускаем воздух на столько градусов теплее порога
+const FROST_DRY_AIR_BONUS_C: f32 = 2.0;
+const SNOW_HYSTERESIS_CM: f32 = 0.5;
+/// Диапазон температур около нуля, при котором мокрая дорога может обледенеть
+const ICE_TEMP_MIN_C: f32 = -3.0;
+const ICE_TEMP_MAX_C: f32 = 1.0;
+/// Сколько часов после осадков дорога считается мокрой
+const ICE_WET_LOOKBACK_HOURS: usize = 3;
+const WET_PRECIP_MM: f32 = 0.1;
+/// Готовые пороги ощущаемой температуры для мастера создания алерта
+pub struct FeelsLikePreset {
+    pub id: &'static str,
+    pub label: &'static str,
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+}
+
+/// Пороги по шкалам индекса жары NWS и ветро-холодового индекса Environment Canada
+pub const FEELS_LIKE_PRESETS: [FeelsLikePreset; 5] = [
+    FeelsLikePreset { id: "heat_caution", label: "🥵 Heat caution (≥ 27°C)", min: None, max: Some(27.0) },
+    FeelsLikePreset { id: "extreme_heat", label: "🥵 Extreme heat caution (≥ 32°C)", min: None, max: Some(32.0) },
+    FeelsLikePreset { id: "heat_danger", label: "🔥 Heat danger (≥ 41°C)", min: None, max: Some(41.0) },
+    FeelsLikePreset { id: "cold_risk", label: "🥶 Cold exposure risk (≤ -10°C)", min: Some(-10.0), max: None },
+    FeelsLikePreset { id: "frostbite", label: "🧊 Frostbite risk (≤ -28°C)", min: Some(-28.0), max: None },
+];
+
+pub fn feels_like_preset(id: &str) -> Option<&'static FeelsLikePreset> {
+    FEELS_LIKE_PRESETS.iter().find(|preset| preset.id == id)
+}
+
+/// Час считается сухим, если вероятность дождя и снега ниже этого значения
+const DRY_MAX_CHANCE_PCT: u32 = 30;
+
+/// Готовые условия "хорошей погоды" для мастера создания алерта
+pub struct GoodWeatherPreset {
+    pub id: &'static str,
+    pub label: &'static str,
+    pub min_temp: f32,
+    pub max_temp: f32,
+    pub max_wind: f32,
+}
+
+pub const GOOD_WEATHER_PRESETS: [GoodWeatherPreset; 3] = [
+    GoodWeatherPreset { id: "outdoor", label: "🧺 Outdoor event (18–25°C, wind < 15 km/h)", min_temp: 18.0, max_temp: 25.0, max_wind: 15.0 },
+    GoodWeatherPreset { id: "cycling", label: "🚴 Cycling (12–24°C, wind < 20 km/h)", min_temp: 12.0, max_temp: 24.0, max_wind: 20.0 },
+    GoodWeatherPreset { id: "running", label: "🏃 Running (5–18°C, wind < 25 km/h)", min_temp: 5.0, max_temp: 18.0, max_wind: 25.0 },
+];
+
+pub fn good_weather_preset(id: &str) -> Option<&'static GoodWeatherPreset> {
+    GOOD_WEATHER_PRESETS.iter().find(|preset| preset.id == id)
+}
+
+/// Ночные часы по местному времени: с вечера до восхода, когда почва остывает сильнее всего.
+/// Утро считается ночью только до восхода (is_day провайдера) и не позже NIGHT_END_HOUR
+const NIGHT_START_HOUR: u32 = 18;
+const NIGHT_END_HOUR: u32 = 10;
+
+/// Вероятность осадков, с которой срабатывает алерт без заданных порогов
+const DEFAULT_PRECIP_CHANCE_PCT: u32 = 50;
+
+/// Результат проверки алерта на прогнозе
+#[derive(Debug, Clone, Default)]
+pub struct AlertEvaluation {
+    pub triggered: bool,
+    /// На какое время прогноза пришлась проверка (дата или час)
+    pub target_time: Option<String>,
+    /// Значения из прогноза, относящиеся к алерту
+    pub observed: Vec<String>,
+}
+
 pub struct AlertChecker;
 
 impl AlertChecker {
@@ -18,7 +105,83 @@ impl AlertChecker {
         Self::check_forecast_for_hours(&forecast, alert)
     }
     
-    fn check_forecast_for_hours(forecast: &ForecastResponse, alert: &WeatherAlert) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    /// Проверяет алерт на уже полученном прогнозе (без запросов к API)
+    pub fn check_forecast_for_hours(forecast: &ForecastResponse, alert: &WeatherAlert) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(Self::evaluate_forecast(forecast, alert)?.triggered)
+    }
+    
+    /// Проверяет алерт на прогнозе и возвращает значения, на которых он сработал.
+    /// Для уже сработавшего алерта пороги смягчаются на величину гистерезиса,
+    /// чтобы значения около порога не вызывали постоянных срабатываний и отбоев
+    pub fn evaluate_forecast(forecast: &ForecastResponse, alert: &WeatherAlert) -> Result<AlertEvaluation, Box<dyn std::error::Error + Send + Sync>> {
+        let hysteresis = alert.state.is_active();
+        
+        // Почасовые типы алертов проверяются по всем часам до горизонта предупреждения
+        match &alert.alert_type {
+            AlertType::Precipitation { min_chance, min_mm, kind } => {
+                let hours = Self::hours_in_window(forecast, alert.hours_ahead);
+                return Ok(Self::evaluate_precipitation(&hours, *min_chance, *min_mm, *kind, hysteresis));
+            }
+            AlertType::UvIndex { max } => {
+                let hours = Self::hours_in_window(forecast, alert.hours_ahead);
+                return Ok(Self::evaluate_uv(&hours, *max, hysteresis));
+            }
+            AlertType::AirQuality { min_epa_index, max_pm2_5 } => {
+                let hours = Self::hours_in_window(forecast, alert.hours_ahead);
+                return Ok(Self::evaluate_air_quality(&hours, *min_epa_index, *max_pm2_5, hysteresis));
+            }
+            // Простой ветровой алерт проверяется по дневному максимуму, а с порывами
+            // или фильтром направления - по часам, где известны и порывы, и направление
+            AlertType::WindSpeed { max, use_gusts, direction } if *use_gusts || direction.is_some() => {
+                let hours = Self::hours_in_window(forecast, alert.hours_ahead);
+                return Ok(Self::evaluate_wind(&hours, *max, *use_gusts, *direction, hysteresis));
+            }
+            AlertType::Composite { rule } => {
+                let hours = Self::hours_in_window(forecast, alert.hours_ahead);
+                return Ok(Self::evaluate_composite(&hours, rule, hysteresis));
+            }
+            AlertType::FeelsLike { min, max } => {
+                let hours = Self::hours_in_window(forecast, alert.hours_ahead);
+                return Ok(Self::evaluate_feels_like(&hours, *min, *max, hysteresis));
+            }
+            AlertType::SnowIce { min_snow_cm, ice_risk } => {
+                let hours = Self::hours_in_window(forecast, alert.hours_ahead);
+                return Ok(Self::evaluate_snow_ice(&hours, *min_snow_cm, *ice_risk, hysteresis));
+            }
+            AlertType::Frost { max_temp } => {
+                let hours = Self::hours_in_window(forecast, alert.hours_ahead);
+                return Ok(Self::evaluate_frost(&hours, *max_temp, hysteresis));
+            }
+            AlertType::PressureDrop { drop_hpa, window_hours } => {
+                // Падение, которое уже началось, тоже считается - берем и прошедшие часы окна
+                let now = chrono::Utc::now().timestamp();
+                let hours = Self::hours_between(
+                    forecast,
+                    now - *window_hours as i64 * 3600,
+                    now + alert.hours_ahead as i64 * 3600,
+                );
+                return Ok(Self::evaluate_pressure_drop(&hours, *drop_hpa, *window_hours, hysteresis));
+            }
+            AlertType::TemperatureSwing { delta, direction, window_hours: Some(window_hours) } => {
+                // Как и для давления, учитываем перепад, который уже начался
+                let now = chrono::Utc::now().timestamp();
+                let hours = Self::hours_between(
+                    forecast,
+                    now - *window_hours as i64 * 3600,
+                    now + alert.hours_ahead as i64 * 3600,
+                );
+                return Ok(Self::evaluate_hourly_swing(&hours, *delta, *direction, *window_hours, hysteresis));
+            }
+            AlertType::TemperatureSwing { delta, direction, window_hours: None } => {
+                return Ok(Self::evaluate_day_swing(&forecast.forecast.forecast_day, alert.hours_ahead, *delta, *direction, hysteresis));
+            }
+            AlertType::GoodWeather { min_temp, max_temp, max_wind, min_hours } => {
+                let hours = Self::hours_in_window(forecast, alert.hours_ahead);
+                return Ok(Self::evaluate_good_weather(&hours, *min_temp, *max_temp, *max_wind, *min_hours, hysteresis));
+            }
+            _ => {}
+        }
+        
         let target_hours = alert.hours_ahead as usize;
         
         // Для простоты проверяем дневные показатели
@@ -26,44 +189,495 @@ impl AlertChecker {
         let days_ahead = if target_hours <= 24 { 0 } else if target_hours <= 48 { 1 } else { 2 };
         
         if let Some(forecast_day) = forecast.forecast.forecast_day.get(days_ahead) {
-            match &alert.alert_type {
-                AlertType::StandardWeatherAlert => {
-                    Self::check_forecast_standard_conditions(forecast_day)
+            Ok(AlertEvaluation {
+                triggered: Self::evaluate_forecast_day(forecast_day, &alert.alert_type, hysteresis)?,
+                target_time: Some(forecast_day.date.clone()),
+                observed: Self::describe_forecast_day(forecast_day, &alert.alert_type),
+            })
+        } else {
+            Ok(AlertEvaluation::default()) // Недостаточно данных прогноза
+        }
+    }
+    
+    /// Часы прогноза от текущего часа до now + hours_ahead
+    pub fn hours_in_window(forecast: &ForecastResponse, hours_ahead: u8) -> Vec<&Hour> {
+        let now = chrono::Utc::now().timestamp();
+        // включаем текущий час
+        Self::hours_between(forecast, now - 3600, now + hours_ahead as i64 * 3600)
+    }
+    
+    /// Часы прогноза с time_epoch в интервале (start, end]
+    fn hours_between(forecast: &ForecastResponse, start: i64, end: i64) -> Vec<&Hour> {
+        forecast.forecast.forecast_day.iter()
+            .flat_map(|day| day.hour.iter())
+            .filter(|hour| hour.time_epoch > start && hour.time_epoch <= end)
+            .collect()
+    }
+    
+    fn evaluate_precipitation(hours: &[&Hour], min_chance: Option<u32>, min_mm: Option<f32>, kind: PrecipitationKind, hysteresis: bool) -> AlertEvaluation {
+        let Some(peak) = hours.iter().max_by_key(|hour| precipitation_chance(hour.chance_of_rain, hour.chance_of_snow, kind)) else {
+            return AlertEvaluation::default(); // Недостаточно данных прогноза
+        };
+        let peak_chance = precipitation_chance(peak.chance_of_rain, peak.chance_of_snow, kind);
+        
+        // Количество осадков считаем только по часам, где ожидается нужный вид осадков
+        let total_mm: f32 = hours.iter()
+            .filter(|hour| match kind {
+                PrecipitationKind::Any => true,
+                PrecipitationKind::Rain => hour.chance_of_rain >= hour.chance_of_snow,
+                PrecipitationKind::Snow => hour.chance_of_snow > hour.chance_of_rain,
+            })
+            .map(|hour| hour.precip_mm)
+            .sum();
+        
+        AlertEvaluation {
+            triggered: precipitation_triggered(peak_chance, total_mm, min_chance, min_mm, hysteresis),
+            target_time: Some(peak.time.clone()),
+            observed: vec![
+                format!("🌧️ Вероятность {}: до {}% ({})", precipitation_kind_genitive(kind), peak_chance, peak.clock_time()),
+                format!("💧 Осадки: {:.1} мм за {} ч", total_mm, hours.len()),
+            ],
+        }
+    }
+    
+    /// УФ-индекс оцениваем только по светлым часам
+    fn evaluate_uv(hours: &[&Hour], max: f32, hysteresis: bool) -> AlertEvaluation {
+        let margin = if hysteresis { UV_HYSTERESIS } else { 0.0 };
+        let Some(peak) = hours.iter()
+            .filter(|hour| hour.is_day == 1)
+            .max_by(|a, b| a.uv.total_cmp(&b.uv)) else {
+            return AlertEvaluation::default(); // В окне нет светлого времени суток
+        };
+        
+        let (category, advice) = uv_exposure_category(peak.uv);
+        AlertEvaluation {
+            triggered: peak.uv > max - margin,
+            target_time: Some(peak.time.clone()),
+            observed: vec![
+                format!("☀️ УФ-индекс до {} ({}) - {}", peak.uv, peak.clock_time(), category),
+                format!("🧴 {}", advice),
+            ],
+        }
+    }
+    
+    /// Качество воздуха: худший по индексу US EPA (или по PM2.5) час в окне
+    fn evaluate_air_quality(hours: &[&Hour], min_epa_index: u8, max_pm2_5: Option<f32>, hysteresis: bool) -> AlertEvaluation {
+        let Some((worst_hour, worst_aq)) = hours.iter()
+            .filter_map(|hour| hour.air_quality.as_ref().map(|aq| (hour, aq)))
+            .max_by(|(_, a), (_, b)| {
+                a.us_epa_index.cmp(&b.us_epa_index)
+                    .then(a.pm2_5.unwrap_or(0.0).total_cmp(&b.pm2_5.unwrap_or(0.0)))
+            }) else {
+            return AlertEvaluation::default(); // Провайдер не вернул данные о качестве воздуха
+        };
+        
+        AlertEvaluation {
+            triggered: air_quality_triggered(worst_aq, min_epa_index, max_pm2_5, hysteresis),
+            target_time: Some(worst_hour.time.clone()),
+            observed: describe_air_quality(worst_aq),
+        }
+    }
+    
+    /// Составное правило: срабатывает на первом часе окна, где выполняется все дерево условий.
+    /// Для уже сработавшего алерта каждое условие смягчается на гистерезис своего показателя
+    fn evaluate_composite(hours: &[&Hour], rule: &RuleExpr, hysteresis: bool) -> AlertEvaluation {
+        let mut observed = vec![format!("🧩 Правило: {}", rule.describe())];
+        let effective = if hysteresis { rule.relaxed(&rule_hysteresis) } else { rule.clone() };
+        match hours.iter().find(|hour| effective.evaluate(hour)) {
+            Some(hour) => {
+                observed.extend(effective.explain(hour));
+                AlertEvaluation {
+                    triggered: true,
+                    target_time: Some(hour.time.clone()),
+                    observed,
+                }
+            }
+            None => AlertEvaluation {
+                triggered: false,
+                target_time: None,
+                observed,
+            },
+        }
+    }
+    
+    /// Ощущаемая температура по часам: жару оцениваем по индексу жары, холод - по ветро-холодовому индексу
+    fn evaluate_feels_like(hours: &[&Hour], min: Option<f32>, max: Option<f32>, hysteresis: bool) -> AlertEvaluation {
+        let margin = if hysteresis { TEMPERATURE_HYSTERESIS_C } else { 0.0 };
+        let hottest = hours.iter().copied().max_by(|a, b| apparent_heat(a).total_cmp(&apparent_heat(b)));
+        let coldest = hours.iter().copied().min_by(|a, b| apparent_cold(a).total_cmp(&apparent_cold(b)));
+        
+        let heat_hour = hottest.filter(|hour| max.is_some_and(|max| apparent_heat(hour) > max - margin));
+        let cold_hour = coldest.filter(|hour| min.is_some_and(|min| apparent_cold(hour) < min + margin));
+        
+        let mut observed = Vec::new();
+        if let Some(hour) = hottest.filter(|_| max.is_some()) {
+            observed.push(describe_heat_hour(hour));
+        }
+        if let Some(hour) = coldest.filter(|_| min.is_some()) {
+            observed.push(describe_cold_hour(hour));
+        }
+        
+        let target = heat_hour.into_iter().chain(cold_hour).min_by_key(|hour| hour.time_epoch);
+        AlertEvaluation {
+            triggered: target.is_some(),
+            target_time: target.or(hottest.filter(|_| max.is_some())).or(coldest).map(|hour| hour.time.clone()),
+            observed,
+        }
+    }
+    
+    /// Ветер по часам: пиковая скорость (или порывы) среди часов с нужным направлением
+    fn evaluate_wind(hours: &[&Hour], max: f32, use_gusts: bool, direction: Option<WindSector>, hysteresis: bool) -> AlertEvaluation {
+        let margin = if hysteresis { WIND_HYSTERESIS_KPH } else { 0.0 };
+        let Some(peak) = peak_wind_hour(hours, use_gusts, direction) else {
+            return AlertEvaluation {
+                observed: direction
+                    .map(|sector| vec![format!("🧭 Ветра направления {} в прогнозе нет", sector.code())])
+                    .unwrap_or_default(),
+                ..AlertEvaluation::default()
+            };
+        };
+        
+        AlertEvaluation {
+            triggered: wind_value(peak, use_gusts) > max - margin,
+            target_time: Some(peak.time.clone()),
+            observed: describe_wind_hour(peak),
+        }
+    }
+    
+    /// Снегопад (сумма snow_cm за окно) и гололед (мокрая дорога при температуре около нуля)
+    fn evaluate_snow_ice(hours: &[&Hour], min_snow_cm: Option<f32>, ice_risk: bool, hysteresis: bool) -> AlertEvaluation {
+        if hours.is_empty() {
+            return AlertEvaluation::default(); // Недостаточно данных прогноза
+        }
+        let snow_margin = if hysteresis { SNOW_HYSTERESIS_CM } else { 0.0 };
+        
+        let snow_hours: Vec<&Hour> = hours.iter().copied().filter(|hour| hour.snow_cm > 0.0).collect();
+        let total_snow: f32 = snow_hours.iter().map(|hour| hour.snow_cm).sum();
+        let snow_triggered = total_snow > 0.0 && min_snow_cm.is_some_and(|min| total_snow >= min - snow_margin);
+        
+        let icy_hours = if ice_risk { icy_hours(hours, hysteresis) } else { Vec::new() };
+        
+        let mut observed = Vec::new();
+        if min_snow_cm.is_some() {
+            match (snow_hours.first(), snow_hours.last()) {
+                (Some(first), Some(last)) => observed.push(format!("❄️ Снег {:.1} см: {}", total_snow, describe_time_window(first, last))),
+                _ => observed.push("❄️ Снегопад не ожидается".to_string()),
+            }
+        }
+        if ice_risk {
+            match (icy_hours.first(), icy_hours.last()) {
+                (Some(first), Some(last)) => {
+                    let min_temp = icy_hours.iter().map(|hour| hour.temperature).fold(f32::MAX, f32::min);
+                    let max_temp = icy_hours.iter().map(|hour| hour.temperature).fold(f32::MIN, f32::max);
+                    observed.push(format!("🧊 Риск гололеда: {} ({}°C .. {}°C)", describe_time_window(first, last), min_temp, max_temp));
+                }
+                _ => observed.push("🧊 Гололед не ожидается".to_string()),
+            }
+        }
+        
+        // Время срабатывания - начало самого раннего опасного периода
+        let target = [
+            snow_hours.first().filter(|_| snow_triggered),
+            icy_hours.first(),
+        ].into_iter().flatten().min_by_key(|hour| hour.time_epoch);
+        
+        AlertEvaluation {
+            triggered: snow_triggered || !icy_hours.is_empty(),
+            target_time: target.map(|hour| hour.time.clone()),
+            observed,
+        }
+    }
+    
+    /// Хорошая погода: самое длинное окно приятных часов подряд. Для уже сработавшего алерта
+    /// диапазон температуры и порог ветра расширяются, чтобы окно не пропадало из-за долей градуса
+    fn evaluate_good_weather(hours: &[&Hour], min_temp: f32, max_temp: f32, max_wind: f32, min_hours: u8, hysteresis: bool) -> AlertEvaluation {
+        let (temp_margin, wind_margin) = if hysteresis {
+            (TEMPERATURE_HYSTERESIS_C, WIND_HYSTERESIS_KPH)
+        } else {
+            (0.0, 0.0)
+        };
+        let window = longest_good_window(hours, |hour| {
+            is_pleasant_hour(hour, min_temp - temp_margin, max_temp + temp_margin, max_wind + wind_margin)
+        });
+        
+        AlertEvaluation {
+            triggered: !window.is_empty() && window.len() >= min_hours as usize,
+            target_time: window.first().map(|hour| hour.time.clone()),
+            observed: describe_good_weather_window(window, min_hours),
+        }
+    }
+    
+    /// Заморозки: самый холодный ночной час в окне (по местному времени города)
+    fn evaluate_frost(hours: &[&Hour], max_temp: f32, hysteresis: bool) -> AlertEvaluation {
+        let night_hours: Vec<&Hour> = hours.iter().copied().filter(|hour| is_night_hour(hour)).collect();
+        let Some(coldest) = coldest_hour(&night_hours) else {
+            return AlertEvaluation::default(); // В окне нет ночных часов
+        };
+        
+        AlertEvaluation {
+            triggered: night_hours.iter().any(|hour| frost_likely(hour, max_temp, hysteresis)),
+            target_time: Some(coldest.time.clone()),
+            observed: describe_frost(coldest),
+        }
+    }
+    
+    /// Быстрое падение давления: наибольшая разница между часами, разнесенными не больше чем на window_hours
+    fn evaluate_pressure_drop(hours: &[&Hour], drop_hpa: f32, window_hours: u8, hysteresis: bool) -> AlertEvaluation {
+        let margin = if hysteresis { PRESSURE_HYSTERESIS_HPA } else { 0.0 };
+        let Some((from, to, drop)) = max_pressure_drop(hours, window_hours) else {
+            return AlertEvaluation::default(); // Нет данных о давлении
+        };
+        
+        AlertEvaluation {
+            triggered: drop > drop_hpa - margin,
+            target_time: Some(to.time.clone()),
+            observed: describe_pressure_drop(from, to, drop),
+        }
+    }
+    
+    /// Перепад температуры между часами, разнесенными не больше чем на window_hours
+    fn evaluate_hourly_swing(hours: &[&Hour], delta: f32, direction: SwingDirection, window_hours: u8, hysteresis: bool) -> AlertEvaluation {
+        let margin = if hysteresis { TEMPERATURE_HYSTERESIS_C } else { 0.0 };
+        let Some((from, to, change)) = max_temperature_swing(hours, window_hours, direction) else {
+            return AlertEvaluation::default(); // Недостаточно данных прогноза
+        };
+        
+        AlertEvaluation {
+            triggered: direction.magnitude(change) >= delta - margin,
+            target_time: Some(to.time.clone()),
+            observed: describe_temperature_swing(from, to, change),
+        }
+    }
+    
+    /// Перепад между соседними днями: завтра против сегодня, послезавтра против завтра.
+    /// Сегодняшний день берем из прогноза - в нем уже учтены прошедшие часы
+    fn evaluate_day_swing(days: &[ForecastDay], hours_ahead: u8, delta: f32, direction: SwingDirection, hysteresis: bool) -> AlertEvaluation {
+        let margin = if hysteresis { TEMPERATURE_HYSTERESIS_C } else { 0.0 };
+        let pairs = (hours_ahead as usize).div_ceil(24);
+        let Some(swing) = days.windows(2)
+            .take(pairs)
+            .map(|pair| day_swing(&pair[0], &pair[1], direction))
+            .max_by(|a, b| direction.magnitude(a.change).total_cmp(&direction.magnitude(b.change))) else {
+            return AlertEvaluation::default(); // В прогнозе меньше двух дней
+        };
+        
+        AlertEvaluation {
+            triggered: direction.magnitude(swing.change) >= delta - margin,
+            target_time: Some(swing.to.date.clone()),
+            observed: describe_day_swing(&swing),
+        }
+    }
+    
+    /// Значения дневного прогноза, которые проверяет данный тип алерта
+    fn describe_forecast_day(forecast_day: &ForecastDay, alert_type: &AlertType) -> Vec<String> {
+        let day = &forecast_day.day;
+        match alert_type {
+            AlertType::StandardWeatherAlert { categories } => {
+                let mut lines = vec![
+                    format!("☁️ {}", day.condition.text),
+                    format!("🌡️ {}°C .. {}°C", day.min_temp, day.max_temp),
+                    format!("💨 до {} км/ч", day.max_wind),
+                ];
+                let detected: Vec<&str> = forecast_day_severe_categories(forecast_day, 0.0, 0.0).into_iter()
+                    .filter(|category| categories.contains(category))
+                    .map(severe_category_ru)
+                    .collect();
+                if !detected.is_empty() {
+                    lines.push(format!("⚠️ {}", detected.join(", ")));
                 }
-                AlertType::TemperatureThreshold { min, max } => {
-                    let min_temp = forecast_day.day.min_temp;
-                    let max_temp = forecast_day.day.max_temp;
-                    
-                    let triggered = if let Some(min_threshold) = min {
-                        min_temp < *min_threshold
-                    } else {
-                        false
-                    } || if let Some(max_threshold) = max {
-                        max_temp > *max_threshold
-                    } else {
-                        false
-                    };
-                    Ok(triggered)
+                lines
+            }
+            AlertType::TemperatureThreshold { .. } => vec![
+                format!("🌡️ {}°C .. {}°C", day.min_temp, day.max_temp),
+            ],
+            AlertType::WindSpeed { use_gusts, direction, .. } if *use_gusts || direction.is_some() => {
+                let hours: Vec<&Hour> = forecast_day.hour.iter().collect();
+                peak_wind_hour(&hours, *use_gusts, *direction)
+                    .map(describe_wind_hour)
+                    .unwrap_or_default()
+            }
+            AlertType::WindSpeed { .. } => vec![
+                format!("💨 до {} км/ч", day.max_wind),
+            ],
+            AlertType::Humidity { .. } => vec![
+                format!("💧 в среднем {}%", day.avg_humidity),
+            ],
+            AlertType::Precipitation { .. } => vec![
+                format!("🌧️ Вероятность дождя {}%, снега {}%", day.daily_chance_of_rain, day.daily_chance_of_snow),
+                format!("💧 Осадки: {} мм", day.total_precip),
+            ],
+            AlertType::UvIndex { .. } => vec![
+                format!("☀️ УФ-индекс {} - {}", day.uv, uv_exposure_category(day.uv).0),
+            ],
+            AlertType::AirQuality { .. } => day.air_quality.as_ref()
+                .map(describe_air_quality)
+                .unwrap_or_default(),
+            AlertType::Composite { rule } => {
+                let hours: Vec<&Hour> = forecast_day.hour.iter().collect();
+                Self::evaluate_composite(&hours, rule, false).observed
+            }
+            AlertType::FeelsLike { min, max } => {
+                let hours: Vec<&Hour> = forecast_day.hour.iter().collect();
+                if hours.is_empty() {
+                    vec![format!("🌡️ {}°C .. {}°C", day.min_temp, day.max_temp)]
+                } else {
+                    Self::evaluate_feels_like(&hours, *min, *max, false).observed
                 }
-                AlertType::WindSpeed { max } => {
-                    Ok(forecast_day.day.max_wind > *max)
+            }
+            AlertType::SnowIce { ice_risk, .. } => {
+                let mut lines = vec![format!("❄️ Снег за день: {} см", day.total_snow)];
+                if *ice_risk {
+                    let hours: Vec<&Hour> = forecast_day.hour.iter().collect();
+                    let icy = icy_hours(&hours, false);
+                    if let (Some(first), Some(last)) = (icy.first(), icy.last()) {
+                        lines.push(format!("🧊 Риск гололеда: {}", describe_time_window(first, last)));
+                    }
                 }
-                AlertType::Humidity { min, max } => {
-                    let avg_humidity = forecast_day.day.avg_humidity as u32;
-                    let triggered = if let Some(min_hum) = min {
-                        avg_humidity < *min_hum
-                    } else {
-                        false
-                    } || if let Some(max_hum) = max {
-                        avg_humidity > *max_hum
-                    } else {
-                        false
-                    };
-                    Ok(triggered)
+                lines
+            }
+            AlertType::Frost { .. } => {
+                let night_hours: Vec<&Hour> = forecast_day.hour.iter().filter(|hour| is_night_hour(hour)).collect();
+                match coldest_hour(&night_hours) {
+                    Some(coldest) => describe_frost(coldest),
+                    None => vec![format!("🌡️ Минимум {}°C", day.min_temp)],
                 }
             }
+            AlertType::PressureDrop { window_hours, .. } => {
+                let hours: Vec<&Hour> = forecast_day.hour.iter().collect();
+                max_pressure_drop(&hours, *window_hours)
+                    .map(|(from, to, drop)| describe_pressure_drop(from, to, drop))
+                    .unwrap_or_default()
+            }
+            AlertType::TemperatureSwing { direction, window_hours: Some(window_hours), .. } => {
+                let hours: Vec<&Hour> = forecast_day.hour.iter().collect();
+                max_temperature_swing(&hours, *window_hours, *direction)
+                    .map(|(from, to, change)| describe_temperature_swing(from, to, change))
+                    .unwrap_or_default()
+            }
+            AlertType::TemperatureSwing { window_hours: None, .. } => vec![
+                format!("🌡️ {}°C .. {}°C", day.min_temp, day.max_temp),
+            ],
+            AlertType::GoodWeather { min_temp, max_temp, max_wind, min_hours } => {
+                let hours: Vec<&Hour> = forecast_day.hour.iter().collect();
+                Self::evaluate_good_weather(&hours, *min_temp, *max_temp, *max_wind, *min_hours, false).observed
+            }
+        }
+    }
+    
+    /// Проверяет условия алерта по дневным показателям одного дня прогноза
+    pub fn check_forecast_day(forecast_day: &ForecastDay, alert_type: &AlertType) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        Self::evaluate_forecast_day(forecast_day, alert_type, false)
+    }
+    
+    fn evaluate_forecast_day(forecast_day: &ForecastDay, alert_type: &AlertType, hysteresis: bool) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let (temp_margin, wind_margin, humidity_margin) = if hysteresis {
+            (TEMPERATURE_HYSTERESIS_C, WIND_HYSTERESIS_KPH, HUMIDITY_HYSTERESIS_PCT)
         } else {
-            Ok(false) // Недостаточно данных прогноза
+            (0.0, 0.0, 0)
+        };
+        
+        match alert_type {
+            AlertType::StandardWeatherAlert { categories } => {
+                Self::check_forecast_standard_conditions(forecast_day, categories, temp_margin, wind_margin)
+            }
+            AlertType::TemperatureThreshold { min, max } => {
+                let min_temp = forecast_day.day.min_temp;
+                let max_temp = forecast_day.day.max_temp;
+                
+                let triggered = if let Some(min_threshold) = min {
+                    min_temp < *min_threshold + temp_margin
+                } else {
+                    false
+                } || if let Some(max_threshold) = max {
+                    max_temp > *max_threshold - temp_margin
+                } else {
+                    false
+                };
+                Ok(triggered)
+            }
+            AlertType::WindSpeed { max, use_gusts, direction } => {
+                if *use_gusts || direction.is_some() {
+                    let hours: Vec<&Hour> = forecast_day.hour.iter().collect();
+                    return Ok(peak_wind_hour(&hours, *use_gusts, *direction)
+                        .is_some_and(|peak| wind_value(peak, *use_gusts) > *max - wind_margin));
+                }
+                Ok(forecast_day.day.max_wind > *max - wind_margin)
+            }
+            AlertType::Humidity { min, max } => {
+                let avg_humidity = forecast_day.day.avg_humidity as u32;
+                let triggered = if let Some(min_hum) = min {
+                    avg_humidity < *min_hum + humidity_margin
+                } else {
+                    false
+                } || if let Some(max_hum) = max {
+                    avg_humidity > max_hum.saturating_sub(humidity_margin)
+                } else {
+                    false
+                };
+                Ok(triggered)
+            }
+            AlertType::Precipitation { min_chance, min_mm, kind } => {
+                let day = &forecast_day.day;
+                let chance = precipitation_chance(day.daily_chance_of_rain, day.daily_chance_of_snow, *kind);
+                Ok(precipitation_triggered(chance, day.total_precip, *min_chance, *min_mm, hysteresis))
+            }
+            AlertType::UvIndex { max } => {
+                let margin = if hysteresis { UV_HYSTERESIS } else { 0.0 };
+                Ok(forecast_day.day.uv > *max - margin)
+            }
+            AlertType::AirQuality { min_epa_index, max_pm2_5 } => {
+                Ok(forecast_day.day.air_quality.as_ref()
+                    .is_some_and(|aq| air_quality_triggered(aq, *min_epa_index, *max_pm2_5, hysteresis)))
+            }
+            AlertType::Composite { rule } => {
+                let hours: Vec<&Hour> = forecast_day.hour.iter().collect();
+                Ok(Self::evaluate_composite(&hours, rule, hysteresis).triggered)
+            }
+            AlertType::FeelsLike { min, max } => {
+                let hours: Vec<&Hour> = forecast_day.hour.iter().collect();
+                if hours.is_empty() {
+                    // Без почасовых данных ориентируемся на дневные экстремумы температуры
+                    let heat = max.is_some_and(|max| forecast_day.day.max_temp > max - temp_margin);
+                    let cold = min.is_some_and(|min| forecast_day.day.min_temp < min + temp_margin);
+                    return Ok(heat || cold);
+                }
+                Ok(Self::evaluate_feels_like(&hours, *min, *max, hysteresis).triggered)
+            }
+            AlertType::SnowIce { min_snow_cm, ice_risk } => {
+                let snow_margin = if hysteresis { SNOW_HYSTERESIS_CM } else { 0.0 };
+                let total_snow = forecast_day.day.total_snow;
+                let snow_triggered = total_snow > 0.0 && min_snow_cm.is_some_and(|min| total_snow >= min - snow_margin);
+                let hours: Vec<&Hour> = forecast_day.hour.iter().collect();
+                Ok(snow_triggered || (*ice_risk && !icy_hours(&hours, hysteresis).is_empty()))
+            }
+            AlertType::Frost { max_temp } => {
+                if forecast_day.hour.is_empty() {
+                    // Без почасовых данных ориентируемся на дневной минимум
+                    let margin = if hysteresis { FROST_HYSTERESIS_C } else { 0.0 };
+                    return Ok(forecast_day.day.min_temp <= *max_temp + margin);
+                }
+                Ok(forecast_day.hour.iter()
+                    .filter(|hour| is_night_hour(hour))
+                    .any(|hour| frost_likely(hour, *max_temp, hysteresis)))
+            }
+            AlertType::PressureDrop { drop_hpa, window_hours } => {
+                // В дневных данных давления нет - смотрим почасовой ряд этого дня
+                let margin = if hysteresis { PRESSURE_HYSTERESIS_HPA } else { 0.0 };
+                let hours: Vec<&Hour> = forecast_day.hour.iter().collect();
+                Ok(max_pressure_drop(&hours, *window_hours).is_some_and(|(_, _, drop)| drop > *drop_hpa - margin))
+            }
+            AlertType::TemperatureSwing { delta, direction, window_hours: Some(window_hours) } => {
+                let hours: Vec<&Hour> = forecast_day.hour.iter().collect();
+                Ok(Self::evaluate_hourly_swing(&hours, *delta, *direction, *window_hours, hysteresis).triggered)
+            }
+            AlertType::TemperatureSwing { window_hours: None, .. } => {
+                // Для сравнения день к дню одного дня прогноза недостаточно
+                Ok(false)
+            }
+            AlertType::GoodWeather { min_temp, max_temp, max_wind, min_hours } => {
+                let hours: Vec<&Hour> = forecast_day.hour.iter().collect();
+                Ok(Self::evaluate_good_weather(&hours, *min_temp, *max_temp, *max_wind, *min_hours, hysteresis).triggered)
+            }
         }
     }
     
@@ -72,9 +686,9 @@ impl AlertChecker {
         let weather = get_current_weather(&alert.city).await?;
         
         match &alert.alert_type {
-            AlertType::StandardWeatherAlert => {
+            AlertType::StandardWeatherAlert { categories } => {
                 // Проверяем на наличие экстремальных погодных условий
-                Self::check_standard_weather_conditions(&weather)
+                Self::check_standard_weather_conditions(&weather, categories)
             }
             AlertType::TemperatureThreshold { min, max } => {
                 let temp = weather.current.temperature;
@@ -89,8 +703,16 @@ impl AlertChecker {
                 };
                 Ok(triggered)
             }
-            AlertType::WindSpeed { max } => {
-                Ok(weather.current.wind_speed > *max)
+            AlertType::WindSpeed { max, use_gusts, direction } => {
+                let current = &weather.current;
+                let sector = current.wind_degree
+                    .map(|degree| WindSector::from_degree(degree as f32))
+                    .or_else(|| WindSector::from_compass(&current.wind_direction));
+                if direction.is_some() && sector != *direction {
+                    return Ok(false);
+                }
+                let speed = if *use_gusts { current.gust_speed.max(current.wind_speed) } else { current.wind_speed };
+                Ok(speed > *max)
             }
             AlertType::Humidity { min, max } => {
                 let humidity = weather.current.humidity;
@@ -105,68 +727,97 @@ impl AlertChecker {
                 };
                 Ok(triggered)
             }
+            AlertType::Precipitation { .. } => {
+                // Для осадков текущих значений недостаточно - смотрим ближайший прогноз
+                Self::check_forecast_alert(alert).await
+            }
+            AlertType::UvIndex { max } => {
+                Ok(weather.current.uv > *max)
+            }
+            AlertType::AirQuality { min_epa_index, max_pm2_5 } => {
+                Ok(weather.current.air_quality.as_ref()
+                    .is_some_and(|aq| air_quality_triggered(aq, *min_epa_index, *max_pm2_5, false)))
+            }
+            AlertType::FeelsLike { min, max } => {
+                let feels_like = weather.current.feels_like;
+                Ok(min.is_some_and(|min| feels_like < min) || max.is_some_and(|max| feels_like > max))
+            }
+            AlertType::Frost { .. } | AlertType::SnowIce { .. } | AlertType::Composite { .. } => {
+                // Эти алерты зависят от почасового хода погоды - смотрим прогноз
+                Self::check_forecast_alert(alert).await
+            }
+            AlertType::PressureDrop { .. } | AlertType::TemperatureSwing { .. } => {
+                // По одному текущему значению тенденцию не определить - смотрим почасовой прогноз
+                Self::check_forecast_alert(alert).await
+            }
+            AlertType::GoodWeather { .. } => {
+                // Нужно окно в несколько часов подряд - смотрим почасовой прогноз
+                Self::check_forecast_alert(alert).await
+            }
         }
     }
     
-    fn check_forecast_standard_conditions(forecast_day: &crate::weather_api::ForecastDay) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-        let condition_text = forecast_day.day.condition.text.to_lowercase();
-        let min_temp = forecast_day.day.min_temp;
-        let max_temp = forecast_day.day.max_temp;
-        let max_wind = forecast_day.day.max_wind;
-        
-        // Список ключевых слов для экстремальных погодных условий
-        let extreme_conditions = [
-            "thunderstorm", "storm", "tornado", "hurricane", "cyclone",
-            "blizzard", "hail", "snow", "ice", "freeze", "freezing",
-            "extreme", "severe", "heavy", "violent", "dangerous"
-        ];
-        
-        // Проверяем условия
-        let extreme_weather = extreme_conditions.iter()
-            .any(|&condition| condition_text.contains(condition));
-        
-        let extreme_temperature = max_temp > 40.0 || min_temp < -20.0;
-        let extreme_wind = max_wind > 50.0;
-        
-        Ok(extreme_weather || extreme_temperature || extreme_wind)
+    fn check_forecast_standard_conditions(forecast_day: &ForecastDay, categories: &[SevereCategory], temp_margin: f32, wind_margin: f32) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let detected = forecast_day_severe_categories(forecast_day, temp_margin, wind_margin);
+        Ok(detected.iter().any(|category| categories.contains(category)))
     }
     
-    fn check_standard_weather_conditions(weather: &CurrentWeather) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-        let condition_text = weather.current.condition.text.to_lowercase();
-        let temp = weather.current.temperature;
-        let wind_speed = weather.current.wind_speed;
-        
-        // Список ключевых слов для экстремальных погодных условий
-        let extreme_conditions = [
-            "thunderstorm", "storm", "tornado", "hurricane", "cyclone",
-            "blizzard", "hail", "snow", "ice", "freeze", "freezing",
-            "extreme", "severe", "heavy", "violent", "dangerous"
-        ];
-        
-        // Проверяем условия
-        let extreme_weather = extreme_conditions.iter()
-            .any(|&condition| condition_text.contains(condition));
-        
-        let extreme_temperature = temp > 40.0 || temp < -20.0;
-        let extreme_wind = wind_speed > 50.0;
-        
-        Ok(extreme_weather || extreme_temperature || extreme_wind)
+    fn check_standard_weather_conditions(weather: &CurrentWeather, categories: &[SevereCategory]) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let current = &weather.current;
+        let detected = severe_categories(
+            [current.condition.code],
+            current.temperature,
+            current.temperature,
+            current.wind_speed,
+            0.0,
+            0.0,
+        );
+        Ok(detected.iter().any(|category| categories.contains(category)))
     }
     
-    pub fn format_alert_message(alert: &WeatherAlert, weather: &CurrentWeather) -> String {
-        let alert_type_str = match &alert.alert_type {
-            AlertType::StandardWeatherAlert => "🚨 Экстремальные погодные условия",
+    pub fn alert_type_title(alert_type: &AlertType) -> &'static str {
+        match alert_type {
+            AlertType::StandardWeatherAlert { .. } => "🚨 Экстремальные погодные условия",
             AlertType::TemperatureThreshold { .. } => "🌡️ Превышение температурного порога",
             AlertType::WindSpeed { .. } => "💨 Сильный ветер",
             AlertType::Humidity { .. } => "💧 Критический уровень влажности",
+            AlertType::Precipitation { .. } => "🌧️ Ожидаются осадки",
+            AlertType::UvIndex { .. } => "☀️ Высокий УФ-индекс",
+            AlertType::AirQuality { .. } => "🫁 Плохое качество воздуха",
+            AlertType::PressureDrop { .. } => "📉 Резкое падение давления",
+            AlertType::Frost { .. } => "🥶 Заморозки на почве",
+            AlertType::SnowIce { .. } => "🧊 Снегопад и гололед",
+            AlertType::FeelsLike { .. } => "🥵 Опасная ощущаемая температура",
+            AlertType::Composite { .. } => "🧩 Сработало составное правило",
+            AlertType::TemperatureSwing { .. } => "🎢 Резкий перепад температуры",
+            AlertType::GoodWeather { .. } => "🌤️ Ожидается хорошая погода",
+        }
+    }
+    
+    pub fn format_alert_message(alert: &WeatherAlert, weather: &CurrentWeather, evaluation: &AlertEvaluation) -> String {
+        let alert_type_str = Self::alert_type_title(&alert.alert_type);
+        
+        let forecast_details = if evaluation.observed.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "📈 Прогноз{}:\n{}\n\n",
+                evaluation.target_time.as_ref().map(|t| format!(" на {}", t)).unwrap_or_default(),
+                evaluation.observed.iter().map(|line| format!("• {}", line)).collect::<Vec<_>>().join("\n")
+            )
         };
         
+        // Алерты-возможности сообщают о хорошей погоде, а не предупреждают
+        let header = if alert.alert_type.is_opportunity() { "🌤️ GOOD WEATHER AHEAD 🌤️" } else { "⚠️ WEATHER ALERT ⚠️" };
+        
         format!(
-            "⚠️ WEATHER ALERT ⚠️\n\n{}\n\n🏠 Город: {}\n📝 Описание: {}\n⏰ Предупреждение за: {} часов\n\n🌡️ Текущая температура: {}°C\n☁️ Условия: {}\n💨 Ветер: {} км/ч\n💧 Влажность: {}%\n\n🕐 Время срабатывания: {}",
+            "{}\n\n{}\n\n🏠 Город: {}\n📝 Описание: {}\n⏰ Предупреждение за: {} часов\n\n{}🌡️ Текущая температура: {}°C\n☁️ Условия: {}\n💨 Ветер: {} км/ч\n💧 Влажность: {}%\n\n🕐 Время срабатывания: {}",
+            header,
             alert_type_str,
             weather.location.name,
             alert.description,
             alert.hours_ahead,
+            forecast_details,
             weather.current.temperature,
             weather.current.condition.text,
             weather.current.wind_speed,
@@ -174,18 +825,555 @@ impl AlertChecker {
             chrono::Utc::now().format("%Y-%m-%d %H:%M UTC")
         )
     }
+    
+    /// Напоминание о критичном алерте, уведомление о котором не подтвердили
+    pub fn format_escalation_message(alert: &WeatherAlert) -> String {
+        format!(
+            "🔔🔔 НАПОМИНАНИЕ 🔔🔔\n\n{}\n\n🏠 Город: {}\n📝 Описание: {}\n\n❗ Предупреждение{} еще не подтверждено. Нажмите «✅ Got it», если вы его видели.\n\n🕐 Время: {}",
+            Self::alert_type_title(&alert.alert_type),
+            alert.city,
+            alert.description,
+            alert.last_triggered.map(|t| format!(" от {}", t.format("%H:%M UTC"))).unwrap_or_default(),
+            chrono::Utc::now().format("%Y-%m-%d %H:%M UTC")
+        )
+    }
+    
+    /// Сообщение об отбое: условие алерта больше не ожидается
+    pub fn format_all_clear_message(alert: &WeatherAlert, location_name: &str) -> String {
+        let summary = if alert.alert_type.is_opportunity() {
+            "Окно хорошей погоды больше не ожидается"
+        } else {
+            "Условия алерта больше не ожидаются"
+        };
+        format!(
+            "✅ ОТБОЙ ✅\n\n{}\n\n🏠 Город: {}\n📝 Описание: {}\n\n{} в ближайшие {} часов.\n\n🕐 Время: {}",
+            Self::alert_type_title(&alert.alert_type),
+            location_name,
+            alert.description,
+            summary,
+            alert.hours_ahead,
+            chrono::Utc::now().format("%Y-%m-%d %H:%M UTC")
+        )
+    }
+}
+
+fn precipitation_chance(chance_of_rain: u32, chance_of_snow: u32, kind: PrecipitationKind) -> u32 {
+    match kind {
+        PrecipitationKind::Any => chance_of_rain.max(chance_of_snow),
+        PrecipitationKind::Rain => chance_of_rain,
+        PrecipitationKind::Snow => chance_of_snow,
+    }
+}
+
+/// Срабатывают все заданные пороги; если порогов нет - вероятность от DEFAULT_PRECIP_CHANCE_PCT
+fn precipitation_triggered(chance: u32, total_mm: f32, min_chance: Option<u32>, min_mm: Option<f32>, hysteresis: bool) -> bool {
+    let (chance_margin, mm_margin) = if hysteresis {
+        (PRECIP_CHANCE_HYSTERESIS_PCT, PRECIP_MM_HYSTERESIS)
+    } else {
+        (0, 0.0)
+    };
+    let min_chance = match (min_chance, min_mm) {
+        (None, None) => Some(DEFAULT_PRECIP_CHANCE_PCT),
+        _ => min_chance,
+    };
+    
+    let chance_ok = min_chance.is_none_or(|threshold| chance >= threshold.saturating_sub(chance_margin));
+    let mm_ok = min_mm.is_none_or(|threshold| total_mm >= (threshold - mm_margin).max(0.0));
+    chance_ok && mm_ok
+}
+
+/// Категория УФ-облучения по шкале ВОЗ и рекомендация по защите
+pub fn uv_exposure_category(uv: f32) -> (&'static str, &'static str) {
+    if uv < 3.0 {
+        ("низкий", "Защита не требуется")
+    } else if uv < 6.0 {
+        ("умеренный", "В полдень держитесь в тени, используйте головной убор и солнцезащитный крем")
+    } else if uv < 8.0 {
+        ("высокий", "Нужна защита: крем SPF 30+, головной убор, солнцезащитные очки, тень в полдень")
+    } else if uv < 11.0 {
+        ("очень высокий", "Избегайте солнца с 11 до 16 часов, обязательны крем SPF 30+, закрытая одежда и очки")
+    } else {
+        ("экстремальный", "По возможности оставайтесь в помещении - незащищенная кожа обгорает за минуты")
+    }
+}
+
+/// Ограничение длины текста бюллетеня, чтобы сообщение влезло в лимит Telegram
+const WARNING_TEXT_MAX_CHARS: usize = 1500;
+
+/// Сообщение о новом официальном предупреждении метеослужбы
+pub fn format_official_warning_message(warning: &WeatherWarning, location_name: &str) -> String {
+    let truncate = |text: &str| {
+        let text = text.trim();
+        if text.chars().count() > WARNING_TEXT_MAX_CHARS {
+            format!("{}…", text.chars().take(WARNING_TEXT_MAX_CHARS).collect::<String>())
+        } else {
+            text.to_string()
+        }
+    };
+    let optional = |label: &str, value: &str| {
+        if value.trim().is_empty() { String::new() } else { format!("{}: {}\n", label, value.trim()) }
+    };
+    
+    let mut message = format!("🚨 ОФИЦИАЛЬНОЕ ПРЕДУПРЕЖДЕНИЕ 🚨\n\n{}\n\n🏠 Город: {}\n", warning.title(), location_name);
+    message.push_str(&optional("⚠️ Событие", &warning.event));
+    message.push_str(&optional("📊 Уровень опасности", &warning.severity));
+    message.push_str(&optional("⏱️ Срочность", &warning.urgency));
+    message.push_str(&optional("🗺️ Районы", &warning.areas));
+    message.push_str(&optional("▶️ Действует с", &warning.effective));
+    message.push_str(&optional("⏹️ Действует до", &warning.expires));
+    if !warning.desc.trim().is_empty() {
+        message.push_str(&format!("\n📝 {}\n", truncate(&warning.desc)));
+    }
+    if !warning.instruction.trim().is_empty() {
+        message.push_str(&format!("\n👉 {}\n", truncate(&warning.instruction)));
+    }
+    message
+}
+
+/// Гистерезис условия составного правила - тот же, что у обычного алерта на этот показатель
+fn rule_hysteresis(metric: Metric) -> f32 {
+    match metric {
+        Metric::Temperature | Metric::FeelsLike | Metric::DewPoint => TEMPERATURE_HYSTERESIS_C,
+        Metric::WindSpeed | Metric::WindGust => WIND_HYSTERESIS_KPH,
+        Metric::Humidity => HUMIDITY_HYSTERESIS_PCT as f32,
+        Metric::PrecipMm => PRECIP_MM_HYSTERESIS,
+        Metric::ChanceOfRain | Metric::ChanceOfSnow => PRECIP_CHANCE_HYSTERESIS_PCT as f32,
+        Metric::SnowCm => SNOW_HYSTERESIS_CM,
+        Metric::Uv => UV_HYSTERESIS,
+        Metric::Pressure => PRESSURE_HYSTERESIS_HPA,
+        // Час суток - не измерение, смягчать нечего
+        Metric::LocalHour => 0.0,
+    }
+}
+
+/// Ощущаемая жара: наибольшее из "ощущается как" и индекса жары
+fn apparent_heat(hour: &Hour) -> f32 {
+    let feels_like = hour.feels_like.unwrap_or(hour.temperature);
+    hour.heat_index.map_or(feels_like, |heat_index| heat_index.max(feels_like))
+}
+
+/// Ощущаемый холод: наименьшее из "ощущается как" и ветро-холодового индекса
+fn apparent_cold(hour: &Hour) -> f32 {
+    let feels_like = hour.feels_like.unwrap_or(hour.temperature);
+    hour.wind_chill.map_or(feels_like, |wind_chill| wind_chill.min(feels_like))
+}
+
+/// Категория риска по шкале индекса жары NWS
+fn heat_risk_category(heat: f32) -> &'static str {
+    if heat >= 54.0 {
+        "крайняя опасность, высокий риск теплового удара"
+    } else if heat >= 41.0 {
+        "опасно: вероятны судороги и тепловое истощение"
+    } else if heat >= 32.0 {
+        "повышенная осторожность"
+    } else if heat >= 27.0 {
+        "осторожно: возможна усталость при нагрузке"
+    } else {
+        "без риска перегрева"
+    }
+}
+
+/// Категория риска по шкале ветро-холодового индекса Environment Canada
+fn cold_risk_category(cold: f32) -> &'static str {
+    if cold <= -48.0 {
+        "крайняя опасность: обморожение за 2-5 минут"
+    } else if cold <= -40.0 {
+        "очень высокий риск: обморожение за 5-10 минут"
+    } else if cold <= -28.0 {
+        "высокий риск: обморожение за 10-30 минут"
+    } else if cold <= -10.0 {
+        "умеренный риск переохлаждения"
+    } else {
+        "низкий риск"
+    }
+}
+
+fn describe_heat_hour(hour: &Hour) -> String {
+    let heat = apparent_heat(hour);
+    format!("🥵 Ощущается как {:.1}°C ({}, воздух {}°C) - {}", heat, hour.clock_time(), hour.temperature, heat_risk_category(heat))
+}
+
+fn describe_cold_hour(hour: &Hour) -> String {
+    let cold = apparent_cold(hour);
+    format!("🥶 Ощущается как {:.1}°C ({}, воздух {}°C) - {}", cold, hour.clock_time(), hour.temperature, cold_risk_category(cold))
+}
+
+/// Направление ветра в часе прогноза: по градусам, а если их нет - по румбу
+fn hour_wind_sector(hour: &Hour) -> Option<WindSector> {
+    hour.wind_degree
+        .map(|degree| WindSector::from_degree(degree as f32))
+        .or_else(|| WindSector::from_compass(&hour.wind_direction))
+}
+
+/// Скорость, с которой сравнивается порог: порывы (не меньше среднего ветра) или средний ветер
+fn wind_value(hour: &Hour, use_gusts: bool) -> f32 {
+    if use_gusts { hour.gust_speed.max(hour.wind_speed) } else { hour.wind_speed }
+}
+
+/// Час с самым сильным ветром (с учетом фильтра направления)
+fn peak_wind_hour<'a>(hours: &[&'a Hour], use_gusts: bool, direction: Option<WindSector>) -> Option<&'a Hour> {
+    hours.iter().copied()
+        .filter(|hour| direction.is_none() || hour_wind_sector(hour) == direction)
+        .max_by(|a, b| wind_value(a, use_gusts).total_cmp(&wind_value(b, use_gusts)))
+}
+
+fn describe_wind_hour(hour: &Hour) -> Vec<String> {
+    let direction = match hour_wind_sector(hour) {
+        Some(sector) => format!(", {} ({}°)", hour.wind_direction, hour.wind_degree.map(|d| d.to_string()).unwrap_or_else(|| sector.code().to_string())),
+        None => String::new(),
+    };
+    vec![
+        format!("💨 Ветер {} км/ч{} ({})", hour.wind_speed, direction, hour.clock_time()),
+        format!("🌬️ Порывы до {} км/ч", hour.gust_speed),
+    ]
+}
+
+/// Часы с риском гололеда: температура около нуля, а дорога мокрая -
+/// осадки идут сейчас или шли в предыдущие ICE_WET_LOOKBACK_HOURS часов
+fn icy_hours<'a>(hours: &[&'a Hour], hysteresis: bool) -> Vec<&'a Hour> {
+    let margin = if hysteresis { TEMPERATURE_HYSTERESIS_C } else { 0.0 };
+    let near_zero = (ICE_TEMP_MIN_C - margin)..=(ICE_TEMP_MAX_C + margin);
+    
+    hours.iter().enumerate()
+        .filter(|(index, hour)| {
+            let wet = hours[index.saturating_sub(ICE_WET_LOOKBACK_HOURS)..=*index]
+                .iter()
+                .any(|h| h.precip_mm >= WET_PRECIP_MM);
+            wet && near_zero.contains(&hour.temperature)
+        })
+        .map(|(_, hour)| *hour)
+        .collect()
+}
+
+/// Период прогноза, например "2024-01-05 06:00 - 11:00" или "2024-01-05 22:00 - 2024-01-06 04:00"
+fn describe_time_window(first: &Hour, last: &Hour) -> String {
+    if first.local_date() == last.local_date() {
+        format!("{} {} - {}", first.local_date(), first.clock_time(), last.clock_time())
+    } else {
+        format!("{} - {}", first.time, last.time)
+    }
+}
+
+fn is_night_hour(hour: &Hour) -> bool {
+    hour.local_hour().is_some_and(|h| h >= NIGHT_START_HOUR || (h < NIGHT_END_HOUR && hour.is_day == 0))
+}
+
+fn coldest_hour<'a>(hours: &[&'a Hour]) -> Option<&'a Hour> {
+    hours.iter().copied().min_by(|a, b| a.temperature.total_cmp(&b.temperature))
+}
+
+/// Заморозки на почве вероятны, если воздух остыл до порога,
+/// или немного теплее порога, но воздух сухой (точка росы ниже нуля)
+fn frost_likely(hour: &Hour, max_temp: f32, hysteresis: bool) -> bool {
+    let threshold = if hysteresis { max_temp + FROST_HYSTERESIS_C } else { max_temp };
+    hour.temperature <= threshold
+        || (hour.dew_point <= 0.0 && hour.temperature <= threshold + FROST_DRY_AIR_BONUS_C)
+}
+
+fn describe_frost(coldest: &Hour) -> Vec<String> {
+    vec![
+        format!("🥶 Самый холодный час: {} ({}) - {}°C", coldest.clock_time(), coldest.local_date(), coldest.temperature),
+        format!("💧 Точка росы: {}°C", coldest.dew_point),
+    ]
+}
+
+/// Наибольшее падение давления (гПа) между двумя часами, разнесенными не больше чем на window_hours.
+/// Часы без данных о давлении пропускаются
+fn max_pressure_drop<'a>(hours: &[&'a Hour], window_hours: u8) -> Option<(&'a Hour, &'a Hour, f32)> {
+    let window = window_hours as i64 * 3600;
+    let series: Vec<&Hour> = hours.iter().copied().filter(|hour| hour.pressure > 0.0).collect();
+    
+    let mut best: Option<(&Hour, &Hour, f32)> = None;
+    for (i, from) in series.iter().enumerate() {
+        for to in series[i + 1..].iter().take_while(|to| to.time_epoch - from.time_epoch <= window) {
+            let drop = from.pressure - to.pressure;
+            if best.is_none_or(|(_, _, best_drop)| drop > best_drop) {
+                best = Some((from, to, drop));
+            }
+        }
+    }
+    best
+}
+
+fn describe_pressure_drop(from: &Hour, to: &Hour, drop: f32) -> Vec<String> {
+    let hours = (to.time_epoch - from.time_epoch) / 3600;
+    if drop > 0.0 {
+        vec![
+            format!("📉 Давление упадет на {:.1} гПа за {} ч.", drop, hours),
+            format!("🧭 {} гПа ({}) → {} гПа ({})", from.pressure, from.clock_time(), to.pressure, to.clock_time()),
+        ]
+    } else {
+        vec![format!("📈 Давление стабильно или растет: {} гПа ({}) → {} гПа ({})", from.pressure, from.clock_time(), to.pressure, to.clock_time())]
+    }
+}
+
+/// Категории опасной погоды по кодам условий и экстремальным значениям, без повторов
+fn severe_categories(codes: impl IntoIterator<Item = u32>, min_temp: f32, max_temp: f32, max_wind: f32, temp_margin: f32, wind_margin: f32) -> Vec<SevereCategory> {
+    let mut detected: Vec<SevereCategory> = codes.into_iter()
+        .filter_map(SevereCategory::from_condition_code)
+        .collect();
+    if max_temp > EXTREME_HEAT_C - temp_margin {
+        detected.push(SevereCategory::ExtremeHeat);
+    }
+    if min_temp < EXTREME_COLD_C + temp_margin {
+        detected.push(SevereCategory::ExtremeCold);
+    }
+    if max_wind > STORM_WIND_KPH - wind_margin {
+        detected.push(SevereCategory::StormWind);
+    }
+    detected.sort();
+    detected.dedup();
+    detected
+}
+
+/// Смотрим не только на общий код дня, но и на каждый час: короткая гроза в код дня обычно не попадает
+fn forecast_day_severe_categories(forecast_day: &ForecastDay, temp_margin: f32, wind_margin: f32) -> Vec<SevereCategory> {
+    let day = &forecast_day.day;
+    let codes = std::iter::once(day.condition.code)
+        .chain(forecast_day.hour.iter().map(|hour| hour.condition.code));
+    severe_categories(codes, day.min_temp, day.max_temp, day.max_wind, temp_margin, wind_margin)
+}
+
+pub fn severe_category_ru(category: SevereCategory) -> &'static str {
+    match category {
+        SevereCategory::Thunderstorm => "гроза",
+        SevereCategory::FreezingRain => "ледяной дождь",
+        SevereCategory::Blizzard => "метель",
+        SevereCategory::HeavySnow => "сильный снег",
+        SevereCategory::HeavyRain => "сильный дождь",
+        SevereCategory::Fog => "туман",
+        SevereCategory::ExtremeHeat => "сильная жара",
+        SevereCategory::ExtremeCold => "сильный мороз",
+        SevereCategory::StormWind => "штормовой ветер",
+    }
+}
+
+/// Наибольший перепад температуры в нужную сторону между двумя часами, разнесенными не больше чем на window_hours.
+/// Возвращает изменение со знаком (отрицательное - похолодание)
+fn max_temperature_swing<'a>(hours: &[&'a Hour], window_hours: u8, direction: SwingDirection) -> Option<(&'a Hour, &'a Hour, f32)> {
+    let window = window_hours as i64 * 3600;
+    
+    let mut best: Option<(&Hour, &Hour, f32)> = None;
+    for (i, from) in hours.iter().enumerate() {
+        for to in hours[i + 1..].iter().take_while(|to| to.time_epoch - from.time_epoch <= window) {
+            let change = to.temperature - from.temperature;
+            if best.is_none_or(|(_, _, best_change)| direction.magnitude(change) > direction.magnitude(best_change)) {
+                best = Some((from, to, change));
+            }
+        }
+    }
+    best
+}
+
+fn describe_temperature_swing(from: &Hour, to: &Hour, change: f32) -> Vec<String> {
+    let hours = (to.time_epoch - from.time_epoch) / 3600;
+    let trend = if change < 0.0 {
+        format!("📉 Температура упадет на {:.1}°C за {} ч.", -change, hours)
+    } else if change > 0.0 {
+        format!("📈 Температура поднимется на {:.1}°C за {} ч.", change, hours)
+    } else {
+        "🌡️ Температура без резких перепадов".to_string()
+    };
+    vec![
+        trend,
+        format!("🧭 {}°C ({}) → {}°C ({})", from.temperature, from.clock_time(), to.temperature, to.clock_time()),
+    ]
+}
+
+/// Приятный час: светло, сухо, температура в диапазоне и ветер слабее порога
+fn is_pleasant_hour(hour: &Hour, min_temp: f32, max_temp: f32, max_wind: f32) -> bool {
+    hour.is_day == 1
+        && hour.precip_mm < WET_PRECIP_MM
+        && hour.chance_of_rain.max(hour.chance_of_snow) < DRY_MAX_CHANCE_PCT
+        && (min_temp..=max_temp).contains(&hour.temperature)
+        && hour.wind_speed < max_wind
+}
+
+/// Самая длинная серия идущих подряд подходящих часов (при равной длине - самая ранняя)
+fn longest_good_window<'a, 'b>(hours: &'b [&'a Hour], is_good: impl Fn(&Hour) -> bool) -> &'b [&'a Hour] {
+    let mut best = &hours[..0];
+    let mut start = 0;
+    for (i, hour) in hours.iter().enumerate() {
+        if !is_good(hour) {
+            start = i + 1;
+            continue;
+        }
+        // Пропуск в почасовом ряду прерывает окно
+        if i > start && hour.time_epoch - hours[i - 1].time_epoch != 3600 {
+            start = i;
+        }
+        if i + 1 - start > best.len() {
+            best = &hours[start..=i];
+        }
+    }
+    best
+}
+
+fn describe_good_weather_window(window: &[&Hour], min_hours: u8) -> Vec<String> {
+    let (Some(first), Some(last)) = (window.first(), window.last()) else {
+        return vec!["🌥️ Подходящих часов в прогнозе нет".to_string()];
+    };
+    let min_temp = window.iter().map(|hour| hour.temperature).fold(f32::INFINITY, f32::min);
+    let max_temp = window.iter().map(|hour| hour.temperature).fold(f32::NEG_INFINITY, f32::max);
+    let max_wind = window.iter().map(|hour| hour.wind_speed).fold(0.0, f32::max);
+    let max_chance = window.iter().map(|hour| hour.chance_of_rain.max(hour.chance_of_snow)).max().unwrap_or(0);
+    
+    let mut lines = vec![format!("🌤️ Лучшее окно: {} ({} ч.)", describe_time_window(first, last), window.len())];
+    if window.len() < min_hours as usize {
+        lines.push(format!("⏳ Нужно хотя бы {} ч. подряд", min_hours));
+    }
+    lines.push(format!("🌡️ {}°C .. {}°C", min_temp, max_temp));
+    lines.push(format!("💨 до {} км/ч", max_wind));
+    lines.push(format!("☔ Вероятность осадков до {}%", max_chance));
+    lines
+}
+
+/// Перепад температуры между двумя соседними днями прогноза
+struct DaySwing<'a> {
+    from: &'a ForecastDay,
+    to: &'a ForecastDay,
+    /// Сравнивались дневные максимумы (иначе - ночные минимумы)
+    by_max: bool,
+    change: f32,
+}
+
+/// Сравнивает и максимумы, и минимумы: похолодание часто заметно только по одному из них
+fn day_swing<'a>(from: &'a ForecastDay, to: &'a ForecastDay, direction: SwingDirection) -> DaySwing<'a> {
+    let max_change = to.day.max_temp - from.day.max_temp;
+    let min_change = to.day.min_temp - from.day.min_temp;
+    let by_max = direction.magnitude(max_change) >= direction.magnitude(min_change);
+    DaySwing {
+        from,
+        to,
+        by_max,
+        change: if by_max { max_change } else { min_change },
+    }
+}
+
+fn describe_day_swing(swing: &DaySwing) -> Vec<String> {
+    let (label, from_temp, to_temp) = if swing.by_max {
+        ("Максимум", swing.from.day.max_temp, swing.to.day.max_temp)
+    } else {
+        ("Минимум", swing.from.day.min_temp, swing.to.day.min_temp)
+    };
+    let trend = if swing.change < 0.0 {
+        format!("📉 {} похолодает на {:.1}°C", swing.to.date, -swing.change)
+    } else if swing.change > 0.0 {
+        format!("📈 {} потеплеет на {:.1}°C", swing.to.date, swing.change)
+    } else {
+        format!("🌡️ {} температура без изменений", swing.to.date)
+    };
+    vec![
+        trend,
+        format!("🌡️ {}: {}°C ({}) → {}°C ({})", label, from_temp, swing.from.date, to_temp, swing.to.date),
+    ]
+}
+
+/// Индекс US EPA достиг порога или PM2.5 превысил заданную концентрацию
+fn air_quality_triggered(air_quality: &AirQuality, min_epa_index: u8, max_pm2_5: Option<f32>, hysteresis: bool) -> bool {
+    let pm_margin = if hysteresis { PM2_5_HYSTERESIS } else { 0.0 };
+    let index_triggered = air_quality.us_epa_index.is_some_and(|index| index >= min_epa_index);
+    let pm_triggered = match (max_pm2_5, air_quality.pm2_5) {
+        (Some(threshold), Some(pm2_5)) => pm2_5 > threshold - pm_margin,
+        _ => false,
+    };
+    index_triggered || pm_triggered
+}
+
+/// Уровень по индексу US EPA (1-6)
+pub fn epa_index_label_ru(index: u8) -> &'static str {
+    match index {
+        0 | 1 => "хорошее",
+        2 => "удовлетворительное",
+        3 => "вредно для чувствительных групп",
+        4 => "вредно",
+        5 => "очень вредно",
+        _ => "опасно",
+    }
+}
+
+fn describe_air_quality(air_quality: &AirQuality) -> Vec<String> {
+    let value = |v: Option<f32>| v.map(|v| format!("{:.1}", v)).unwrap_or_else(|| "н/д".to_string());
+    let mut lines = Vec::new();
+    if let Some(index) = air_quality.us_epa_index {
+        lines.push(format!("🫁 Индекс US EPA {} - {}", index, epa_index_label_ru(index)));
+    }
+    if let Some(index) = air_quality.gb_defra_index {
+        lines.push(format!("🇬🇧 Индекс DEFRA {}", index));
+    }
+    lines.push(format!(
+        "🌫️ PM2.5 {} | PM10 {} | O₃ {} | NO₂ {} µg/m³",
+        value(air_quality.pm2_5), value(air_quality.pm10), value(air_quality.o3), value(air_quality.no2)
+    ));
+    lines
+}
+
+fn precipitation_kind_genitive(kind: PrecipitationKind) -> &'static str {
+    match kind {
+        PrecipitationKind::Any => "осадков",
+        PrecipitationKind::Rain => "дождя",
+        PrecipitationKind::Snow => "снега",
+    }
 }
 
 pub fn generate_alert_id() -> String {
     Uuid::new_v4().to_string()
 }
 
-pub fn create_standard_alert(city: String, hours_ahead: u8) -> WeatherAlert {
+/// Создает алерт любого типа с описанием, как у соответствующей функции create_*
+pub fn create_alert(city: String, alert_type: AlertType, hours_ahead: u8) -> WeatherAlert {
+    match alert_type {
+        AlertType::StandardWeatherAlert { categories } => create_standard_alert(city, categories, hours_ahead),
+        AlertType::TemperatureThreshold { min, max } => create_temperature_alert(city, min, max, hours_ahead),
+        AlertType::WindSpeed { max, use_gusts, direction } => create_wind_alert(city, max, use_gusts, direction, hours_ahead),
+        AlertType::Humidity { min, max } => create_humidity_alert(city, min, max, hours_ahead),
+        AlertType::Precipitation { min_chance, min_mm, kind } => create_precipitation_alert(city, min_chance, min_mm, kind, hours_ahead),
+        AlertType::UvIndex { max } => create_uv_alert(city, max, hours_ahead),
+        AlertType::AirQuality { min_epa_index, max_pm2_5 } => create_air_quality_alert(city, min_epa_index, max_pm2_5, hours_ahead),
+        AlertType::PressureDrop { drop_hpa, window_hours } => create_pressure_drop_alert(city, drop_hpa, window_hours, hours_ahead),
+        AlertType::Frost { max_temp } => create_frost_alert(city, max_temp, hours_ahead),
+        AlertType::SnowIce { min_snow_cm, ice_risk } => create_snow_ice_alert(city, min_snow_cm, ice_risk, hours_ahead),
+        AlertType::FeelsLike { min, max } => create_feels_like_alert(city, min, max, hours_ahead),
+        AlertType::Composite { rule } => create_composite_alert(city, rule, hours_ahead),
+        AlertType::TemperatureSwing { delta, direction, window_hours } => create_temperature_swing_alert(city, delta, direction, window_hours, hours_ahead),
+        AlertType::GoodWeather { min_temp, max_temp, max_wind, min_hours } => create_good_weather_alert(city, min_temp, max_temp, max_wind, min_hours, hours_ahead),
+    }
+}
+
+/// Меняет параметры существующего алерта: id, дата создания и история сохраняются,
+/// описание собирается заново (если пользователь не задал свое)
+pub fn update_alert(alert: &mut WeatherAlert, city: String, alert_type: AlertType, hours_ahead: u8) {
+    let rebuilt = create_alert(city, alert_type, hours_ahead);
+    
+    // Для другого города или других порогов прежнее состояние уже не актуально - как и откладывание
+    // и ожидание подтверждения уведомления о старом условии
+    if normalize_location(&rebuilt.city) != normalize_location(&alert.city) || rebuilt.alert_type != alert.alert_type {
+        alert.state = AlertState::Clear;
+        alert.snoozed_until = None;
+        alert.ack_requested_at = None;
+        alert.escalated = false;
+    }
+    alert.city = rebuilt.city;
+    alert.alert_type = rebuilt.alert_type;
+    alert.hours_ahead = hours_ahead;
+    if !alert.custom_description {
+        alert.description = rebuilt.description;
+    }
+}
+
+pub fn create_standard_alert(city: String, categories: Vec<SevereCategory>, hours_ahead: u8) -> WeatherAlert {
+    let description = if categories == SevereCategory::DEFAULTS {
+        format!("Стандартные предупреждения о погоде для {} (за {} ч.)", city, hours_ahead)
+    } else {
+        let names: Vec<&str> = categories.iter().copied().map(severe_category_ru).collect();
+        format!("Опасная погода ({}) в {} (за {} ч.)", names.join(", "), city, hours_ahead)
+    };
+    
     WeatherAlert::new(
         generate_alert_id(),
-        city.clone(),
-        AlertType::StandardWeatherAlert,
-        format!("Стандартные предупреждения о погоде для {} (за {} ч.)", city, hours_ahead),
+        city,
+        AlertType::StandardWeatherAlert { categories },
+        description,
         hours_ahead
     )
 }
@@ -207,12 +1395,15 @@ pub fn create_temperature_alert(city: String, min: Option<f32>, max: Option<f32>
     )
 }
 
-pub fn create_wind_alert(city: String, max_speed: f32, hours_ahead: u8) -> WeatherAlert {
+pub fn create_wind_alert(city: String, max_speed: f32, use_gusts: bool, direction: Option<WindSector>, hours_ahead: u8) -> WeatherAlert {
+    let measure = if use_gusts { "Порывы ветра" } else { "Скорость ветра" };
+    let sector = direction.map(|d| format!(" (направление {})", d.code())).unwrap_or_default();
+    
     WeatherAlert::new(
         generate_alert_id(),
         city.clone(),
-        AlertType::WindSpeed { max: max_speed },
-        format!("Скорость ветра выше {} км/ч в {} (за {} ч.)", max_speed, city, hours_ahead),
+        AlertType::WindSpeed { max: max_speed, use_gusts, direction },
+        format!("{} выше {} км/ч{} в {} (за {} ч.)", measure, max_speed, sector, city, hours_ahead),
         hours_ahead
     )
 }
@@ -232,4 +1423,412 @@ pub fn create_humidity_alert(city: String, min: Option<u32>, max: Option<u32>, h
         description,
         hours_ahead
     )
-} 
\ No newline at end of file
+}
+
+pub fn create_precipitation_alert(city: String, min_chance: Option<u32>, min_mm: Option<f32>, kind: PrecipitationKind, hours_ahead: u8) -> WeatherAlert {
+    let mut conditions = Vec::new();
+    if let Some(chance) = min_chance {
+        conditions.push(format!("вероятность от {}%", chance));
+    }
+    if let Some(mm) = min_mm {
+        conditions.push(format!("от {} мм", mm));
+    }
+    let description = if conditions.is_empty() {
+        format!("Ожидание {} в {} (за {} ч.)", precipitation_kind_genitive(kind), city, hours_ahead)
+    } else {
+        format!("Ожидание {}: {} в {} (за {} ч.)", precipitation_kind_genitive(kind), conditions.join(", "), city, hours_ahead)
+    };
+    
+    WeatherAlert::new(
+        generate_alert_id(),
+        city,
+        AlertType::Precipitation { min_chance, min_mm, kind },
+        description,
+        hours_ahead
+    )
+}
+
+pub fn create_uv_alert(city: String, max: f32, hours_ahead: u8) -> WeatherAlert {
+    WeatherAlert::new(
+        generate_alert_id(),
+        city.clone(),
+        AlertType::UvIndex { max },
+        format!("УФ-индекс выше {} в {} (за {} ч.)", max, city, hours_ahead),
+        hours_ahead
+    )
+}
+
+pub fn create_air_quality_alert(city: String, min_epa_index: u8, max_pm2_5: Option<f32>, hours_ahead: u8) -> WeatherAlert {
+    let description = match max_pm2_5 {
+        Some(pm2_5) => format!("Качество воздуха: индекс US EPA от {} или PM2.5 выше {} µg/m³ в {} (за {} ч.)", min_epa_index, pm2_5, city, hours_ahead),
+        None => format!("Качество воздуха: индекс US EPA от {} ({}) в {} (за {} ч.)", min_epa_index, epa_index_label_ru(min_epa_index), city, hours_ahead),
+    };
+    
+    WeatherAlert::new(
+        generate_alert_id(),
+        city,
+        AlertType::AirQuality { min_epa_index, max_pm2_5 },
+        description,
+        hours_ahead
+    )
+}
+
+pub fn create_pressure_drop_alert(city: String, drop_hpa: f32, window_hours: u8, hours_ahead: u8) -> WeatherAlert {
+    WeatherAlert::new(
+        generate_alert_id(),
+        city.clone(),
+        AlertType::PressureDrop { drop_hpa, window_hours },
+        format!("Падение давления на {} гПа за {} ч. в {} (за {} ч.)", drop_hpa, window_hours, city, hours_ahead),
+        hours_ahead
+    )
+}
+
+pub fn create_frost_alert(city: String, max_temp: f32, hours_ahead: u8) -> WeatherAlert {
+    WeatherAlert::new(
+        generate_alert_id(),
+        city.clone(),
+        AlertType::Frost { max_temp },
+        format!("Заморозки на почве (ночью воздух до {}°C) в {} (за {} ч.)", max_temp, city, hours_ahead),
+        hours_ahead
+    )
+}
+
+pub fn create_snow_ice_alert(city: String, min_snow_cm: Option<f32>, ice_risk: bool, hours_ahead: u8) -> WeatherAlert {
+    let conditions = match (min_snow_cm, ice_risk) {
+        (Some(snow), true) => format!("снег от {} см или гололед", snow),
+        (Some(snow), false) => format!("снег от {} см", snow),
+        (None, _) => "гололед".to_string(),
+    };
+    
+    WeatherAlert::new(
+        generate_alert_id(),
+        city.clone(),
+        AlertType::SnowIce { min_snow_cm, ice_risk },
+        format!("Снег и гололед: {} в {} (за {} ч.)", conditions, city, hours_ahead),
+        hours_ahead
+    )
+}
+
+pub fn create_feels_like_alert(city: String, min: Option<f32>, max: Option<f32>, hours_ahead: u8) -> WeatherAlert {
+    let range = match (min, max) {
+        (Some(min), Some(max)) => format!("ниже {}°C или выше {}°C", min, max),
+        (Some(min), None) => format!("ниже {}°C", min),
+        (None, Some(max)) => format!("выше {}°C", max),
+        (None, None) => "вне нормы".to_string(),
+    };
+    
+    WeatherAlert::new(
+        generate_alert_id(),
+        city.clone(),
+        AlertType::FeelsLike { min, max },
+        format!("Ощущаемая температура {} в {} (за {} ч.)", range, city, hours_ahead),
+        hours_ahead
+    )
+}
+
+pub fn create_composite_alert(city: String, rule: RuleExpr, hours_ahead: u8) -> WeatherAlert {
+    WeatherAlert::new(
+        generate_alert_id(),
+        city.clone(),
+        AlertType::Composite { rule: rule.clone() },
+        format!("Правило \"{}\" в {} (за {} ч.)", rule.describe(), city, hours_ahead),
+        hours_ahead
+    )
+}
+
+pub fn create_temperature_swing_alert(city: String, delta: f32, direction: SwingDirection, window_hours: Option<u8>, hours_ahead: u8) -> WeatherAlert {
+    let change = match direction {
+        SwingDirection::Drop => "Похолодание",
+        SwingDirection::Rise => "Потепление",
+        SwingDirection::Either => "Перепад температуры",
+    };
+    let period = match window_hours {
+        Some(window) => format!("за {} ч.", window),
+        None => "день к дню".to_string(),
+    };
+    
+    WeatherAlert::new(
+        generate_alert_id(),
+        city.clone(),
+        AlertType::TemperatureSwing { delta, direction, window_hours },
+        format!("{} от {}°C {} в {} (за {} ч.)", change, delta, period, city, hours_ahead),
+        hours_ahead
+    )
+}
+
+pub fn create_good_weather_alert(city: String, min_temp: f32, max_temp: f32, max_wind: f32, min_hours: u8, hours_ahead: u8) -> WeatherAlert {
+    WeatherAlert::new(
+        generate_alert_id(),
+        city.clone(),
+        AlertType::GoodWeather { min_temp, max_temp, max_wind, min_hours },
+        format!("Хорошая погода: сухо, {}-{}°C, ветер до {} км/ч не меньше {} ч. подряд в {} (за {} ч.)", min_temp, max_temp, max_wind, min_hours, city, hours_ahead),
+        hours_ahead
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{Comparison, RuleCondition};
+    use crate::weather_api::{Condition, Day, Forecast, Location};
+
+    fn forecast_day(min_temp: f32, max_temp: f32, max_wind: f32, avg_humidity: f32, hour: Vec<Hour>) -> ForecastDay {
+        ForecastDay {
+            date: "2024-01-05".to_string(),
+            day: Day {
+                max_temp,
+                min_temp,
+                condition: Condition { text: "Clear".to_string(), icon: String::new(), code: 1000 },
+                avg_humidity,
+                max_wind,
+                total_precip: 0.0,
+                total_snow: 0.0,
+                daily_chance_of_rain: 0,
+                daily_chance_of_snow: 0,
+                uv: 0.0,
+                air_quality: None,
+            },
+            hour,
+        }
+    }
+
+    /// Срабатывание для нового (hysteresis = false) и уже сработавшего (true) алерта
+    fn day_triggers(day: &ForecastDay, alert_type: &AlertType) -> (bool, bool) {
+        (
+            AlertChecker::evaluate_forecast_day(day, alert_type, false).unwrap(),
+            AlertChecker::evaluate_forecast_day(day, alert_type, true).unwrap(),
+        )
+    }
+
+    #[test]
+    fn triggered_daily_alerts_clear_only_past_the_hysteresis_margin() {
+        let heat = AlertType::TemperatureThreshold { min: None, max: Some(30.0) };
+        assert_eq!(day_triggers(&forecast_day(18.0, 30.5, 10.0, 50.0, Vec::new()), &heat), (true, true));
+        // Чуть ниже порога: новый алерт молчит, сработавший еще не дает отбой
+        assert_eq!(day_triggers(&forecast_day(18.0, 29.5, 10.0, 50.0, Vec::new()), &heat), (false, true));
+        assert_eq!(day_triggers(&forecast_day(18.0, 28.9, 10.0, 50.0, Vec::new()), &heat), (false, false));
+
+        let cold = AlertType::TemperatureThreshold { min: Some(-10.0), max: None };
+        assert_eq!(day_triggers(&forecast_day(-9.5, 0.0, 10.0, 50.0, Vec::new()), &cold), (false, true));
+
+        let wind = AlertType::WindSpeed { max: 50.0, use_gusts: false, direction: None };
+        assert_eq!(day_triggers(&forecast_day(5.0, 10.0, 47.0, 50.0, Vec::new()), &wind), (false, true));
+        assert_eq!(day_triggers(&forecast_day(5.0, 10.0, 44.0, 50.0, Vec::new()), &wind), (false, false));
+
+        let humid = AlertType::Humidity { min: None, max: Some(80) };
+        assert_eq!(day_triggers(&forecast_day(5.0, 10.0, 10.0, 78.0, Vec::new()), &humid), (false, true));
+        assert_eq!(day_triggers(&forecast_day(5.0, 10.0, 10.0, 76.0, Vec::new()), &humid), (false, false));
+    }
+
+    #[test]
+    fn alert_state_decides_whether_hysteresis_applies() {
+        let forecast = ForecastResponse {
+            location: Location {
+                name: "Kyiv".to_string(),
+                region: String::new(),
+                country: String::new(),
+                localtime: String::new(),
+                localtime_epoch: 0,
+            },
+            forecast: Forecast { forecast_day: vec![forecast_day(18.0, 29.5, 10.0, 50.0, Vec::new())] },
+            alerts: Default::default(),
+        };
+        let mut alert = create_temperature_alert("Kyiv".to_string(), None, Some(30.0), 12);
+
+        for (state, triggered) in [(AlertState::Clear, false), (AlertState::Triggered, true), (AlertState::Ongoing, true)] {
+            alert.state = state;
+            let evaluation = AlertChecker::evaluate_forecast(&forecast, &alert).unwrap();
+            assert_eq!(evaluation.triggered, triggered, "{:?}", state);
+            assert_eq!(evaluation.target_time.as_deref(), Some("2024-01-05"));
+        }
+    }
+
+    #[test]
+    fn editing_the_condition_drops_snooze_and_pending_acknowledgment() {
+        let mut alert = create_temperature_alert("Kyiv".to_string(), None, Some(30.0), 24);
+        alert.state = AlertState::Triggered;
+        alert.snoozed_until = Some(chrono::Utc::now());
+        alert.ack_requested_at = Some(chrono::Utc::now());
+        alert.escalated = true;
+        alert.escalation_message_id = Some(42);
+
+        // Тот же город и тип - правка горизонта ничего не сбрасывает
+        let same_type = alert.alert_type.clone();
+        update_alert(&mut alert, "kyiv".to_string(), same_type, 12);
+        assert_eq!(alert.state, AlertState::Triggered);
+        assert!(alert.snoozed_until.is_some() && alert.escalated);
+
+        update_alert(&mut alert, "Kyiv".to_string(), AlertType::TemperatureThreshold { min: None, max: Some(35.0) }, 12);
+        assert_eq!(alert.state, AlertState::Clear);
+        assert!(alert.snoozed_until.is_none());
+        assert!(alert.ack_requested_at.is_none());
+        assert!(!alert.escalated);
+        // Id закрепленного сообщения остается, чтобы бот мог его открепить
+        assert_eq!(alert.escalation_message_id, Some(42));
+    }
+
+    fn hours_with(values: &[(u32, f32)], set: impl Fn(&mut Hour, f32)) -> Vec<Hour> {
+        values.iter()
+            .map(|&(index, value)| {
+                let mut hour = Hour::sample(index, 10.0);
+                set(&mut hour, value);
+                hour
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pressure_drop_is_measured_within_the_window() {
+        // Час без данных о давлении (0) не должен давать огромного "падения"
+        let hours = hours_with(&[(0, 1012.0), (1, 0.0), (2, 1009.0), (4, 1006.8), (8, 1003.0)], |hour, value| hour.pressure = value);
+        let hours: Vec<&Hour> = hours.iter().collect();
+
+        let evaluation = AlertChecker::evaluate_pressure_drop(&hours, 5.5, 4, false);
+        assert!(!evaluation.triggered);
+        assert_eq!(evaluation.target_time.as_deref(), Some("2024-01-05 04:00"));
+        // 5.2 гПа за 4 ч.: для нового алерта мало, сработавший остается активным
+        assert!(AlertChecker::evaluate_pressure_drop(&hours, 5.5, 4, true).triggered);
+        assert!(!AlertChecker::evaluate_pressure_drop(&hours, 5.5, 3, true).triggered);
+        assert!(AlertChecker::evaluate_pressure_drop(&hours, 5.5, 8, false).triggered);
+
+        let rising = hours_with(&[(0, 1000.0), (3, 1004.0)], |hour, value| hour.pressure = value);
+        let rising: Vec<&Hour> = rising.iter().collect();
+        assert!(!AlertChecker::evaluate_pressure_drop(&rising, 1.0, 6, true).triggered);
+    }
+
+    #[test]
+    fn frost_checks_night_hours_with_dry_air_bonus() {
+        let mut humid_night = Hour::sample(3, 2.5);
+        humid_night.dew_point = 1.0;
+        humid_night.is_day = 0;
+        let afternoon = Hour::sample(14, -3.0);
+        let hours = vec![&humid_night, &afternoon];
+
+        // Холодный дневной час не считается, ночной чуть теплее порога
+        assert!(!AlertChecker::evaluate_frost(&hours, 2.0, false).triggered);
+        assert!(AlertChecker::evaluate_frost(&hours, 2.0, true).triggered);
+        assert_eq!(AlertChecker::evaluate_frost(&hours, 2.0, false).target_time.as_deref(), Some("2024-01-05 03:00"));
+
+        // Сухой воздух (точка росы ниже нуля) выхолаживает почву и при более теплом воздухе
+        let mut dry_night = Hour::sample(22, 3.5);
+        dry_night.dew_point = -1.0;
+        assert!(AlertChecker::evaluate_frost(&[&dry_night], 2.0, false).triggered);
+        dry_night.temperature = 4.5;
+        assert!(!AlertChecker::evaluate_frost(&[&dry_night], 2.0, false).triggered);
+
+        let evaluation = AlertChecker::evaluate_frost(&[&afternoon], 2.0, true);
+        assert!(!evaluation.triggered && evaluation.target_time.is_none());
+
+        // Утро после восхода уже не ночь, до восхода - еще ночь
+        let mut morning = Hour::sample(8, -1.0);
+        assert!(!AlertChecker::evaluate_frost(&[&morning], 2.0, false).triggered);
+        morning.is_day = 0;
+        assert!(AlertChecker::evaluate_frost(&[&morning], 2.0, false).triggered);
+    }
+
+    #[test]
+    fn snow_sums_over_the_window_and_ice_needs_a_wet_road() {
+        let snowy = hours_with(&[(0, 0.0), (1, 0.6), (2, 0.6), (3, 0.6)], |hour, value| hour.snow_cm = value);
+        let snowy: Vec<&Hour> = snowy.iter().collect();
+        // 1.8 см при пороге 2 см: новый алерт молчит, сработавший не дает отбой
+        assert!(!AlertChecker::evaluate_snow_ice(&snowy, Some(2.0), false, false).triggered);
+        let evaluation = AlertChecker::evaluate_snow_ice(&snowy, Some(2.0), false, true);
+        assert!(evaluation.triggered);
+        assert_eq!(evaluation.target_time.as_deref(), Some("2024-01-05 01:00"));
+
+        // Дождь при +3, затем 0.5°C - мокрая дорога подмерзает; через 4 сухих часа риска уже нет
+        let mut road: Vec<Hour> = (5..=10).map(|index| Hour::sample(index, 0.5)).collect();
+        road[0].temperature = 3.0;
+        road[0].precip_mm = 0.5;
+        let hours: Vec<&Hour> = road.iter().collect();
+        let icy: Vec<&str> = icy_hours(&hours, false).iter().map(|hour| hour.clock_time()).collect();
+        assert_eq!(icy, ["06:00", "07:00", "08:00"]);
+        assert!(AlertChecker::evaluate_snow_ice(&hours, None, true, false).triggered);
+        assert!(!AlertChecker::evaluate_snow_ice(&hours[4..], None, true, false).triggered);
+
+        // Чуть выше диапазона гололеда - только для уже сработавшего алерта
+        for hour in road.iter_mut().skip(1) {
+            hour.temperature = 1.5;
+        }
+        let hours: Vec<&Hour> = road.iter().collect();
+        assert!(!AlertChecker::evaluate_snow_ice(&hours, None, true, false).triggered);
+        assert!(AlertChecker::evaluate_snow_ice(&hours, None, true, true).triggered);
+    }
+
+    #[test]
+    fn wind_uses_gusts_and_direction_filter() {
+        let mut northerly = Hour::sample(9, 5.0);
+        northerly.wind_speed = 20.0;
+        northerly.gust_speed = 48.0;
+        northerly.wind_degree = Some(10);
+        let mut southerly = Hour::sample(12, 5.0);
+        southerly.wind_speed = 60.0;
+        southerly.wind_direction = "S".to_string();
+        let hours = vec![&northerly, &southerly];
+
+        // Средний ветер южный, порывы до 48 км/ч - северные
+        assert!(AlertChecker::evaluate_wind(&hours, 50.0, false, None, false).triggered);
+        assert!(!AlertChecker::evaluate_wind(&hours, 50.0, true, Some(WindSector::N), false).triggered);
+        assert!(AlertChecker::evaluate_wind(&hours, 50.0, true, Some(WindSector::N), true).triggered);
+        assert!(AlertChecker::evaluate_wind(&hours, 50.0, false, Some(WindSector::S), false).triggered);
+
+        let evaluation = AlertChecker::evaluate_wind(&hours, 10.0, false, Some(WindSector::E), true);
+        assert!(!evaluation.triggered);
+        assert!(evaluation.observed[0].contains("направления E"));
+
+        // Порывы не бывают слабее среднего ветра
+        assert_eq!(wind_value(&southerly, true), 60.0);
+    }
+
+    #[test]
+    fn feels_like_uses_heat_index_and_wind_chill() {
+        let mut muggy = Hour::sample(15, 31.0);
+        muggy.feels_like = Some(33.0);
+        muggy.heat_index = Some(34.5);
+        let mut windy = Hour::sample(6, -12.0);
+        windy.feels_like = Some(-17.0);
+        windy.wind_chill = Some(-19.5);
+        let hours = vec![&windy, &muggy];
+
+        assert!(!AlertChecker::evaluate_feels_like(&hours, None, Some(35.0), false).triggered);
+        let evaluation = AlertChecker::evaluate_feels_like(&hours, None, Some(35.0), true);
+        assert!(evaluation.triggered);
+        assert_eq!(evaluation.target_time.as_deref(), Some("2024-01-05 15:00"));
+
+        assert!(!AlertChecker::evaluate_feels_like(&hours, Some(-20.0), None, false).triggered);
+        assert!(AlertChecker::evaluate_feels_like(&hours, Some(-20.0), None, true).triggered);
+
+        // Сработали оба порога - время срабатывания по более раннему часу
+        let evaluation = AlertChecker::evaluate_feels_like(&hours, Some(-19.0), Some(34.0), false);
+        assert!(evaluation.triggered);
+        assert_eq!(evaluation.target_time.as_deref(), Some("2024-01-05 06:00"));
+        assert_eq!(evaluation.observed.len(), 2);
+    }
+
+    #[test]
+    fn triggered_composite_rules_clear_only_past_the_hysteresis_margin() {
+        let rule = AlertType::Composite {
+            rule: RuleExpr::And(vec![
+                RuleExpr::Condition(RuleCondition { metric: Metric::Temperature, op: Comparison::Greater, value: 25.0 }),
+                RuleExpr::Not(Box::new(RuleExpr::Condition(RuleCondition {
+                    metric: Metric::ChanceOfRain,
+                    op: Comparison::GreaterOrEqual,
+                    value: 50.0,
+                }))),
+            ]),
+        };
+        let day = |temperature: f32, chance_of_rain: u32| {
+            let mut hour = Hour::sample(14, temperature);
+            hour.chance_of_rain = chance_of_rain;
+            forecast_day(10.0, temperature, 10.0, 50.0, vec![hour])
+        };
+
+        assert_eq!(day_triggers(&day(26.0, 20), &rule), (true, true));
+        // Температура чуть ниже порога: новый алерт молчит, сработавший не дает отбой
+        assert_eq!(day_triggers(&day(24.5, 20), &rule), (false, true));
+        assert_eq!(day_triggers(&day(23.9, 20), &rule), (false, false));
+        // Под NOT запас в обратную сторону: отбой только когда дождь вероятнее 60%
+        assert_eq!(day_triggers(&day(26.0, 55), &rule), (false, true));
+        assert_eq!(day_triggers(&day(26.0, 60), &rule), (false, false));
+    }
+}
@@ -1,3 +1,4 @@
+use chrono::Timelike;
 use teloxide::{
     prelude::*,
     types::{InlineKeyboardButton, InlineKeyboardMarkup},
@@ -5,8 +6,11 @@ use teloxide::{
 };
 use crate::{
     weather_api, 
-    state::{SharedState, get_user_data, update_user_data, AlertType}, 
-    alerts::{create_standard_alert, create_temperature_alert, create_wind_alert, create_humidity_alert}
+    state::{SharedState, get_user_data, update_user_data, AlertType, PrecipitationKind, SevereCategory, SwingDirection, WindSector},
+    rules::{Comparison, Metric, RuleCondition, RuleExpr, RuleJoin, MAX_RULE_CONDITIONS}, 
+    alerts::{create_alert, feels_like_preset, good_weather_preset, update_alert, DEFAULT_FROST_AIR_TEMP_C, FEELS_LIKE_PRESETS, GOOD_WEATHER_PRESETS},
+    dsl::{format_alert_command, parse_alert_command, parse_schedule, AlertSchedule},
+    presets::{alert_presets, find_preset, AlertPreset}
 };
 
 type HandlerResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
@@ -18,6 +22,8 @@ pub enum Command {
     Help,
     #[command(description = "Start the bot.")]
     Start,
+    #[command(description = "Create an alert in one line, e.g. /alert Kyiv temp<-5 or wind>50 within 24h")]
+    Alert(String),
 }
 
 pub async fn answer(bot: Bot, msg: Message, cmd: Command, state: SharedState) -> HandlerResult {
@@ -45,6 +51,33 @@ pub async fn answer(bot: Bot, msg: Message, cmd: Command, state: SharedState) ->
                 .reply_markup(keyboard)
                 .await?
         }
+        Command::Alert(args) => {
+            if args.trim().is_empty() {
+                bot.send_message(chat_id, ALERT_COMMAND_HELP)
+                    .await?
+            } else {
+                match parse_alert_command(&args) {
+                    Ok(parsed) => {
+                        let alert = parsed.into_alert();
+                        let text = format!(
+                            "✅ Alert created!\n\n{} {}\n⌨️ {}",
+                            alert.alert_type.emoji(),
+                            alert.description,
+                            format_alert_command(&alert).unwrap_or_default()
+                        );
+                        update_user_data(&state, chat_id, |user_data| {
+                            user_data.weather_alerts.push(alert);
+                        });
+                        bot.send_message(chat_id, text)
+                            .await?
+                    }
+                    Err(e) => {
+                        bot.send_message(chat_id, format!("{}\n\nSend /alert without arguments for the syntax.", e.render(&args)))
+                            .await?
+                    }
+                }
+            }
+        }
     };
 
     Ok(())
@@ -101,7 +134,7 @@ pub async fn callback_handler(bot: Bot, q: CallbackQuery, state: SharedState) ->
                         // Send "typing" action while fetching forecast
                         bot.send_chat_action(chat_id, teloxide::types::ChatAction::Typing).await?;
                         
-                        match weather_api::get_forecast(home_town, 3).await {
+                        match weather_api::get_forecast_with_warnings(home_town, 3).await {
                             Ok(forecast) => {
                                 let forecast_message = weather_api::format_forecast(&forecast);
                                 bot.send_message(chat_id, forecast_message)
@@ -145,7 +178,8 @@ pub async fn callback_handler(bot: Bot, q: CallbackQuery, state: SharedState) ->
                         
                         match weather_api::get_current_weather(home_town).await {
                             Ok(weather) => {
-                                let weather_message = weather_api::format_current_weather(&weather);
+                                let warnings = weather_api::get_weather_warnings(home_town).await;
+                                let weather_message = weather_api::format_current_weather(&weather, &warnings);
                                 bot.send_message(chat_id, weather_message)
                                     .parse_mode(teloxide::types::ParseMode::MarkdownV2)
                                     .await?;
@@ -231,7 +265,8 @@ pub async fn callback_handler(bot: Bot, q: CallbackQuery, state: SharedState) ->
                         
                         match weather_api::get_current_weather(home_town).await {
                             Ok(weather) => {
-                                let weather_message = weather_api::format_current_weather(&weather);
+                                let warnings = weather_api::get_weather_warnings(home_town).await;
+                                let weather_message = weather_api::format_current_weather(&weather, &warnings);
                                 bot.send_message(chat_id, weather_message)
                                     .parse_mode(teloxide::types::ParseMode::MarkdownV2)
                                     .await?;
@@ -264,6 +299,51 @@ pub async fn callback_handler(bot: Bot, q: CallbackQuery, state: SharedState) ->
                             .await?;
                     }
                 }
+                "toggle_weekly_digest" => {
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.weekly_digest_enabled = !user_data.weekly_digest_enabled;
+                    });
+                    
+                    let user_data = get_user_data(&state, chat_id);
+                    let text = if !user_data.weekly_digest_enabled {
+                        "📭 Weekly digest disabled.".to_string()
+                    } else if crate::digest::digest_towns(&user_data).is_empty() {
+                        "📬 Weekly digest enabled! Add a home town or interested towns so there is something to summarize.".to_string()
+                    } else {
+                        format!(
+                            "📬 Weekly digest enabled! Every {} you'll get an outlook for the coming days in your towns (as many as the weather plan provides).",
+                            crate::digest::describe_schedule()
+                        )
+                    };
+                    bot.send_message(chat_id, text)
+                        .await?;
+                    
+                    let keyboard = make_my_towns_keyboard(&state, chat_id);
+                    bot.send_message(chat_id, "Manage your towns:")
+                        .reply_markup(keyboard)
+                        .await?;
+                }
+                "toggle_official_warnings" => {
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.official_warnings_enabled = !user_data.official_warnings_enabled;
+                    });
+                    
+                    let user_data = get_user_data(&state, chat_id);
+                    let text = if !user_data.official_warnings_enabled {
+                        "🔕 Official warnings disabled."
+                    } else if crate::digest::digest_towns(&user_data).is_empty() {
+                        "🚨 Official warnings enabled! Add a home town or interested towns so I know where to watch."
+                    } else {
+                        "🚨 Official warnings enabled! I'll forward each new government weather warning issued for your towns."
+                    };
+                    bot.send_message(chat_id, text)
+                        .await?;
+                    
+                    let keyboard = make_my_towns_keyboard(&state, chat_id);
+                    bot.send_message(chat_id, "Manage your towns:")
+                        .reply_markup(keyboard)
+                        .await?;
+                }
                 "back_to_main" => {
                     let keyboard = make_main_menu_keyboard(&state, chat_id);
                     bot.send_message(chat_id, "Welcome! Please choose an option:")
@@ -290,7 +370,45 @@ pub async fn callback_handler(bot: Bot, q: CallbackQuery, state: SharedState) ->
                         user_data.waiting_for_alert_wind_speed = false;
                         user_data.waiting_for_alert_humidity_min = false;
                         user_data.waiting_for_alert_humidity_max = false;
+                        user_data.waiting_for_alert_precip_chance = false;
+                        user_data.waiting_for_alert_precip_mm = false;
+                        user_data.waiting_for_alert_precip_kind = false;
+                        user_data.waiting_for_alert_uv_max = false;
+                        user_data.waiting_for_alert_aqi_index = false;
+                        user_data.waiting_for_alert_aqi_pm25 = false;
+                        user_data.waiting_for_alert_pressure_drop = false;
+                        user_data.waiting_for_alert_pressure_window = false;
+                        user_data.waiting_for_alert_frost_temp = false;
+                        user_data.waiting_for_alert_snow_cm = false;
+                        user_data.waiting_for_alert_snow_ice = false;
+                        user_data.waiting_for_alert_wind_gusts = false;
+                        user_data.waiting_for_alert_wind_direction = false;
+                        user_data.waiting_for_alert_feels_preset = false;
+                        user_data.waiting_for_alert_feels_min = false;
+                        user_data.waiting_for_alert_feels_max = false;
+                        user_data.waiting_for_alert_rule_metric = false;
+                        user_data.waiting_for_alert_rule_op = false;
+                        user_data.waiting_for_alert_rule_value = false;
+                        user_data.waiting_for_alert_rule_next = false;
+                        user_data.waiting_for_alert_swing_delta = false;
+                        user_data.waiting_for_alert_swing_direction = false;
+                        user_data.waiting_for_alert_swing_mode = false;
+                        user_data.waiting_for_alert_good_preset = false;
+                        user_data.waiting_for_alert_good_temp_min = false;
+                        user_data.waiting_for_alert_good_temp_max = false;
+                        user_data.waiting_for_alert_good_wind = false;
+                        user_data.waiting_for_alert_good_window = false;
+                        user_data.waiting_for_alert_standard_categories = false;
+                        user_data.waiting_for_edit_city = false;
+                        user_data.waiting_for_edit_hours = false;
+                        user_data.waiting_for_edit_description = false;
+                        user_data.waiting_for_edit_schedule = false;
+                        user_data.editing_alert_id = None;
+                        user_data.pending_rule_metric = None;
+                        user_data.pending_rule_op = None;
+                        user_data.pending_rule_join = None;
                         user_data.waiting_for_alert_hours_input = false;
+                        user_data.waiting_for_alert_schedule = false;
                         user_data.pending_alert_city = None;
                         user_data.pending_alert_type = None;
                         user_data.pending_alert_hours = None;
@@ -305,8 +423,9 @@ pub async fn callback_handler(bot: Bot, q: CallbackQuery, state: SharedState) ->
                     // This button does nothing, used as a separator
                 }
                 "alerts_menu" => {
+                    let user_data = get_user_data(&state, chat_id);
                     let keyboard = make_alerts_menu_keyboard(&state, chat_id);
-                    bot.send_message(chat_id, "🚨 Weather Alerts Management\n\nChoose an option:")
+                    bot.send_message(chat_id, format!("🚨 Weather Alerts Management\n{}\nChoose an option:", format_alerts_listing(&user_data.weather_alerts)))
                         .reply_markup(keyboard)
                         .await?;
                 }
@@ -316,6 +435,18 @@ pub async fn callback_handler(bot: Bot, q: CallbackQuery, state: SharedState) ->
                         .reply_markup(keyboard)
                         .await?;
                 }
+                "alert_presets" => {
+                    if alert_presets().is_empty() {
+                        bot.send_message(chat_id, "No ready-made alerts are available right now.")
+                            .reply_markup(make_add_alert_keyboard())
+                            .await?;
+                        return Ok(());
+                    }
+                    
+                    bot.send_message(chat_id, "📦 Ready-made alerts\n\nPick one and I'll set it up for your home town or a saved location:")
+                        .reply_markup(make_alert_presets_keyboard())
+                        .await?;
+                }
 
                 "remove_alert" => {
                     let user_data = get_user_data(&state, chat_id);
@@ -337,7 +468,7 @@ pub async fn callback_handler(bot: Bot, q: CallbackQuery, state: SharedState) ->
                 "add_standard_alert" => {
                     update_user_data(&state, chat_id, |user_data| {
                         user_data.waiting_for_alert_city = true;
-                        user_data.pending_alert_type = Some(AlertType::StandardWeatherAlert);
+                        user_data.pending_alert_type = Some(AlertType::StandardWeatherAlert { categories: SevereCategory::DEFAULTS.to_vec() });
                     });
                     
                     let cancel_keyboard = make_cancel_keyboard();
@@ -359,7 +490,7 @@ pub async fn callback_handler(bot: Bot, q: CallbackQuery, state: SharedState) ->
                 "add_wind_alert" => {
                     update_user_data(&state, chat_id, |user_data| {
                         user_data.waiting_for_alert_city = true;
-                        user_data.pending_alert_type = Some(AlertType::WindSpeed { max: 0.0 });
+                        user_data.pending_alert_type = Some(AlertType::WindSpeed { max: 0.0, use_gusts: false, direction: None });
                     });
                     
                     let cancel_keyboard = make_cancel_keyboard();
@@ -378,17 +509,306 @@ pub async fn callback_handler(bot: Bot, q: CallbackQuery, state: SharedState) ->
                         .reply_markup(cancel_keyboard)
                         .await?;
                 }
+                "add_precipitation_alert" => {
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.waiting_for_alert_city = true;
+                        user_data.pending_alert_type = Some(AlertType::Precipitation { min_chance: None, min_mm: None, kind: PrecipitationKind::Any });
+                    });
+                    
+                    let cancel_keyboard = make_cancel_keyboard();
+                    bot.send_message(chat_id, "Enter the city name for precipitation alerts:")
+                        .reply_markup(cancel_keyboard)
+                        .await?;
+                }
+                "add_uv_alert" => {
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.waiting_for_alert_city = true;
+                        user_data.pending_alert_type = Some(AlertType::UvIndex { max: 0.0 });
+                    });
+                    
+                    let cancel_keyboard = make_cancel_keyboard();
+                    bot.send_message(chat_id, "Enter the city name for UV index alerts:")
+                        .reply_markup(cancel_keyboard)
+                        .await?;
+                }
+                "add_air_quality_alert" => {
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.waiting_for_alert_city = true;
+                        user_data.pending_alert_type = Some(AlertType::AirQuality { min_epa_index: 3, max_pm2_5: None });
+                    });
+                    
+                    let cancel_keyboard = make_cancel_keyboard();
+                    bot.send_message(chat_id, "Enter the city name for air quality alerts:")
+                        .reply_markup(cancel_keyboard)
+                        .await?;
+                }
+                "add_pressure_drop_alert" => {
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.waiting_for_alert_city = true;
+                        user_data.pending_alert_type = Some(AlertType::PressureDrop { drop_hpa: 0.0, window_hours: 0 });
+                    });
+                    
+                    let cancel_keyboard = make_cancel_keyboard();
+                    bot.send_message(chat_id, "Enter the city name for pressure drop alerts:")
+                        .reply_markup(cancel_keyboard)
+                        .await?;
+                }
+                "add_frost_alert" => {
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.waiting_for_alert_city = true;
+                        user_data.pending_alert_type = Some(AlertType::Frost { max_temp: DEFAULT_FROST_AIR_TEMP_C });
+                    });
+                    
+                    let cancel_keyboard = make_cancel_keyboard();
+                    bot.send_message(chat_id, "Enter the city name for frost alerts:")
+                        .reply_markup(cancel_keyboard)
+                        .await?;
+                }
+                "add_snow_ice_alert" => {
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.waiting_for_alert_city = true;
+                        user_data.pending_alert_type = Some(AlertType::SnowIce { min_snow_cm: None, ice_risk: true });
+                    });
+                    
+                    let cancel_keyboard = make_cancel_keyboard();
+                    bot.send_message(chat_id, "Enter the city name for snow and road ice alerts:")
+                        .reply_markup(cancel_keyboard)
+                        .await?;
+                }
+                "snow_ice_yes" | "snow_ice_no" => {
+                    let user_data = get_user_data(&state, chat_id);
+                    if !user_data.waiting_for_alert_snow_ice {
+                        return Ok(());
+                    }
+                    
+                    let warn_about_ice = data == "snow_ice_yes";
+                    let has_snow_threshold = matches!(
+                        user_data.pending_alert_type,
+                        Some(AlertType::SnowIce { min_snow_cm: Some(_), .. })
+                    );
+                    if !warn_about_ice && !has_snow_threshold {
+                        bot.send_message(chat_id, "You skipped the snowfall threshold, so the alert needs ice warnings to be useful. Please choose again:")
+                            .reply_markup(make_snow_ice_keyboard())
+                            .await?;
+                        return Ok(());
+                    }
+                    
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.waiting_for_alert_snow_ice = false;
+                        user_data.waiting_for_alert_hours_input = true;
+                        if let Some(AlertType::SnowIce { ice_risk, .. }) = &mut user_data.pending_alert_type {
+                            *ice_risk = warn_about_ice;
+                        }
+                    });
+                    
+                    ask_alert_hours(&bot, &state, chat_id, "🕐 How many hours ahead should I warn you about snow and ice?\n\nEnter a number (1-72 hours):").await?;
+                }
+                "wind_gusts_yes" | "wind_gusts_no" => {
+                    let user_data = get_user_data(&state, chat_id);
+                    if !user_data.waiting_for_alert_wind_gusts {
+                        return Ok(());
+                    }
+                    
+                    let gusts = data == "wind_gusts_yes";
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.waiting_for_alert_wind_gusts = false;
+                        user_data.waiting_for_alert_wind_direction = true;
+                        if let Some(AlertType::WindSpeed { use_gusts, .. }) = &mut user_data.pending_alert_type {
+                            *use_gusts = gusts;
+                        }
+                    });
+                    
+                    bot.send_message(chat_id, "Only warn when the wind blows from a particular direction?")
+                        .reply_markup(make_wind_direction_keyboard())
+                        .await?;
+                }
+                "add_feels_like_alert" => {
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.waiting_for_alert_city = true;
+                        user_data.pending_alert_type = Some(AlertType::FeelsLike { min: None, max: None });
+                    });
+                    
+                    let cancel_keyboard = make_cancel_keyboard();
+                    bot.send_message(chat_id, "Enter the city name for feels-like temperature alerts:")
+                        .reply_markup(cancel_keyboard)
+                        .await?;
+                }
+                "add_composite_alert" => {
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.waiting_for_alert_city = true;
+                        user_data.pending_alert_type = Some(AlertType::Composite { rule: RuleExpr::default() });
+                    });
+                    
+                    let cancel_keyboard = make_cancel_keyboard();
+                    bot.send_message(chat_id, "Enter the city name for your custom rule alert:")
+                        .reply_markup(cancel_keyboard)
+                        .await?;
+                }
+                "std_cat_done" => {
+                    let user_data = get_user_data(&state, chat_id);
+                    if !user_data.waiting_for_alert_standard_categories {
+                        return Ok(());
+                    }
+                    if matches!(&user_data.pending_alert_type, Some(AlertType::StandardWeatherAlert { categories }) if categories.is_empty()) {
+                        bot.send_message(chat_id, "⚠️ Select at least one category.")
+                            .await?;
+                        return Ok(());
+                    }
+                    
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.waiting_for_alert_standard_categories = false;
+                        user_data.waiting_for_alert_hours_input = true;
+                    });
+                    
+                    ask_alert_hours(&bot, &state, chat_id, format!("🕐 How many hours ahead should I warn you about weather in '{}'?\n\nEnter a number (1-72 hours):", user_data.pending_alert_city.unwrap_or_default())).await?;
+                }
+                "add_temperature_swing_alert" => {
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.waiting_for_alert_city = true;
+                        user_data.pending_alert_type = Some(AlertType::TemperatureSwing { delta: 0.0, direction: SwingDirection::Drop, window_hours: None });
+                    });
+                    
+                    let cancel_keyboard = make_cancel_keyboard();
+                    bot.send_message(chat_id, "Enter the city name for temperature swing alerts:")
+                        .reply_markup(cancel_keyboard)
+                        .await?;
+                }
+                "add_good_weather_alert" => {
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.waiting_for_alert_city = true;
+                        user_data.pending_alert_type = Some(AlertType::GoodWeather { min_temp: 18.0, max_temp: 25.0, max_wind: 15.0, min_hours: 3 });
+                    });
+                    
+                    let cancel_keyboard = make_cancel_keyboard();
+                    bot.send_message(chat_id, "Enter the city name for good weather alerts:")
+                        .reply_markup(cancel_keyboard)
+                        .await?;
+                }
+                "schedule_default" => {
+                    let user_data = get_user_data(&state, chat_id);
+                    if user_data.waiting_for_edit_schedule {
+                        update_user_data(&state, chat_id, |user_data| {
+                            user_data.waiting_for_edit_schedule = false;
+                        });
+                        finish_alert_edit(&bot, &state, chat_id, |alert| {
+                            alert.check_interval_minutes = alert.alert_type.default_check_interval_minutes();
+                            alert.cooldown_minutes = alert.alert_type.default_cooldown_minutes();
+                        }).await?;
+                    } else if user_data.waiting_for_alert_schedule {
+                        create_pending_alert(&bot, &state, chat_id, AlertSchedule::default()).await?;
+                    }
+                }
+                "good_custom" => {
+                    let user_data = get_user_data(&state, chat_id);
+                    if !user_data.waiting_for_alert_good_preset {
+                        return Ok(());
+                    }
+                    
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.waiting_for_alert_good_preset = false;
+                        user_data.waiting_for_alert_good_temp_min = true;
+                    });
+                    
+                    bot.send_message(chat_id, "Enter the lowest comfortable temperature (°C):")
+                        .reply_markup(GOOD_TEMP_MIN_INPUT.keyboard())
+                        .await?;
+                }
+                "rule_and" | "rule_or" => {
+                    let user_data = get_user_data(&state, chat_id);
+                    if !user_data.waiting_for_alert_rule_next {
+                        return Ok(());
+                    }
+                    
+                    let join = if data == "rule_and" { RuleJoin::And } else { RuleJoin::Or };
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.waiting_for_alert_rule_next = false;
+                        user_data.waiting_for_alert_rule_metric = true;
+                        user_data.pending_rule_join = Some(join);
+                    });
+                    
+                    bot.send_message(chat_id, "Choose the metric for the next condition:")
+                        .reply_markup(make_rule_metric_keyboard())
+                        .await?;
+                }
+                "rule_not" => {
+                    let user_data = get_user_data(&state, chat_id);
+                    if !user_data.waiting_for_alert_rule_next {
+                        return Ok(());
+                    }
+                    
+                    update_user_data(&state, chat_id, |user_data| {
+                        if let Some(AlertType::Composite { rule }) = &mut user_data.pending_alert_type {
+                            *rule = std::mem::take(rule).negate_last();
+                        }
+                    });
+                    
+                    let user_data = get_user_data(&state, chat_id);
+                    if let Some(AlertType::Composite { rule }) = &user_data.pending_alert_type {
+                        bot.send_message(chat_id, format!("🧩 Rule so far:\n{}", rule.describe()))
+                            .reply_markup(make_rule_next_keyboard(rule))
+                            .await?;
+                    }
+                }
+                "rule_done" => {
+                    let user_data = get_user_data(&state, chat_id);
+                    if !user_data.waiting_for_alert_rule_next {
+                        return Ok(());
+                    }
+                    
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.waiting_for_alert_rule_next = false;
+                        user_data.waiting_for_alert_hours_input = true;
+                    });
+                    
+                    ask_alert_hours(&bot, &state, chat_id, "🕐 How many hours ahead should I check the rule?\n\nEnter a number (1-72 hours):").await?;
+                }
+                "feels_custom" => {
+                    let user_data = get_user_data(&state, chat_id);
+                    if !user_data.waiting_for_alert_feels_preset {
+                        return Ok(());
+                    }
+                    
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.waiting_for_alert_feels_preset = false;
+                        user_data.waiting_for_alert_feels_min = true;
+                    });
+                    
+                    bot.send_message(chat_id, "Enter the minimum feels-like temperature (°C), below which I should warn you, or type 'skip' to skip:")
+                        .reply_markup(FEELS_MIN_INPUT.keyboard())
+                        .await?;
+                }
+                "precip_kind_any" | "precip_kind_rain" | "precip_kind_snow" => {
+                    let user_data = get_user_data(&state, chat_id);
+                    if !user_data.waiting_for_alert_precip_kind {
+                        return Ok(());
+                    }
+                    
+                    let selected_kind = match data.as_str() {
+                        "precip_kind_rain" => PrecipitationKind::Rain,
+                        "precip_kind_snow" => PrecipitationKind::Snow,
+                        _ => PrecipitationKind::Any,
+                    };
+                    update_user_data(&state, chat_id, |user_data| {
+                        user_data.waiting_for_alert_precip_kind = false;
+                        user_data.waiting_for_alert_hours_input = true;
+                        if let Some(AlertType::Precipitation { kind, .. }) = &mut user_data.pending_alert_type {
+                            *kind = selected_kind;
+                        }
+                    });
+                    
+                    ask_alert_hours(&bot, &state, chat_id, format!("🕐 How many hours ahead should I warn you about {}?\n\nEnter a number (1-72 hours):", selected_kind.label())).await?;
+                }
                 _ => {
                     // Check if it's an interested town button (format: "town_<town_name>")
-                    if data.starts_with("town_") {
-                        let town_name = &data[5..]; // Remove "town_" prefix
+                    if let Some(town_name) = data.strip_prefix("town_") {
                         
                         // Send "typing" action while fetching weather
                         bot.send_chat_action(chat_id, teloxide::types::ChatAction::Typing).await?;
                         
                         match weather_api::get_current_weather(town_name).await {
                             Ok(weather) => {
-                                let weather_message = weather_api::format_current_weather(&weather);
+                                let warnings = weather_api::get_weather_warnings(town_name).await;
+                                let weather_message = weather_api::format_current_weather(&weather, &warnings);
                                 bot.send_message(chat_id, weather_message)
                                     .parse_mode(teloxide::types::ParseMode::MarkdownV2)
                                     .await?;
@@ -410,8 +830,7 @@ pub async fn callback_handler(bot: Bot, q: CallbackQuery, state: SharedState) ->
                                     .await?;
                             }
                         }
-                    } else if data.starts_with("remove_town_") {
-                        let town_name = &data[12..]; // Remove "remove_town_" prefix
+                    } else if let Some(town_name) = data.strip_prefix("remove_town_") {
                         
                         update_user_data(&state, chat_id, |user_data| {
                             user_data.interested_towns.retain(|town| town != town_name);
@@ -425,12 +844,14 @@ pub async fn callback_handler(bot: Bot, q: CallbackQuery, state: SharedState) ->
                         bot.send_message(chat_id, "Manage your towns:")
                             .reply_markup(keyboard)
                             .await?;
-                    } else if data.starts_with("remove_alert_") {
-                        let alert_id = &data[13..]; // Remove "remove_alert_" prefix
+                    } else if let Some(alert_id) = data.strip_prefix("remove_alert_") {
                         
                         update_user_data(&state, chat_id, |user_data| {
                             user_data.weather_alerts.retain(|alert| alert.id != alert_id);
                         });
+                        if let Err(e) = state.storage.remove_alert_history(chat_id, alert_id) {
+                            log::error!("Failed to remove history for alert {}: {}", alert_id, e);
+                        }
                         
                         bot.send_message(chat_id, "Alert removed successfully!")
                             .await?;
@@ -440,65 +861,279 @@ pub async fn callback_handler(bot: Bot, q: CallbackQuery, state: SharedState) ->
                         bot.send_message(chat_id, "Weather Alerts Management:")
                             .reply_markup(keyboard)
                             .await?;
-                    } else if data.starts_with("check_alert_") {
-                        let alert_id = &data[12..]; // Remove "check_alert_" prefix
-                        
+                    } else if let Some(alert_id) = data.strip_prefix("edit_alert_") {
                         let user_data = get_user_data(&state, chat_id);
-                        if let Some(alert) = user_data.weather_alerts.iter().find(|a| a.id == alert_id) {
-                            // Send "typing" action while checking alert
-                            bot.send_chat_action(chat_id, teloxide::types::ChatAction::Typing).await?;
-                            
-                            match crate::alerts::AlertChecker::check_current_alert(alert).await {
-                                Ok(is_triggered) => {
-                                    match weather_api::get_current_weather(&alert.city).await {
-                                        Ok(weather) => {
-                                            let status_emoji = if is_triggered { "🚨" } else { "✅" };
-                                            let status_text = if is_triggered { "ALERT TRIGGERED!" } else { "All Good" };
-                                            
-                                                                        let alert_type_str = match &alert.alert_type {
-                                AlertType::StandardWeatherAlert => "🚨 Standard Weather Alert".to_string(),
-                                AlertType::TemperatureThreshold { min, max } => {
-                                    let range = match (min, max) {
-                                        (Some(min_val), Some(max_val)) => format!("{}°C - {}°C", min_val, max_val),
-                                        (Some(min_val), None) => format!("min {}°C", min_val),
-                                        (None, Some(max_val)) => format!("max {}°C", max_val),
-                                        (None, None) => "Temperature".to_string(),
-                                    };
-                                    format!("🌡️ Temperature Alert ({})", range)
-                                },
-                                AlertType::WindSpeed { max } => format!("💨 Wind Speed Alert (max {} km/h)", max),
-                                AlertType::Humidity { min, max } => {
-                                    let range = match (min, max) {
-                                        (Some(min_val), Some(max_val)) => format!("{}% - {}%", min_val, max_val),
-                                        (Some(min_val), None) => format!("min {}%", min_val),
-                                        (None, Some(max_val)) => format!("max {}%", max_val),
-                                        (None, None) => "Humidity".to_string(),
-                                    };
-                                    format!("💧 Humidity Alert ({})", range)
-                                },
-                            };
-                                            
-                                                                        let message = format!(
-                                "{} <b>{}</b>\n\n{}\n\n📍 <b>City:</b> {}\n📝 <b>Description:</b> {}\n\n<b>Current Weather:</b>\n🌡️ Temperature: {}°C\n☁️ Condition: {}\n💨 Wind: {} km/h\n💧 Humidity: {}%\n\n⏰ Created: {}\n{}",
-                                status_emoji,
-                                status_text,
-                                alert_type_str,
-                                weather.location.name,
-                                alert.description,
-                                weather.current.temperature,
-                                weather.current.condition.text,
-                                weather.current.wind_speed,
-                                weather.current.humidity,
-                                alert.created_at.format("%Y-%m-%d %H:%M"),
-                                if let Some(last_triggered) = alert.last_triggered {
-                                    format!("🔔 Last triggered: {}", last_triggered.format("%Y-%m-%d %H:%M"))
-                                } else {
-                                    "🔔 Never triggered".to_string()
+                        match user_data.weather_alerts.iter().find(|a| a.id == alert_id) {
+                            Some(alert) => {
+                                bot.send_message(chat_id, format!("✏️ Editing alert\n\n{}\n📍 {}\n📝 {}\n🕐 {} hours ahead\n🔁 {}\n\nWhat would you like to change?", format_alert_type_details(&alert.alert_type), alert.city, alert.description, alert.hours_ahead, format_alert_schedule(alert)))
+                                    .reply_markup(make_edit_alert_keyboard(alert))
+                                    .await?;
+                            }
+                            None => {
+                                bot.send_message(chat_id, "Alert not found.")
+                                    .await?;
+                            }
+                        }
+                    } else if let Some((field, alert_id)) = data.strip_prefix("edit_")
+                        .and_then(|rest| rest.split_once('_'))
+                        .filter(|(field, _)| matches!(*field, "city" | "params" | "hours" | "desc" | "schedule")) {
+                        let user_data = get_user_data(&state, chat_id);
+                        let Some(alert) = user_data.weather_alerts.iter().find(|a| a.id == alert_id) else {
+                            bot.send_message(chat_id, "Alert not found.")
+                                .await?;
+                            return Ok(());
+                        };
+                        
+                        update_user_data(&state, chat_id, |user_data| {
+                            user_data.editing_alert_id = Some(alert.id.clone());
+                            match field {
+                                "city" => user_data.waiting_for_edit_city = true,
+                                "hours" => user_data.waiting_for_edit_hours = true,
+                                "desc" => user_data.waiting_for_edit_description = true,
+                                "schedule" => user_data.waiting_for_edit_schedule = true,
+                                _ => {
+                                    // Пороги задаются тем же мастером, что и при создании.
+                                    // Составное правило собирается заново, остальные шаги перезаписывают значения
+                                    user_data.pending_alert_city = Some(alert.city.clone());
+                                    user_data.pending_alert_type = Some(match &alert.alert_type {
+                                        AlertType::Composite { .. } => AlertType::Composite { rule: RuleExpr::default() },
+                                        other => other.clone(),
+                                    });
+                                }
+                            }
+                        });
+                        
+                        let cancel_keyboard = make_cancel_keyboard();
+                        match field {
+                            "city" => {
+                                bot.send_message(chat_id, format!("Current city: {}\n\nEnter the new city name:", alert.city))
+                                    .reply_markup(cancel_keyboard)
+                                    .await?;
+                            }
+                            "hours" => {
+                                bot.send_message(chat_id, format!("Currently warning {} hours ahead.\n\nEnter a new number (1-72 hours):", alert.hours_ahead))
+                                    .reply_markup(HOURS_INPUT.keyboard())
+                                    .await?;
+                            }
+                            "desc" => {
+                                bot.send_message(chat_id, format!("Current description: {}\n\nEnter a new description, or type 'auto' to generate it from the alert settings:", alert.description))
+                                    .reply_markup(cancel_keyboard)
+                                    .await?;
+                            }
+                            "schedule" => {
+                                bot.send_message(chat_id, format!("Currently: {}.\n\n{}\nParts you leave out stay as they are.", format_alert_schedule(alert), SCHEDULE_INPUT_HINT))
+                                    .reply_markup(make_schedule_keyboard("↩️ Reset to defaults"))
+                                    .await?;
+                            }
+                            _ => {
+                                ask_alert_params(&bot, &state, chat_id, &alert.city).await?;
+                            }
+                        }
+                    } else if let Some(alert_id) = data.strip_prefix("ack_alert_") {
+                        let mut pending = None;
+                        update_user_data(&state, chat_id, |user_data| {
+                            if let Some(alert) = user_data.weather_alerts.iter_mut().find(|a| a.id == alert_id) {
+                                let was_pending = alert.ack_requested_at.is_some();
+                                pending = Some((was_pending, alert.clear_ack_request()));
+                            }
+                        });
+
+                        let Some((was_pending, pinned_escalation)) = pending else {
+                            bot.send_message(chat_id, "Alert not found.")
+                                .await?;
+                            return Ok(());
+                        };
+
+                        // Кнопка "Got it" больше не нужна, закрепленное напоминание тоже
+                        bot.edit_message_reply_markup(chat_id, message.id())
+                            .reply_markup(make_delivered_alert_keyboard(alert_id, false))
+                            .await?;
+                        unpin_escalation(&bot, chat_id, alert_id, pinned_escalation).await;
+                        if !was_pending {
+                            bot.send_message(chat_id, "This alert is already acknowledged.")
+                                .await?;
+                            return Ok(());
+                        }
+
+                        let acknowledged_by = match &q.from.username {
+                            Some(username) => format!("{} (@{})", q.from.full_name(), username),
+                            None => q.from.full_name(),
+                        };
+                        let entry = crate::state::AlertHistoryEntry {
+                            timestamp: chrono::Utc::now(),
+                            kind: crate::state::AlertEventKind::Acknowledged,
+                            target_time: None,
+                            observed: Vec::new(),
+                            delivered: true,
+                            error: None,
+                            acknowledged_by: Some(acknowledged_by.clone()),
+                        };
+                        if let Err(e) = state.storage.record_alert_event(chat_id, alert_id, &entry) {
+                            log::error!("Failed to record acknowledgment for alert {}: {}", alert_id, e);
+                        }
+                        
+                        bot.send_message(chat_id, format!("✅ Got it, {}. I won't remind you about this alert.", acknowledged_by))
+                            .await?;
+                    } else if let Some(alert_id) = data.strip_prefix("toggle_alert_") {
+                        let mut toggled = None;
+                        let mut pinned_escalation = None;
+                        update_user_data(&state, chat_id, |user_data| {
+                            if let Some(alert) = user_data.weather_alerts.iter_mut().find(|a| a.id == alert_id) {
+                                alert.is_active = !alert.is_active;
+                                alert.snoozed_until = None;
+                                // Уведомление явно увидели - напоминание о нем не нужно
+                                pinned_escalation = alert.clear_ack_request();
+                                toggled = Some(alert.clone());
+                            }
+                        });
+                        unpin_escalation(&bot, chat_id, alert_id, pinned_escalation).await;
+                        
+                        match toggled {
+                            Some(alert) => {
+                                let text = if alert.is_active {
+                                    format!("▶️ Alert resumed: {}", alert.description)
+                                } else {
+                                    format!("⏸️ Alert paused: {}\n\nNo checks or notifications until you resume it.", alert.description)
+                                };
+                                bot.send_message(chat_id, text)
+                                    .reply_markup(make_alert_details_keyboard(&alert))
+                                    .await?;
+                            }
+                            None => {
+                                bot.send_message(chat_id, "Alert not found.")
+                                    .await?;
+                            }
+                        }
+                    } else if let Some(alert_id) = data.strip_prefix("pause_alert_") {
+                        // Кнопка под доставленным уведомлением только ставит на паузу: повторное нажатие
+                        // (или нажатие под старым сообщением) не должно снова включать алерт
+                        let mut paused = None;
+                        let mut pinned_escalation = None;
+                        update_user_data(&state, chat_id, |user_data| {
+                            if let Some(alert) = user_data.weather_alerts.iter_mut().find(|a| a.id == alert_id) {
+                                let was_active = alert.is_active;
+                                alert.is_active = false;
+                                alert.snoozed_until = None;
+                                pinned_escalation = alert.clear_ack_request();
+                                paused = Some((alert.clone(), was_active));
+                            }
+                        });
+                        unpin_escalation(&bot, chat_id, alert_id, pinned_escalation).await;
+                        
+                        match paused {
+                            Some((alert, true)) => {
+                                bot.send_message(chat_id, format!("⏸️ Alert paused: {}\n\nNo checks or notifications until you resume it.", alert.description))
+                                    .reply_markup(make_alert_details_keyboard(&alert))
+                                    .await?;
+                            }
+                            Some((alert, false)) => {
+                                bot.send_message(chat_id, format!("⏸️ This alert is already paused: {}", alert.description))
+                                    .reply_markup(make_alert_details_keyboard(&alert))
+                                    .await?;
+                            }
+                            None => {
+                                bot.send_message(chat_id, "Alert not found.")
+                                    .await?;
+                            }
+                        }
+                    } else if let Some((period, alert_id)) = data.strip_prefix("snooze_").and_then(|rest| rest.split_once('_')) {
+                        let user_data = get_user_data(&state, chat_id);
+                        let Some(alert) = user_data.weather_alerts.iter().find(|a| a.id == alert_id) else {
+                            bot.send_message(chat_id, "Alert not found.")
+                                .await?;
+                            return Ok(());
+                        };
+                        
+                        let now = chrono::Utc::now();
+                        let (until, period_text) = match period {
+                            "6h" => (now + chrono::Duration::hours(6), "for 6 hours".to_string()),
+                            "24h" => (now + chrono::Duration::hours(24), "for 24 hours".to_string()),
+                            _ => match next_local_morning(&alert.city).await {
+                                Some(morning) => (
+                                    morning.with_timezone(&chrono::Utc),
+                                    format!("until {} ({} time)", morning.format("%a %d %b %H:%M"), alert.city),
+                                ),
+                                None => (now + chrono::Duration::hours(24), "for 24 hours".to_string()),
+                            },
+                        };
+                        
+                        let mut snoozed = None;
+                        let mut pinned_escalation = None;
+                        update_user_data(&state, chat_id, |user_data| {
+                            if let Some(alert) = user_data.weather_alerts.iter_mut().find(|a| a.id == alert_id) {
+                                alert.is_active = true;
+                                alert.snoozed_until = Some(until);
+                                pinned_escalation = alert.clear_ack_request();
+                                snoozed = Some(alert.clone());
+                            }
+                        });
+                        unpin_escalation(&bot, chat_id, alert_id, pinned_escalation).await;
+                        
+                        if let Some(alert) = snoozed {
+                            bot.send_message(chat_id, format!("😴 Alert snoozed {}: {}", period_text, alert.description))
+                                .reply_markup(make_alert_details_keyboard(&alert))
+                                .await?;
+                        }
+                    } else if let Some(alert_id) = data.strip_prefix("resume_alert_") {
+                        let mut resumed = None;
+                        update_user_data(&state, chat_id, |user_data| {
+                            if let Some(alert) = user_data.weather_alerts.iter_mut().find(|a| a.id == alert_id) {
+                                alert.is_active = true;
+                                alert.snoozed_until = None;
+                                resumed = Some(alert.clone());
+                            }
+                        });
+                        
+                        if let Some(alert) = resumed {
+                            bot.send_message(chat_id, format!("🔔 Alert resumed: {}", alert.description))
+                                .reply_markup(make_alert_details_keyboard(&alert))
+                                .await?;
+                        }
+                    } else if let Some(alert_id) = data.strip_prefix("check_alert_") {
+                        
+                        let user_data = get_user_data(&state, chat_id);
+                        if let Some(alert) = user_data.weather_alerts.iter().find(|a| a.id == alert_id) {
+                            // Send "typing" action while checking alert
+                            bot.send_chat_action(chat_id, teloxide::types::ChatAction::Typing).await?;
+                            
+                            match crate::alerts::AlertChecker::check_current_alert(alert).await {
+                                Ok(is_triggered) => {
+                                    match weather_api::get_current_weather(&alert.city).await {
+                                        Ok(weather) => {
+                                            let status_emoji = if is_triggered { "🚨" } else { "✅" };
+                                            let status_text = if is_triggered { "ALERT TRIGGERED!" } else { "All Good" };
+                                            
+                                                                        let alert_type_str = format_alert_type_details(&alert.alert_type);
+                                            
+                                                                        let message = format!(
+                                "{} <b>{}</b>\n\n{}\n\n📍 <b>City:</b> {}\n📝 <b>Description:</b> {}{}\n\n<b>Current Weather:</b>\n🌡️ Temperature: {}°C\n☁️ Condition: {}\n💨 Wind: {} km/h\n💧 Humidity: {}%\n\n📊 State: {}\n⏯️ Status: {}\n🔁 Checked: {}\n🔕 Repeat notifications: {}\n⏰ Created: {}\n{}",
+                                status_emoji,
+                                status_text,
+                                teloxide::utils::html::escape(&alert_type_str),
+                                weather.location.name,
+                                teloxide::utils::html::escape(&alert.description),
+                                format_alert_command(alert)
+                                    .map(|command| format!("\n⌨️ <b>Command:</b> <code>{}</code>", teloxide::utils::html::escape(&command)))
+                                    .unwrap_or_default(),
+                                weather.current.temperature,
+                                weather.current.condition.text,
+                                weather.current.wind_speed,
+                                weather.current.humidity,
+                                alert.state.label(),
+                                format_alert_run_status(alert),
+                                format_minutes_setting(alert.check_interval_minutes, "every", "default schedule"),
+                                format_minutes_setting(alert.cooldown_minutes, "at most once per", "default cooldown"),
+                                alert.created_at.format("%Y-%m-%d %H:%M"),
+                                if let Some(last_triggered) = alert.last_triggered {
+                                    format!("🔔 Last triggered: {}", last_triggered.format("%Y-%m-%d %H:%M"))
+                                } else {
+                                    "🔔 Never triggered".to_string()
                                 }
                             );
                                             
                                                                         bot.send_message(chat_id, message)
                                 .parse_mode(teloxide::types::ParseMode::Html)
+                                .reply_markup(make_alert_details_keyboard(alert))
                                 .await?;
                                         }
                                         Err(e) => {
@@ -523,6 +1158,218 @@ pub async fn callback_handler(bot: Bot, q: CallbackQuery, state: SharedState) ->
                             .reply_markup(keyboard)
                             .await?;
 
+                    } else if let Some((alert_id, page)) = data.strip_prefix("alert_history_")
+                        .and_then(|rest| rest.rsplit_once('_'))
+                        .and_then(|(alert_id, page)| page.parse::<usize>().ok().map(|page| (alert_id, page))) {
+                        let user_data = get_user_data(&state, chat_id);
+                        let alert_description = user_data.weather_alerts.iter()
+                            .find(|a| a.id == alert_id)
+                            .map(|a| a.description.clone())
+                            .unwrap_or_default();
+                        
+                        // Ошибку Sled переводим в строку сразу: Box<dyn Error> нельзя держать через await
+                        let history = state.storage.load_alert_history(chat_id, alert_id, page * HISTORY_PAGE_SIZE, HISTORY_PAGE_SIZE)
+                            .map_err(|e| e.to_string());
+                        match history {
+                            Ok((entries, total)) => {
+                                let message = format_alert_history(&alert_description, &entries, page, total);
+                                bot.send_message(chat_id, message)
+                                    .reply_markup(make_alert_history_keyboard(alert_id, page, total))
+                                    .await?;
+                            }
+                            Err(e) => {
+                                bot.send_message(chat_id, format!("❌ Failed to load alert history: {}", e))
+                                    .await?;
+                            }
+                        }
+                    } else if let Some(metric) = data.strip_prefix("rule_metric_").and_then(Metric::from_code) {
+                        let user_data = get_user_data(&state, chat_id);
+                        if !user_data.waiting_for_alert_rule_metric {
+                            return Ok(());
+                        }
+                        
+                        update_user_data(&state, chat_id, |user_data| {
+                            user_data.waiting_for_alert_rule_metric = false;
+                            user_data.waiting_for_alert_rule_op = true;
+                            user_data.pending_rule_metric = Some(metric);
+                        });
+                        
+                        bot.send_message(chat_id, format!("{}: choose the comparison:", metric.label()))
+                            .reply_markup(make_rule_op_keyboard())
+                            .await?;
+                    } else if let Some(op) = data.strip_prefix("rule_op_").and_then(Comparison::from_code) {
+                        let user_data = get_user_data(&state, chat_id);
+                        if !user_data.waiting_for_alert_rule_op {
+                            return Ok(());
+                        }
+                        let Some(metric) = user_data.pending_rule_metric else {
+                            return Ok(());
+                        };
+                        
+                        update_user_data(&state, chat_id, |user_data| {
+                            user_data.waiting_for_alert_rule_op = false;
+                            user_data.waiting_for_alert_rule_value = true;
+                            user_data.pending_rule_op = Some(op);
+                        });
+                        
+                        let input = rule_value_input(metric);
+                        bot.send_message(chat_id, format!("Enter the value for: {} {} …\n\nAllowed: {} to {}{}", metric.code(), op.symbol(), input.min, input.max, input.unit))
+                            .reply_markup(input.keyboard())
+                            .await?;
+                    } else if let Some(preset) = data.strip_prefix("feels_preset_").and_then(feels_like_preset) {
+                        let user_data = get_user_data(&state, chat_id);
+                        if !user_data.waiting_for_alert_feels_preset {
+                            return Ok(());
+                        }
+                        
+                        update_user_data(&state, chat_id, |user_data| {
+                            user_data.waiting_for_alert_feels_preset = false;
+                            user_data.waiting_for_alert_hours_input = true;
+                            if let Some(AlertType::FeelsLike { min, max }) = &mut user_data.pending_alert_type {
+                                *min = preset.min;
+                                *max = preset.max;
+                            }
+                        });
+                        
+                        ask_alert_hours(&bot, &state, chat_id, format!("{} selected.\n\n🕐 How many hours ahead should I warn you?\n\nEnter a number (1-72 hours):", preset.label)).await?;
+                    } else if let Some(preset) = data.strip_prefix("good_preset_").and_then(good_weather_preset) {
+                        let user_data = get_user_data(&state, chat_id);
+                        if !user_data.waiting_for_alert_good_preset {
+                            return Ok(());
+                        }
+                        
+                        update_user_data(&state, chat_id, |user_data| {
+                            user_data.waiting_for_alert_good_preset = false;
+                            user_data.waiting_for_alert_good_window = true;
+                            if let Some(AlertType::GoodWeather { min_temp, max_temp, max_wind, .. }) = &mut user_data.pending_alert_type {
+                                *min_temp = preset.min_temp;
+                                *max_temp = preset.max_temp;
+                                *max_wind = preset.max_wind;
+                            }
+                        });
+                        
+                        bot.send_message(chat_id, format!("{} selected.\n\nHow many dry, pleasant hours in a row do you need?", preset.label))
+                            .reply_markup(GOOD_WINDOW_INPUT.keyboard())
+                            .await?;
+                    } else if let Some(category) = data.strip_prefix("std_cat_").and_then(SevereCategory::from_code) {
+                        let user_data = get_user_data(&state, chat_id);
+                        if !user_data.waiting_for_alert_standard_categories {
+                            return Ok(());
+                        }
+                        
+                        let mut selected = Vec::new();
+                        update_user_data(&state, chat_id, |user_data| {
+                            if let Some(AlertType::StandardWeatherAlert { categories }) = &mut user_data.pending_alert_type {
+                                if let Some(index) = categories.iter().position(|c| *c == category) {
+                                    categories.remove(index);
+                                } else {
+                                    categories.push(category);
+                                    categories.sort();
+                                }
+                                selected = categories.clone();
+                            }
+                        });
+                        
+                        // Обновляем отметки на той же клавиатуре, чтобы не плодить сообщения
+                        bot.edit_message_reply_markup(chat_id, message.id())
+                            .reply_markup(make_standard_categories_keyboard(&selected))
+                            .await?;
+                    } else if let Some(swing) = data.strip_prefix("swing_dir_").and_then(SwingDirection::from_code) {
+                        let user_data = get_user_data(&state, chat_id);
+                        if !user_data.waiting_for_alert_swing_direction {
+                            return Ok(());
+                        }
+                        
+                        update_user_data(&state, chat_id, |user_data| {
+                            user_data.waiting_for_alert_swing_direction = false;
+                            user_data.waiting_for_alert_swing_mode = true;
+                            if let Some(AlertType::TemperatureSwing { direction, .. }) = &mut user_data.pending_alert_type {
+                                *direction = swing;
+                            }
+                        });
+                        
+                        bot.send_message(chat_id, "What should I compare?\n\nBoth modes use the forecast only: day mode compares each forecast day with the forecast for the day before (today included), not with the weather observed so far.")
+                            .reply_markup(make_swing_mode_keyboard())
+                            .await?;
+                    } else if let Some(mode) = data.strip_prefix("swing_mode_") {
+                        let user_data = get_user_data(&state, chat_id);
+                        if !user_data.waiting_for_alert_swing_mode {
+                            return Ok(());
+                        }
+                        
+                        // "swing_mode_day" - сравнение день к дню, иначе - окно в часах
+                        let window = mode.parse::<u8>().ok().filter(|window| (1..=24).contains(window));
+                        update_user_data(&state, chat_id, |user_data| {
+                            user_data.waiting_for_alert_swing_mode = false;
+                            user_data.waiting_for_alert_hours_input = true;
+                            if let Some(AlertType::TemperatureSwing { window_hours, .. }) = &mut user_data.pending_alert_type {
+                                *window_hours = window;
+                            }
+                        });
+                        
+                        let prompt = if window.is_some() {
+                            "🕐 How many hours ahead should I watch for the swing?\n\nEnter a number (1-72 hours):"
+                        } else {
+                            "🕐 How many days ahead should I compare?\n\nEnter a number of hours (1-72): 24 compares tomorrow's forecast with today's forecast, 48 also checks the day after."
+                        };
+                        ask_alert_hours(&bot, &state, chat_id, prompt).await?;
+                    } else if let Some(preset) = data.strip_prefix("alert_preset_").and_then(find_preset) {
+                        let user_data = get_user_data(&state, chat_id);
+                        let towns = preset_locations(&user_data);
+                        match towns.as_slice() {
+                            [] => {
+                                bot.send_message(chat_id, "Set your home town or add one in Interested towns first, then pick the preset again.")
+                                    .reply_markup(make_alert_presets_keyboard())
+                                    .await?;
+                            }
+                            // Единственный город - создаем алерт сразу, в одно нажатие
+                            [(_, city, _)] => {
+                                apply_alert_preset(&bot, &state, chat_id, preset, city).await?;
+                            }
+                            _ => {
+                                bot.send_message(chat_id, format!("{}\n\nWhich location should it watch?", preset.title))
+                                    .reply_markup(make_preset_location_keyboard(preset, &towns))
+                                    .await?;
+                            }
+                        }
+                    } else if let Some((town, preset)) = data.strip_prefix("apply_preset_")
+                        .and_then(|rest| rest.split_once('_'))
+                        .and_then(|(town, id)| find_preset(id).map(|preset| (town, preset))) {
+                        let user_data = get_user_data(&state, chat_id);
+                        match preset_locations(&user_data).into_iter().find(|(key, _, _)| key == town) {
+                            Some((_, city, _)) => apply_alert_preset(&bot, &state, chat_id, preset, &city).await?,
+                            None => {
+                                bot.send_message(chat_id, "This location is no longer saved. Please pick the preset again.")
+                                    .reply_markup(make_alert_presets_keyboard())
+                                    .await?;
+                            }
+                        }
+                    } else if let Some(value) = data.strip_prefix("input_") {
+                        // Кнопка с готовым значением - тот же ответ, что и набранный текст.
+                        // Устаревшие кнопки (шаг уже пройден) игнорируются
+                        let user_data = get_user_data(&state, chat_id);
+                        if awaited_numeric_input(&user_data).is_none() {
+                            return Ok(());
+                        }
+                        
+                        handle_text_input(&bot, &state, chat_id, value).await?;
+                    } else if let Some(code) = data.strip_prefix("wind_dir_") {
+                        let user_data = get_user_data(&state, chat_id);
+                        if !user_data.waiting_for_alert_wind_direction {
+                            return Ok(());
+                        }
+                        
+                        // "wind_dir_any" - без фильтра направления
+                        let sector = WindSector::from_code(code);
+                        update_user_data(&state, chat_id, |user_data| {
+                            user_data.waiting_for_alert_wind_direction = false;
+                            user_data.waiting_for_alert_hours_input = true;
+                            if let Some(AlertType::WindSpeed { direction, .. }) = &mut user_data.pending_alert_type {
+                                *direction = sector;
+                            }
+                        });
+                        
+                        ask_alert_hours(&bot, &state, chat_id, "🕐 How many hours ahead should I warn you about wind conditions?\n\nEnter a number (1-72 hours):").await?;
                     } else {
                     bot.send_message(chat_id, "Unknown button.")
                         .await?;
@@ -543,361 +1390,1396 @@ pub async fn callback_handler(bot: Bot, q: CallbackQuery, state: SharedState) ->
 
 pub async fn message_handler(bot: Bot, msg: Message, state: SharedState) -> HandlerResult {
     if let Some(text) = msg.text() {
-        let chat_id = msg.chat.id;
-        let user_data = get_user_data(&state, chat_id);
+        handle_text_input(&bot, &state, msg.chat.id, text).await?;
+    }
+    
+    Ok(())
+}
+
+/// Ответ пользователя на текущем шаге диалога. Кнопки с готовыми значениями (input_*) приходят сюда же,
+/// поэтому проверки одинаковы для набранного текста и для кнопок
+async fn handle_text_input(bot: &Bot, state: &SharedState, chat_id: ChatId, text: &str) -> HandlerResult {
+    let user_data = get_user_data(state, chat_id);
+    
+    // Check if user is waiting for home town input
+    if user_data.waiting_for_home_town {
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_home_town = false;
+            user_data.home_town = Some(text.to_string());
+        });
         
-        // Check if user is waiting for home town input
-        if user_data.waiting_for_home_town {
-            update_user_data(&state, chat_id, |user_data| {
-                user_data.waiting_for_home_town = false;
-                user_data.home_town = Some(text.to_string());
-            });
-            
-            bot.send_message(chat_id, format!("Home town set to: {}", text))
-                .await?;
-            
-            // Отправляем главное меню для удобства
-            let keyboard = make_main_menu_keyboard(&state, chat_id);
-            bot.send_message(chat_id, "Choose another option:")
-                .reply_markup(keyboard)
-                .await?;
+        bot.send_message(chat_id, format!("Home town set to: {}", text))
+            .await?;
+        
+        // Отправляем главное меню для удобства
+        let keyboard = make_main_menu_keyboard(state, chat_id);
+        bot.send_message(chat_id, "Choose another option:")
+            .reply_markup(keyboard)
+            .await?;
+    }
+    // Check if user is waiting for interested town input
+    else if user_data.waiting_for_interested_town {
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_interested_town = false;
+            if !user_data.interested_towns.contains(&text.to_string()) {
+                user_data.interested_towns.push(text.to_string());
+            }
+        });
+        
+        bot.send_message(chat_id, format!("Added '{}' to your interested towns", text))
+            .await?;
+        
+        // Возвращаемся в interested towns меню
+        let keyboard = make_my_towns_keyboard(state, chat_id);
+        bot.send_message(chat_id, "Manage your towns:")
+            .reply_markup(keyboard)
+            .await?;
+    }
+            // Check if user is waiting for city input (current weather only)
+    else if user_data.waiting_for_city {
+        // Reset the waiting state
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_city = false;
+        });
+        
+        // Send "typing" action while fetching weather
+        bot.send_chat_action(chat_id, teloxide::types::ChatAction::Typing).await?;
+        
+        // Fetch ONLY current weather
+        match weather_api::get_current_weather(text).await {
+            Ok(weather) => {
+                let warnings = weather_api::get_weather_warnings(text).await;
+                let weather_message = weather_api::format_current_weather(&weather, &warnings);
+                bot.send_message(chat_id, weather_message)
+                    .parse_mode(teloxide::types::ParseMode::MarkdownV2)
+                    .await?;
+            }
+            Err(e) => {
+                bot.send_message(chat_id, format!("Sorry, I couldn't get the weather for '{}'. Please check the city name and try again.\n\nError: {}", text, e))
+                    .await?;
+            }
         }
-        // Check if user is waiting for interested town input
-        else if user_data.waiting_for_interested_town {
-            update_user_data(&state, chat_id, |user_data| {
-                user_data.waiting_for_interested_town = false;
-                if !user_data.interested_towns.contains(&text.to_string()) {
-                    user_data.interested_towns.push(text.to_string());
-                }
-            });
-            
-            bot.send_message(chat_id, format!("Added '{}' to your interested towns", text))
-                .await?;
-            
-            // Возвращаемся в interested towns меню
-            let keyboard = make_my_towns_keyboard(&state, chat_id);
-            bot.send_message(chat_id, "Manage your towns:")
-                .reply_markup(keyboard)
-                .await?;
+        
+        // Return to main menu after weather
+        let keyboard = make_main_menu_keyboard(state, chat_id);
+        bot.send_message(chat_id, "Choose another option:")
+            .reply_markup(keyboard)
+            .await?;
+    }
+    // Check if user is waiting for forecast city input
+    else if user_data.waiting_for_forecast_city {
+        // Reset the waiting state
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_forecast_city = false;
+        });
+        
+        // Send "typing" action while fetching forecast
+        bot.send_chat_action(chat_id, teloxide::types::ChatAction::Typing).await?;
+        
+        // Fetch 3-day forecast  
+        match weather_api::get_forecast_with_warnings(text, 3).await {
+            Ok(forecast) => {
+                let forecast_message = weather_api::format_forecast(&forecast);
+                bot.send_message(chat_id, forecast_message)
+                    .parse_mode(teloxide::types::ParseMode::MarkdownV2)
+                    .await?;
+            }
+            Err(e) => {
+                bot.send_message(chat_id, format!("Sorry, I couldn't get the forecast for '{}'. Error: {}", text, e))
+                    .await?;
+            }
         }
-                // Check if user is waiting for city input (current weather only)
-        else if user_data.waiting_for_city {
-            // Reset the waiting state
-            update_user_data(&state, chat_id, |user_data| {
-                user_data.waiting_for_city = false;
-            });
-            
-            // Send "typing" action while fetching weather
-            bot.send_chat_action(chat_id, teloxide::types::ChatAction::Typing).await?;
-            
-            // Fetch ONLY current weather
-            match weather_api::get_current_weather(text).await {
-                Ok(weather) => {
-                    let weather_message = weather_api::format_current_weather(&weather);
-                    bot.send_message(chat_id, weather_message)
-                        .parse_mode(teloxide::types::ParseMode::MarkdownV2)
-                        .await?;
-                }
-                Err(e) => {
-                    bot.send_message(chat_id, format!("Sorry, I couldn't get the weather for '{}'. Please check the city name and try again.\n\nError: {}", text, e))
-                        .await?;
-                }
+        
+        // Return to main menu after forecast
+        let keyboard = make_main_menu_keyboard(state, chat_id);
+        bot.send_message(chat_id, "Choose another option:")
+            .reply_markup(keyboard)
+            .await?;
+    }
+    // Check if user is waiting for alert city input
+    else if user_data.waiting_for_alert_city {
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_alert_city = false;
+            user_data.pending_alert_city = Some(text.to_string());
+            // Город спрашивается только при создании - это новый алерт, а не правка
+            user_data.editing_alert_id = None;
+        });
+        
+        ask_alert_params(bot, state, chat_id, text).await?;
+    }
+    // Handle temperature alert parameters
+    else if user_data.waiting_for_alert_temperature_min {
+        let min_temp = match TEMPERATURE_MIN_INPUT.parse(text) {
+            Ok(value) => value,
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(TEMPERATURE_MIN_INPUT.keyboard())
+                    .await?;
+                return Ok(());
+            }
+        };
+        
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_alert_temperature_min = false;
+            user_data.waiting_for_alert_temperature_max = true;
+            if let Some(AlertType::TemperatureThreshold { min, .. }) = &mut user_data.pending_alert_type {
+                *min = min_temp;
+            }
+        });
+        
+        bot.send_message(chat_id, "Enter maximum temperature threshold (°C) or type 'skip' to skip:")
+            .reply_markup(TEMPERATURE_MAX_INPUT.keyboard())
+            .await?;
+    }
+    else if user_data.waiting_for_alert_temperature_max {
+        let max_temp = match TEMPERATURE_MAX_INPUT.parse(text) {
+            Ok(value) => value,
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(TEMPERATURE_MAX_INPUT.keyboard())
+                    .await?;
+                return Ok(());
+            }
+        };
+        if let (Some(AlertType::TemperatureThreshold { min: Some(min), .. }), Some(max)) = (&user_data.pending_alert_type, max_temp) {
+            if max <= *min {
+                bot.send_message(chat_id, format!("The maximum must be above the minimum ({}°C). Please enter it again:", min))
+                    .reply_markup(TEMPERATURE_MAX_INPUT.keyboard())
+                    .await?;
+                return Ok(());
             }
-            
-            // Return to main menu after weather
-            let keyboard = make_main_menu_keyboard(&state, chat_id);
-            bot.send_message(chat_id, "Choose another option:")
-                .reply_markup(keyboard)
-                .await?;
         }
-        // Check if user is waiting for forecast city input
-        else if user_data.waiting_for_forecast_city {
-            // Reset the waiting state
-            update_user_data(&state, chat_id, |user_data| {
-                user_data.waiting_for_forecast_city = false;
-            });
-            
-            // Send "typing" action while fetching forecast
-            bot.send_chat_action(chat_id, teloxide::types::ChatAction::Typing).await?;
-            
-            // Fetch 3-day forecast  
-            match weather_api::get_forecast(text, 3).await {
-                Ok(forecast) => {
-                    let forecast_message = weather_api::format_forecast(&forecast);
-                    bot.send_message(chat_id, forecast_message)
-                        .parse_mode(teloxide::types::ParseMode::MarkdownV2)
-                        .await?;
-                }
-                Err(e) => {
-                    bot.send_message(chat_id, format!("Sorry, I couldn't get the forecast for '{}'. Error: {}", text, e))
-                        .await?;
-                }
+        
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_alert_temperature_max = false;
+            user_data.waiting_for_alert_hours_input = true;
+            if let Some(AlertType::TemperatureThreshold { max, .. }) = &mut user_data.pending_alert_type {
+                *max = max_temp;
             }
-            
-            // Return to main menu after forecast
-            let keyboard = make_main_menu_keyboard(&state, chat_id);
-            bot.send_message(chat_id, "Choose another option:")
-                .reply_markup(keyboard)
+        });
+        
+        ask_alert_hours(bot, state, chat_id, "🕐 How many hours ahead should I warn you about temperature changes?\n\nEnter a number (1-72 hours):").await?;
+    }
+    // Handle wind speed alert
+    else if user_data.waiting_for_alert_wind_speed {
+        match WIND_INPUT.parse_value(text) {
+            Ok(wind_speed) => {
+                update_user_data(state, chat_id, |user_data| {
+                    user_data.waiting_for_alert_wind_speed = false;
+                    user_data.waiting_for_alert_wind_gusts = true;
+                    if let Some(AlertType::WindSpeed { max, .. }) = &mut user_data.pending_alert_type {
+                        *max = wind_speed;
+                    }
+                });
+                
+                bot.send_message(chat_id, "Should the threshold apply to sustained wind or to gusts?")
+                    .reply_markup(make_wind_gusts_keyboard())
+                    .await?;
+            }
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(WIND_INPUT.keyboard())
+                    .await?;
+            }
+        }
+    }
+    // Handle humidity alert parameters
+    else if user_data.waiting_for_alert_humidity_min {
+        let min_humidity = match HUMIDITY_MIN_INPUT.parse(text) {
+            Ok(value) => value.map(|humidity| humidity as u32),
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(HUMIDITY_MIN_INPUT.keyboard())
+                    .await?;
+                return Ok(());
+            }
+        };
+        
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_alert_humidity_min = false;
+            user_data.waiting_for_alert_humidity_max = true;
+            if let Some(AlertType::Humidity { min, .. }) = &mut user_data.pending_alert_type {
+                *min = min_humidity;
+            }
+        });
+        
+        bot.send_message(chat_id, "Enter maximum humidity threshold (%) or type 'skip' to skip:")
+            .reply_markup(HUMIDITY_MAX_INPUT.keyboard())
+            .await?;
+    }
+    else if user_data.waiting_for_alert_humidity_max {
+        let max_humidity = match HUMIDITY_MAX_INPUT.parse(text) {
+            Ok(value) => value.map(|humidity| humidity as u32),
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(HUMIDITY_MAX_INPUT.keyboard())
+                    .await?;
+                return Ok(());
+            }
+        };
+        if let (Some(AlertType::Humidity { min: Some(min), .. }), Some(max)) = (&user_data.pending_alert_type, max_humidity) {
+            if max <= *min {
+                bot.send_message(chat_id, format!("The maximum must be above the minimum ({}%). Please enter it again:", min))
+                    .reply_markup(HUMIDITY_MAX_INPUT.keyboard())
+                    .await?;
+                return Ok(());
+            }
+        }
+        
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_alert_humidity_max = false;
+            user_data.waiting_for_alert_hours_input = true;
+            if let Some(AlertType::Humidity { max, .. }) = &mut user_data.pending_alert_type {
+                *max = max_humidity;
+            }
+        });
+        
+        ask_alert_hours(bot, state, chat_id, "🕐 How many hours ahead should I warn you about humidity changes?\n\nEnter a number (1-72 hours):").await?;
+    }
+    // Handle precipitation alert parameters
+    else if user_data.waiting_for_alert_precip_chance {
+        let min_chance = match PRECIP_CHANCE_INPUT.parse(text) {
+            Ok(value) => value.map(|chance| chance as u32),
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(PRECIP_CHANCE_INPUT.keyboard())
+                    .await?;
+                return Ok(());
+            }
+        };
+        
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_alert_precip_chance = false;
+            user_data.waiting_for_alert_precip_mm = true;
+            if let Some(AlertType::Precipitation { min_chance: chance, .. }) = &mut user_data.pending_alert_type {
+                *chance = min_chance;
+            }
+        });
+        
+        bot.send_message(chat_id, "Enter the minimum expected amount of precipitation (mm) or type 'skip' to skip:")
+            .reply_markup(PRECIP_MM_INPUT.keyboard())
+            .await?;
+    }
+    else if user_data.waiting_for_alert_precip_mm {
+        let min_mm = match PRECIP_MM_INPUT.parse(text) {
+            Ok(value) => value,
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(PRECIP_MM_INPUT.keyboard())
+                    .await?;
+                return Ok(());
+            }
+        };
+        
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_alert_precip_mm = false;
+            user_data.waiting_for_alert_precip_kind = true;
+            if let Some(AlertType::Precipitation { min_mm: mm, .. }) = &mut user_data.pending_alert_type {
+                *mm = min_mm;
+            }
+        });
+        
+        bot.send_message(chat_id, "Which precipitation should trigger the alert?")
+            .reply_markup(make_precipitation_kind_keyboard())
+            .await?;
+    }
+    // Handle UV index alert
+    else if user_data.waiting_for_alert_uv_max {
+        match UV_INPUT.parse_value(text) {
+            Ok(uv) => {
+                update_user_data(state, chat_id, |user_data| {
+                    user_data.waiting_for_alert_uv_max = false;
+                    user_data.waiting_for_alert_hours_input = true;
+                    if let Some(AlertType::UvIndex { max }) = &mut user_data.pending_alert_type {
+                        *max = uv;
+                    }
+                });
+                
+                ask_alert_hours(bot, state, chat_id, "🕐 How many hours ahead should I warn you about strong sun?\n\nEnter a number (1-72 hours):").await?;
+            }
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(UV_INPUT.keyboard())
+                    .await?;
+            }
+        }
+    }
+    // Handle air quality alert parameters
+    else if user_data.waiting_for_alert_aqi_index {
+        match AQI_INDEX_INPUT.parse_value(text) {
+            Ok(index) => {
+                update_user_data(state, chat_id, |user_data| {
+                    user_data.waiting_for_alert_aqi_index = false;
+                    user_data.waiting_for_alert_aqi_pm25 = true;
+                    if let Some(AlertType::AirQuality { min_epa_index, .. }) = &mut user_data.pending_alert_type {
+                        *min_epa_index = index as u8;
+                    }
+                });
+                
+                bot.send_message(chat_id, "Enter a PM2.5 concentration (µg/m³) that should also trigger the alert, or type 'skip' to skip:")
+                    .reply_markup(PM25_INPUT.keyboard())
+                    .await?;
+            }
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(AQI_INDEX_INPUT.keyboard())
+                    .await?;
+            }
+        }
+    }
+    else if user_data.waiting_for_alert_aqi_pm25 {
+        let pm2_5 = match PM25_INPUT.parse(text) {
+            Ok(value) => value,
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(PM25_INPUT.keyboard())
+                    .await?;
+                return Ok(());
+            }
+        };
+        
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_alert_aqi_pm25 = false;
+            user_data.waiting_for_alert_hours_input = true;
+            if let Some(AlertType::AirQuality { max_pm2_5, .. }) = &mut user_data.pending_alert_type {
+                *max_pm2_5 = pm2_5;
+            }
+        });
+        
+        ask_alert_hours(bot, state, chat_id, "🕐 How many hours ahead should I warn you about poor air quality?\n\nEnter a number (1-72 hours):").await?;
+    }
+    // Handle composite rule condition value
+    else if user_data.waiting_for_alert_rule_value {
+        let (Some(metric), Some(op)) = (user_data.pending_rule_metric, user_data.pending_rule_op) else {
+            return Ok(());
+        };
+        let input = rule_value_input(metric);
+        let value = match input.parse_value(text) {
+            Ok(value) => value,
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(input.keyboard())
+                    .await?;
+                return Ok(());
+            }
+        };
+        
+        let condition = RuleExpr::Condition(RuleCondition { metric, op, value });
+        update_user_data(state, chat_id, |user_data| {
+            let join = user_data.pending_rule_join.take().unwrap_or(RuleJoin::And);
+            if let Some(AlertType::Composite { rule }) = &mut user_data.pending_alert_type {
+                *rule = std::mem::take(rule).join(join, condition);
+            }
+            user_data.waiting_for_alert_rule_value = false;
+            user_data.waiting_for_alert_rule_next = true;
+            user_data.pending_rule_metric = None;
+            user_data.pending_rule_op = None;
+        });
+        
+        let user_data = get_user_data(state, chat_id);
+        if let Some(AlertType::Composite { rule }) = &user_data.pending_alert_type {
+            bot.send_message(chat_id, format!("🧩 Rule so far:\n{}", rule.describe()))
+                .reply_markup(make_rule_next_keyboard(rule))
                 .await?;
         }
-        // Check if user is waiting for alert city input
-        else if user_data.waiting_for_alert_city {
-            update_user_data(&state, chat_id, |user_data| {
-                user_data.waiting_for_alert_city = false;
-                user_data.pending_alert_city = Some(text.to_string());
-            });
-            
-            let user_data = get_user_data(&state, chat_id);
-            match &user_data.pending_alert_type {
-                Some(AlertType::StandardWeatherAlert) => {
-                    update_user_data(&state, chat_id, |user_data| {
-                        user_data.waiting_for_alert_hours_input = true;
-                    });
-                    
-                    let cancel_keyboard = make_cancel_keyboard();
-                    bot.send_message(chat_id, format!("🕐 How many hours ahead should I warn you about weather in '{}'?\n\nEnter a number (1-72 hours):", text))
-                        .reply_markup(cancel_keyboard)
-                        .await?;
-                }
-                Some(AlertType::TemperatureThreshold { .. }) => {
-                    update_user_data(&state, chat_id, |user_data| {
-                        user_data.waiting_for_alert_temperature_min = true;
-                    });
-                    
-                    let cancel_keyboard = make_cancel_keyboard();
-                    bot.send_message(chat_id, "Enter minimum temperature threshold (°C) or type 'skip' to skip:")
-                        .reply_markup(cancel_keyboard)
-                        .await?;
-                }
-                Some(AlertType::WindSpeed { .. }) => {
-                    update_user_data(&state, chat_id, |user_data| {
-                        user_data.waiting_for_alert_wind_speed = true;
-                    });
-                    
-                    let cancel_keyboard = make_cancel_keyboard();
-                    bot.send_message(chat_id, "Enter maximum wind speed threshold (km/h):")
-                        .reply_markup(cancel_keyboard)
-                        .await?;
-                }
-                Some(AlertType::Humidity { .. }) => {
-                    update_user_data(&state, chat_id, |user_data| {
-                        user_data.waiting_for_alert_humidity_min = true;
-                    });
-                    
-                    let cancel_keyboard = make_cancel_keyboard();
-                    bot.send_message(chat_id, "Enter minimum humidity threshold (%) or type 'skip' to skip:")
-                        .reply_markup(cancel_keyboard)
-                        .await?;
-                }
-                _ => {
-                    bot.send_message(chat_id, "Error: Unknown alert type. Please try again.")
-                        .await?;
-                    
-                    let keyboard = make_alerts_menu_keyboard(&state, chat_id);
-                    bot.send_message(chat_id, "Weather Alerts Management:")
-                        .reply_markup(keyboard)
-                        .await?;
-                }
+    }
+    // Handle custom feels-like thresholds
+    else if user_data.waiting_for_alert_feels_min {
+        let min_feels = match FEELS_MIN_INPUT.parse(text) {
+            Ok(value) => value,
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(FEELS_MIN_INPUT.keyboard())
+                    .await?;
+                return Ok(());
+            }
+        };
+        
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_alert_feels_min = false;
+            user_data.waiting_for_alert_feels_max = true;
+            if let Some(AlertType::FeelsLike { min, .. }) = &mut user_data.pending_alert_type {
+                *min = min_feels;
+            }
+        });
+        
+        bot.send_message(chat_id, "Enter the maximum feels-like temperature (°C), above which I should warn you, or type 'skip' to skip:")
+            .reply_markup(FEELS_MAX_INPUT.keyboard())
+            .await?;
+    }
+    else if user_data.waiting_for_alert_feels_max {
+        let max_feels = match FEELS_MAX_INPUT.parse(text) {
+            Ok(value) => value,
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(FEELS_MAX_INPUT.keyboard())
+                    .await?;
+                return Ok(());
+            }
+        };
+        
+        let min_feels = match &user_data.pending_alert_type {
+            Some(AlertType::FeelsLike { min, .. }) => *min,
+            _ => None,
+        };
+        if min_feels.is_none() && max_feels.is_none() {
+            bot.send_message(chat_id, "Please set at least one threshold. Enter the maximum feels-like temperature (°C):")
+                .reply_markup(FEELS_MAX_INPUT.keyboard())
+                .await?;
+            return Ok(());
+        }
+        if let (Some(min), Some(max)) = (min_feels, max_feels) {
+            if max <= min {
+                bot.send_message(chat_id, format!("The maximum must be above the minimum ({}°C). Please enter it again:", min))
+                    .reply_markup(FEELS_MAX_INPUT.keyboard())
+                    .await?;
+                return Ok(());
+            }
+        }
+        
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_alert_feels_max = false;
+            user_data.waiting_for_alert_hours_input = true;
+            if let Some(AlertType::FeelsLike { max, .. }) = &mut user_data.pending_alert_type {
+                *max = max_feels;
+            }
+        });
+        
+        ask_alert_hours(bot, state, chat_id, "🕐 How many hours ahead should I warn you about the feels-like temperature?\n\nEnter a number (1-72 hours):").await?;
+    }
+    // Handle snow and ice alert parameters
+    else if user_data.waiting_for_alert_snow_cm {
+        let snow_cm = match SNOW_INPUT.parse(text) {
+            Ok(value) => value,
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(SNOW_INPUT.keyboard())
+                    .await?;
+                return Ok(());
+            }
+        };
+        
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_alert_snow_cm = false;
+            user_data.waiting_for_alert_snow_ice = true;
+            if let Some(AlertType::SnowIce { min_snow_cm, .. }) = &mut user_data.pending_alert_type {
+                *min_snow_cm = snow_cm;
+            }
+        });
+        
+        bot.send_message(chat_id, "Should I also warn about black ice (wet roads with temperatures around 0°C)?")
+            .reply_markup(make_snow_ice_keyboard())
+            .await?;
+    }
+    // Handle frost alert threshold
+    else if user_data.waiting_for_alert_frost_temp {
+        let threshold = match FROST_INPUT.parse(text) {
+            Ok(value) => value.unwrap_or(DEFAULT_FROST_AIR_TEMP_C),
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(FROST_INPUT.keyboard())
+                    .await?;
+                return Ok(());
+            }
+        };
+        
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_alert_frost_temp = false;
+            user_data.waiting_for_alert_hours_input = true;
+            if let Some(AlertType::Frost { max_temp }) = &mut user_data.pending_alert_type {
+                *max_temp = threshold;
+            }
+        });
+        
+        ask_alert_hours(bot, state, chat_id, "🕐 How many hours ahead should I check the nights for frost?\n\nEnter a number (1-72 hours, 24 covers the coming night):").await?;
+    }
+    // Handle pressure drop alert parameters
+    else if user_data.waiting_for_alert_pressure_drop {
+        match PRESSURE_DROP_INPUT.parse_value(text) {
+            Ok(drop) => {
+                update_user_data(state, chat_id, |user_data| {
+                    user_data.waiting_for_alert_pressure_drop = false;
+                    user_data.waiting_for_alert_pressure_window = true;
+                    if let Some(AlertType::PressureDrop { drop_hpa, .. }) = &mut user_data.pending_alert_type {
+                        *drop_hpa = drop;
+                    }
+                });
+                
+                bot.send_message(chat_id, "Over how many hours should the pressure fall? Enter a number (1-24 hours, 3 is typical for storms):")
+                    .reply_markup(PRESSURE_WINDOW_INPUT.keyboard())
+                    .await?;
+            }
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(PRESSURE_DROP_INPUT.keyboard())
+                    .await?;
+            }
+        }
+    }
+    else if user_data.waiting_for_alert_pressure_window {
+        match PRESSURE_WINDOW_INPUT.parse_value(text) {
+            Ok(window) => {
+                update_user_data(state, chat_id, |user_data| {
+                    user_data.waiting_for_alert_pressure_window = false;
+                    user_data.waiting_for_alert_hours_input = true;
+                    if let Some(AlertType::PressureDrop { window_hours, .. }) = &mut user_data.pending_alert_type {
+                        *window_hours = window as u8;
+                    }
+                });
+                
+                ask_alert_hours(bot, state, chat_id, "🕐 How many hours ahead should I watch the pressure?\n\nEnter a number (1-72 hours):").await?;
+            }
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(PRESSURE_WINDOW_INPUT.keyboard())
+                    .await?;
             }
         }
-        // Handle temperature alert parameters
-        else if user_data.waiting_for_alert_temperature_min {
-            let min_temp = if text.to_lowercase() == "skip" {
-                None
+    }
+    // Handle alert edits
+    else if user_data.waiting_for_edit_city {
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_edit_city = false;
+        });
+        
+        let city = text.trim().to_string();
+        finish_alert_edit(bot, state, chat_id, |alert| {
+            update_alert(alert, city, alert.alert_type.clone(), alert.hours_ahead);
+        }).await?;
+    }
+    else if user_data.waiting_for_edit_hours {
+        match HOURS_INPUT.parse_value(text) {
+            Ok(hours) => {
+                update_user_data(state, chat_id, |user_data| {
+                    user_data.waiting_for_edit_hours = false;
+                });
+                
+                finish_alert_edit(bot, state, chat_id, |alert| {
+                    update_alert(alert, alert.city.clone(), alert.alert_type.clone(), hours as u8);
+                }).await?;
+            }
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(HOURS_INPUT.keyboard())
+                    .await?;
+            }
+        }
+    }
+    else if user_data.waiting_for_edit_schedule {
+        match parse_schedule(text) {
+            Ok(schedule) => {
+                update_user_data(state, chat_id, |user_data| {
+                    user_data.waiting_for_edit_schedule = false;
+                });
+                
+                finish_alert_edit(bot, state, chat_id, |alert| schedule.apply(alert)).await?;
+            }
+            Err(e) => {
+                bot.send_message(chat_id, e.render(text))
+                    .reply_markup(make_schedule_keyboard("↩️ Reset to defaults"))
+                    .await?;
+            }
+        }
+    }
+    else if user_data.waiting_for_edit_description {
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_edit_description = false;
+        });
+        
+        let description = text.trim().to_string();
+        finish_alert_edit(bot, state, chat_id, |alert| {
+            if description.eq_ignore_ascii_case("auto") {
+                alert.custom_description = false;
+                update_alert(alert, alert.city.clone(), alert.alert_type.clone(), alert.hours_ahead);
             } else {
-                match text.parse::<f32>() {
-                    Ok(temp) => Some(temp),
-                    Err(_) => {
-                        bot.send_message(chat_id, "Invalid temperature value. Please enter a valid number or 'skip':")
-                            .await?;
-                        return Ok(());
+                alert.custom_description = true;
+                alert.description = description;
+            }
+        }).await?;
+    }
+    // Handle temperature swing alert parameters
+    else if user_data.waiting_for_alert_swing_delta {
+        match SWING_INPUT.parse_value(text) {
+            Ok(change) => {
+                update_user_data(state, chat_id, |user_data| {
+                    user_data.waiting_for_alert_swing_delta = false;
+                    user_data.waiting_for_alert_swing_direction = true;
+                    if let Some(AlertType::TemperatureSwing { delta, .. }) = &mut user_data.pending_alert_type {
+                        *delta = change;
+                    }
+                });
+                
+                bot.send_message(chat_id, format!("Warn me when it gets at least {}°C…", change))
+                    .reply_markup(make_swing_direction_keyboard())
+                    .await?;
+            }
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(SWING_INPUT.keyboard())
+                    .await?;
+            }
+        }
+    }
+    // Handle good weather alert parameters
+    else if user_data.waiting_for_alert_good_temp_min {
+        match GOOD_TEMP_MIN_INPUT.parse_value(text) {
+            Ok(value) => {
+                update_user_data(state, chat_id, |user_data| {
+                    user_data.waiting_for_alert_good_temp_min = false;
+                    user_data.waiting_for_alert_good_temp_max = true;
+                    if let Some(AlertType::GoodWeather { min_temp, .. }) = &mut user_data.pending_alert_type {
+                        *min_temp = value;
                     }
+                });
+                
+                bot.send_message(chat_id, "Enter the highest comfortable temperature (°C):")
+                    .reply_markup(GOOD_TEMP_MAX_INPUT.keyboard())
+                    .await?;
+            }
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(GOOD_TEMP_MIN_INPUT.keyboard())
+                    .await?;
+            }
+        }
+    }
+    else if user_data.waiting_for_alert_good_temp_max {
+        let value = match GOOD_TEMP_MAX_INPUT.parse_value(text) {
+            Ok(value) => value,
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(GOOD_TEMP_MAX_INPUT.keyboard())
+                    .await?;
+                return Ok(());
+            }
+        };
+        if let Some(AlertType::GoodWeather { min_temp, .. }) = &user_data.pending_alert_type {
+            if value <= *min_temp {
+                bot.send_message(chat_id, format!("The highest temperature must be above the lowest ({}°C). Please enter it again:", min_temp))
+                    .reply_markup(GOOD_TEMP_MAX_INPUT.keyboard())
+                    .await?;
+                return Ok(());
+            }
+        }
+        
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_alert_good_temp_max = false;
+            user_data.waiting_for_alert_good_wind = true;
+            if let Some(AlertType::GoodWeather { max_temp, .. }) = &mut user_data.pending_alert_type {
+                *max_temp = value;
+            }
+        });
+        
+        bot.send_message(chat_id, "Enter the strongest wind you're fine with (km/h):")
+            .reply_markup(GOOD_WIND_INPUT.keyboard())
+            .await?;
+    }
+    else if user_data.waiting_for_alert_good_wind {
+        match GOOD_WIND_INPUT.parse_value(text) {
+            Ok(value) => {
+                update_user_data(state, chat_id, |user_data| {
+                    user_data.waiting_for_alert_good_wind = false;
+                    user_data.waiting_for_alert_good_window = true;
+                    if let Some(AlertType::GoodWeather { max_wind, .. }) = &mut user_data.pending_alert_type {
+                        *max_wind = value;
+                    }
+                });
+                
+                bot.send_message(chat_id, "How many dry, pleasant hours in a row do you need?")
+                    .reply_markup(GOOD_WINDOW_INPUT.keyboard())
+                    .await?;
+            }
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(GOOD_WIND_INPUT.keyboard())
+                    .await?;
+            }
+        }
+    }
+    else if user_data.waiting_for_alert_good_window {
+        match GOOD_WINDOW_INPUT.parse_value(text) {
+            Ok(value) => {
+                update_user_data(state, chat_id, |user_data| {
+                    user_data.waiting_for_alert_good_window = false;
+                    user_data.waiting_for_alert_hours_input = true;
+                    if let Some(AlertType::GoodWeather { min_hours, .. }) = &mut user_data.pending_alert_type {
+                        *min_hours = value as u8;
+                    }
+                });
+                
+                ask_alert_hours(bot, state, chat_id, "🕐 How far ahead should I look for a good weather window?\n\nEnter a number (1-72 hours, 48 covers the next two days):").await?;
+            }
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(GOOD_WINDOW_INPUT.keyboard())
+                    .await?;
+            }
+        }
+    }
+    // Handle hours input for alerts
+    else if user_data.waiting_for_alert_hours_input {
+        match HOURS_INPUT.parse_value(text) {
+            Ok(hours) => {
+                let Some(alert_type) = user_data.pending_alert_type.clone() else {
+                    bot.send_message(chat_id, "Error: No pending alert data found.")
+                        .await?;
+                    return Ok(());
+                };
+                update_user_data(state, chat_id, |user_data| {
+                    user_data.waiting_for_alert_hours_input = false;
+                    user_data.waiting_for_alert_schedule = true;
+                    user_data.pending_alert_hours = Some(hours as u8);
+                });
+                
+                let defaults = format_schedule_minutes(alert_type.default_check_interval_minutes(), alert_type.default_cooldown_minutes());
+                bot.send_message(chat_id, format!("How often should I check this alert, and how long should I stay quiet after a notification?\n\nDefaults for this alert type: {}.\n\n{}", defaults, SCHEDULE_INPUT_HINT))
+                    .reply_markup(make_schedule_keyboard("✅ Use defaults"))
+                    .await?;
+            }
+            Err(hint) => {
+                bot.send_message(chat_id, hint)
+                    .reply_markup(HOURS_INPUT.keyboard())
+                    .await?;
+            }
+        }
+    }
+    // Handle check frequency and cooldown - last step of the wizard
+    else if user_data.waiting_for_alert_schedule {
+        match parse_schedule(text) {
+            Ok(schedule) => create_pending_alert(bot, state, chat_id, schedule).await?,
+            Err(e) => {
+                bot.send_message(chat_id, e.render(text))
+                    .reply_markup(make_schedule_keyboard("✅ Use defaults"))
+                    .await?;
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Создает алерт из pending-полей мастера с выбранным расписанием
+async fn create_pending_alert(bot: &Bot, state: &SharedState, chat_id: ChatId, schedule: AlertSchedule) -> HandlerResult {
+    let user_data = get_user_data(state, chat_id);
+    let (Some(city), Some(alert_type), Some(hours)) = (user_data.pending_alert_city, user_data.pending_alert_type, user_data.pending_alert_hours) else {
+        bot.send_message(chat_id, "Error: No pending alert data found.")
+            .await?;
+        return Ok(());
+    };
+    
+    let mut alert = create_alert(city.clone(), alert_type, hours);
+    schedule.apply(&mut alert);
+    let summary = format_alert_schedule(&alert);
+    
+    update_user_data(state, chat_id, |user_data| {
+        user_data.weather_alerts.push(alert);
+        user_data.waiting_for_alert_schedule = false;
+        user_data.pending_alert_city = None;
+        user_data.pending_alert_type = None;
+        user_data.pending_alert_hours = None;
+    });
+    
+    bot.send_message(chat_id, format!("✅ Weather alert created for '{}' with {} hours advance warning!\n🔁 {}", city, hours, summary))
+        .await?;
+    
+    let keyboard = make_alerts_menu_keyboard(state, chat_id);
+    bot.send_message(chat_id, "Weather Alerts Management:")
+        .reply_markup(keyboard)
+        .await?;
+    Ok(())
+}
+
+/// Последний шаг мастера - горизонт предупреждения. При правке порогов горизонт не меняется:
+/// новые параметры сразу сохраняются в редактируемый алерт
+async fn ask_alert_hours(bot: &Bot, state: &SharedState, chat_id: ChatId, prompt: impl Into<String>) -> HandlerResult {
+    let user_data = get_user_data(state, chat_id);
+    if let (Some(_), Some(alert_type)) = (&user_data.editing_alert_id, user_data.pending_alert_type) {
+        update_user_data(state, chat_id, |user_data| {
+            user_data.waiting_for_alert_hours_input = false;
+        });
+        return finish_alert_edit(bot, state, chat_id, |alert| {
+            update_alert(alert, alert.city.clone(), alert_type, alert.hours_ahead);
+        }).await;
+    }
+    
+    bot.send_message(chat_id, prompt)
+        .reply_markup(HOURS_INPUT.keyboard())
+        .await?;
+    Ok(())
+}
+
+/// Применяет правку к алерту из editing_alert_id и показывает его карточку
+async fn finish_alert_edit<F>(bot: &Bot, state: &SharedState, chat_id: ChatId, edit: F) -> HandlerResult
+where
+    F: FnOnce(&mut crate::state::WeatherAlert),
+{
+    let mut updated = None;
+    let mut pinned_escalation = None;
+    update_user_data(state, chat_id, |user_data| {
+        if let Some(alert_id) = user_data.editing_alert_id.take() {
+            if let Some(alert) = user_data.weather_alerts.iter_mut().find(|a| a.id == alert_id) {
+                edit(alert);
+                // Правка сняла запрос подтверждения - закрепленное напоминание больше не нужно
+                if !alert.escalated {
+                    pinned_escalation = alert.escalation_message_id.take();
                 }
-            };
+                updated = Some(alert.clone());
+            }
+        }
+        user_data.pending_alert_city = None;
+        user_data.pending_alert_type = None;
+        user_data.pending_alert_hours = None;
+    });
+    
+    if let Some(alert) = &updated {
+        unpin_escalation(bot, chat_id, &alert.id, pinned_escalation).await;
+    }
+    match updated {
+        Some(alert) => {
+            bot.send_message(chat_id, format!("✅ Alert updated!\n\n{}\n📝 {}\n🕐 {} hours ahead\n🔁 {}", format_alert_type_details(&alert.alert_type), alert.description, alert.hours_ahead, format_alert_schedule(&alert)))
+                .reply_markup(make_edit_alert_keyboard(&alert))
+                .await?;
+        }
+        None => {
+            bot.send_message(chat_id, "Alert not found.")
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Первый шаг настройки параметров алерта (после выбора города) - зависит от типа в pending_alert_type
+async fn ask_alert_params(bot: &Bot, state: &SharedState, chat_id: ChatId, city: &str) -> HandlerResult {
+    let user_data = get_user_data(state, chat_id);
+    match &user_data.pending_alert_type {
+        Some(AlertType::StandardWeatherAlert { categories }) => {
+            update_user_data(state, chat_id, |user_data| {
+                user_data.waiting_for_alert_standard_categories = true;
+            });
             
-            update_user_data(&state, chat_id, |user_data| {
-                user_data.waiting_for_alert_temperature_min = false;
-                user_data.waiting_for_alert_temperature_max = true;
-                if let Some(AlertType::TemperatureThreshold { min, .. }) = &mut user_data.pending_alert_type {
-                    *min = min_temp;
-                }
+            bot.send_message(chat_id, format!("Which kinds of severe weather in '{}' should I warn you about?\n\nTap a category to toggle it, then press Done:", city))
+                .reply_markup(make_standard_categories_keyboard(categories))
+                .await?;
+        }
+        Some(AlertType::TemperatureThreshold { .. }) => {
+            update_user_data(state, chat_id, |user_data| {
+                user_data.waiting_for_alert_temperature_min = true;
             });
             
-            let cancel_keyboard = make_cancel_keyboard();
-            bot.send_message(chat_id, "Enter maximum temperature threshold (°C) or type 'skip' to skip:")
-                .reply_markup(cancel_keyboard)
+            bot.send_message(chat_id, "Enter minimum temperature threshold (°C) or type 'skip' to skip:")
+                .reply_markup(TEMPERATURE_MIN_INPUT.keyboard())
                 .await?;
         }
-        else if user_data.waiting_for_alert_temperature_max {
-            let max_temp = if text.to_lowercase() == "skip" {
-                None
-            } else {
-                match text.parse::<f32>() {
-                    Ok(temp) => Some(temp),
-                    Err(_) => {
-                        bot.send_message(chat_id, "Invalid temperature value. Please enter a valid number or 'skip':")
-                            .await?;
-                        return Ok(());
-                    }
-                }
-            };
+        Some(AlertType::WindSpeed { .. }) => {
+            update_user_data(state, chat_id, |user_data| {
+                user_data.waiting_for_alert_wind_speed = true;
+            });
             
-            update_user_data(&state, chat_id, |user_data| {
-                user_data.waiting_for_alert_temperature_max = false;
-                user_data.waiting_for_alert_hours_input = true;
-                if let Some(AlertType::TemperatureThreshold { max, .. }) = &mut user_data.pending_alert_type {
-                    *max = max_temp;
-                }
+            bot.send_message(chat_id, "Enter maximum wind speed threshold (km/h):")
+                .reply_markup(WIND_INPUT.keyboard())
+                .await?;
+        }
+        Some(AlertType::Humidity { .. }) => {
+            update_user_data(state, chat_id, |user_data| {
+                user_data.waiting_for_alert_humidity_min = true;
             });
             
-            let cancel_keyboard = make_cancel_keyboard();
-            bot.send_message(chat_id, "🕐 How many hours ahead should I warn you about temperature changes?\n\nEnter a number (1-72 hours):")
-                .reply_markup(cancel_keyboard)
+            bot.send_message(chat_id, "Enter minimum humidity threshold (%) or type 'skip' to skip:")
+                .reply_markup(HUMIDITY_MIN_INPUT.keyboard())
                 .await?;
         }
-        // Handle wind speed alert
-        else if user_data.waiting_for_alert_wind_speed {
-            match text.parse::<f32>() {
-                Ok(wind_speed) => {
-                    update_user_data(&state, chat_id, |user_data| {
-                        user_data.waiting_for_alert_wind_speed = false;
-                        user_data.waiting_for_alert_hours_input = true;
-                        if let Some(AlertType::WindSpeed { max }) = &mut user_data.pending_alert_type {
-                            *max = wind_speed;
-                        }
-                    });
-                    
-                    let cancel_keyboard = make_cancel_keyboard();
-                    bot.send_message(chat_id, "🕐 How many hours ahead should I warn you about wind conditions?\n\nEnter a number (1-72 hours):")
-                        .reply_markup(cancel_keyboard)
-                        .await?;
-                }
-                Err(_) => {
-                    bot.send_message(chat_id, "Invalid wind speed value. Please enter a valid number:")
-                        .await?;
-                }
-            }
+        Some(AlertType::UvIndex { .. }) => {
+            update_user_data(state, chat_id, |user_data| {
+                user_data.waiting_for_alert_uv_max = true;
+            });
+            
+            bot.send_message(chat_id, "Enter the UV index that should trigger the alert (e.g. 6 = high, 8 = very high, 11 = extreme):")
+                .reply_markup(UV_INPUT.keyboard())
+                .await?;
         }
-        // Handle humidity alert parameters
-        else if user_data.waiting_for_alert_humidity_min {
-            let min_humidity = if text.to_lowercase() == "skip" {
-                None
-            } else {
-                match text.parse::<u32>() {
-                    Ok(humidity) => Some(humidity),
-                    Err(_) => {
-                        bot.send_message(chat_id, "Invalid humidity value. Please enter a valid number (0-100) or 'skip':")
-                            .await?;
-                        return Ok(());
-                    }
-                }
-            };
+        Some(AlertType::AirQuality { .. }) => {
+            update_user_data(state, chat_id, |user_data| {
+                user_data.waiting_for_alert_aqi_index = true;
+            });
             
-            update_user_data(&state, chat_id, |user_data| {
-                user_data.waiting_for_alert_humidity_min = false;
-                user_data.waiting_for_alert_humidity_max = true;
-                if let Some(AlertType::Humidity { min, .. }) = &mut user_data.pending_alert_type {
-                    *min = min_humidity;
-                }
+            bot.send_message(chat_id, "Enter the US EPA air quality index level that should trigger the alert (1-6):\n\n1 - Good\n2 - Moderate\n3 - Unhealthy for sensitive groups\n4 - Unhealthy\n5 - Very unhealthy\n6 - Hazardous")
+                .reply_markup(AQI_INDEX_INPUT.keyboard())
+                .await?;
+        }
+        Some(AlertType::TemperatureSwing { .. }) => {
+            update_user_data(state, chat_id, |user_data| {
+                user_data.waiting_for_alert_swing_delta = true;
             });
             
-            let cancel_keyboard = make_cancel_keyboard();
-            bot.send_message(chat_id, "Enter maximum humidity threshold (%) or type 'skip' to skip:")
-                .reply_markup(cancel_keyboard)
+            bot.send_message(chat_id, "Enter the temperature change (°C) that should trigger the alert (e.g. 8 for \"tomorrow at least 8°C colder\"):")
+                .reply_markup(SWING_INPUT.keyboard())
                 .await?;
         }
-        else if user_data.waiting_for_alert_humidity_max {
-            let max_humidity = if text.to_lowercase() == "skip" {
-                None
-            } else {
-                match text.parse::<u32>() {
-                    Ok(humidity) => Some(humidity),
-                    Err(_) => {
-                        bot.send_message(chat_id, "Invalid humidity value. Please enter a valid number (0-100) or 'skip':")
-                            .await?;
-                        return Ok(());
-                    }
-                }
-            };
+        Some(AlertType::GoodWeather { .. }) => {
+            update_user_data(state, chat_id, |user_data| {
+                user_data.waiting_for_alert_good_preset = true;
+            });
             
-            update_user_data(&state, chat_id, |user_data| {
-                user_data.waiting_for_alert_humidity_max = false;
-                user_data.waiting_for_alert_hours_input = true;
-                if let Some(AlertType::Humidity { max, .. }) = &mut user_data.pending_alert_type {
-                    *max = max_humidity;
-                }
+            bot.send_message(chat_id, format!("I'll let you know when a dry, pleasant daytime window is forecast in '{}'.\n\nChoose what good weather means for you:", city))
+                .reply_markup(make_good_weather_preset_keyboard())
+                .await?;
+        }
+        Some(AlertType::Composite { .. }) => {
+            update_user_data(state, chat_id, |user_data| {
+                user_data.waiting_for_alert_rule_metric = true;
             });
             
-            let cancel_keyboard = make_cancel_keyboard();
-            bot.send_message(chat_id, "🕐 How many hours ahead should I warn you about humidity changes?\n\nEnter a number (1-72 hours):")
-                .reply_markup(cancel_keyboard)
+            bot.send_message(chat_id, "Let's build a rule from conditions joined with AND / OR. Each condition is checked hour by hour in the forecast.\n\nChoose the metric for the first condition:")
+                .reply_markup(make_rule_metric_keyboard())
                 .await?;
         }
-        // Handle hours input for alerts
-        else if user_data.waiting_for_alert_hours_input {
-            match text.parse::<u8>() {
-                Ok(hours) if hours >= 1 && hours <= 72 => {
-                    let user_data_clone = get_user_data(&state, chat_id);
-                    if let (Some(city), Some(alert_type)) = (&user_data_clone.pending_alert_city, &user_data_clone.pending_alert_type) {
-                        let alert = match alert_type {
-                            AlertType::StandardWeatherAlert => create_standard_alert(city.clone(), hours),
-                            AlertType::TemperatureThreshold { min, max } => create_temperature_alert(city.clone(), *min, *max, hours),
-                            AlertType::WindSpeed { max } => create_wind_alert(city.clone(), *max, hours),
-                            AlertType::Humidity { min, max } => create_humidity_alert(city.clone(), *min, *max, hours),
-                        };
-                        
-                        update_user_data(&state, chat_id, |user_data| {
-                            user_data.weather_alerts.push(alert);
-                            user_data.waiting_for_alert_hours_input = false;
-                            user_data.pending_alert_city = None;
-                            user_data.pending_alert_type = None;
-                            user_data.pending_alert_hours = None;
-                        });
-                        
-                        bot.send_message(chat_id, format!("✅ Weather alert created for '{}' with {} hours advance warning!", city, hours))
-                            .await?;
-                        
-                        let keyboard = make_alerts_menu_keyboard(&state, chat_id);
-                        bot.send_message(chat_id, "Weather Alerts Management:")
-                            .reply_markup(keyboard)
-                            .await?;
-                    } else {
-                        bot.send_message(chat_id, "Error: No pending alert data found.")
-                            .await?;
-                    }
-                }
-                Ok(_) => {
-                    bot.send_message(chat_id, "⚠️ Please enter a number between 1 and 72 hours:")
-                        .await?;
-                }
-                Err(_) => {
-                    bot.send_message(chat_id, "⚠️ Please enter a valid number:")
-                        .await?;
-                }
+        Some(AlertType::FeelsLike { .. }) => {
+            update_user_data(state, chat_id, |user_data| {
+                user_data.waiting_for_alert_feels_preset = true;
+            });
+            
+            bot.send_message(chat_id, "Choose a health-risk preset or set your own feels-like thresholds:")
+                .reply_markup(make_feels_like_preset_keyboard())
+                .await?;
+        }
+        Some(AlertType::SnowIce { .. }) => {
+            update_user_data(state, chat_id, |user_data| {
+                user_data.waiting_for_alert_snow_cm = true;
+            });
+            
+            bot.send_message(chat_id, "Enter the snowfall (cm) over the warning period that should trigger the alert, or type 'skip' to only watch for road ice:")
+                .reply_markup(SNOW_INPUT.keyboard())
+                .await?;
+        }
+        Some(AlertType::Frost { .. }) => {
+            update_user_data(state, chat_id, |user_data| {
+                user_data.waiting_for_alert_frost_temp = true;
+            });
+            
+            bot.send_message(chat_id, format!("Ground frost is likely when the night air cools to about {}°C or below. Enter your own air temperature threshold (°C) or type 'skip' to use the default:", DEFAULT_FROST_AIR_TEMP_C))
+                .reply_markup(FROST_INPUT.keyboard())
+                .await?;
+        }
+        Some(AlertType::PressureDrop { .. }) => {
+            update_user_data(state, chat_id, |user_data| {
+                user_data.waiting_for_alert_pressure_drop = true;
+            });
+            
+            bot.send_message(chat_id, "Enter the pressure fall (hPa) that should trigger the alert (e.g. 3 = noticeable, 6 = storm approaching):")
+                .reply_markup(PRESSURE_DROP_INPUT.keyboard())
+                .await?;
+        }
+        Some(AlertType::Precipitation { .. }) => {
+            update_user_data(state, chat_id, |user_data| {
+                user_data.waiting_for_alert_precip_chance = true;
+            });
+            
+            bot.send_message(chat_id, "Enter the minimum chance of precipitation (%) that should trigger the alert, or type 'skip' to skip:")
+                .reply_markup(PRECIP_CHANCE_INPUT.keyboard())
+                .await?;
+        }
+        _ => {
+            bot.send_message(chat_id, "Error: Unknown alert type. Please try again.")
+                .await?;
+            
+            let keyboard = make_alerts_menu_keyboard(state, chat_id);
+            bot.send_message(chat_id, "Weather Alerts Management:")
+                .reply_markup(keyboard)
+                .await?;
+        }
+    }
+    
+    Ok(())
+}
+
+/// Описание типа алерта с порогами для карточки алерта
+fn format_alert_type_details(alert_type: &AlertType) -> String {
+    match alert_type {
+        AlertType::StandardWeatherAlert { categories } if categories.as_slice() == SevereCategory::DEFAULTS => "🚨 Standard Weather Alert".to_string(),
+        AlertType::StandardWeatherAlert { categories } => {
+            let labels: Vec<&str> = categories.iter().map(|category| category.label()).collect();
+            format!("🚨 Standard Weather Alert ({})", labels.join(", "))
+        }
+        AlertType::TemperatureThreshold { min, max } => {
+            let range = match (min, max) {
+                (Some(min_val), Some(max_val)) => format!("{}°C - {}°C", min_val, max_val),
+                (Some(min_val), None) => format!("min {}°C", min_val),
+                (None, Some(max_val)) => format!("max {}°C", max_val),
+                (None, None) => "Temperature".to_string(),
+            };
+            format!("🌡️ Temperature Alert ({})", range)
+        },
+        AlertType::WindSpeed { max, use_gusts, direction } => {
+            let measure = if *use_gusts { "gusts" } else { "max" };
+            let sector = direction.map(|d| format!(", {} only", d.label())).unwrap_or_default();
+            format!("💨 Wind Speed Alert ({} {} km/h{})", measure, max, sector)
+        }
+        AlertType::Humidity { min, max } => {
+            let range = match (min, max) {
+                (Some(min_val), Some(max_val)) => format!("{}% - {}%", min_val, max_val),
+                (Some(min_val), None) => format!("min {}%", min_val),
+                (None, Some(max_val)) => format!("max {}%", max_val),
+                (None, None) => "Humidity".to_string(),
+            };
+            format!("💧 Humidity Alert ({})", range)
+        },
+        AlertType::Precipitation { min_chance, min_mm, kind } => {
+            let mut thresholds = Vec::new();
+            if let Some(chance) = min_chance {
+                thresholds.push(format!("chance ≥ {}%", chance));
+            }
+            if let Some(mm) = min_mm {
+                thresholds.push(format!("≥ {} mm", mm));
             }
+            if thresholds.is_empty() {
+                format!("🌧️ Precipitation Alert ({})", kind.label())
+            } else {
+                format!("🌧️ Precipitation Alert ({}: {})", kind.label(), thresholds.join(", "))
+            }
+        },
+        AlertType::UvIndex { max } => format!("☀️ UV Index Alert (above {})", max),
+        AlertType::Composite { rule } => format!("🧩 Custom Rule ({})", rule.describe()),
+        AlertType::TemperatureSwing { delta, direction, window_hours } => match window_hours {
+            Some(window) => format!("🎢 Temperature Swing Alert ({}°C {} within {} h)", delta, direction.label(), window),
+            None => format!("🎢 Temperature Swing Alert ({}°C {} than the day before)", delta, direction.label()),
+        },
+        AlertType::GoodWeather { min_temp, max_temp, max_wind, min_hours } => {
+            format!("🌤️ Good Weather Alert (dry, {}-{}°C, wind < {} km/h for {}+ h)", min_temp, max_temp, max_wind, min_hours)
+        }
+        AlertType::FeelsLike { min, max } => {
+            let range = match (min, max) {
+                (Some(min_val), Some(max_val)) => format!("below {}°C or above {}°C", min_val, max_val),
+                (Some(min_val), None) => format!("below {}°C", min_val),
+                (None, Some(max_val)) => format!("above {}°C", max_val),
+                (None, None) => "no thresholds".to_string(),
+            };
+            format!("🥵 Feels Like Alert ({})", range)
+        }
+        AlertType::SnowIce { min_snow_cm, ice_risk } => match (min_snow_cm, ice_risk) {
+            (Some(snow), true) => format!("🧊 Snow & Ice Alert (snow ≥ {} cm or black ice)", snow),
+            (Some(snow), false) => format!("🧊 Snow & Ice Alert (snow ≥ {} cm)", snow),
+            (None, _) => "🧊 Snow & Ice Alert (black ice)".to_string(),
+        },
+        AlertType::Frost { max_temp } => format!("🥶 Frost Alert (night air at or below {}°C)", max_temp),
+        AlertType::PressureDrop { drop_hpa, window_hours } => format!("📉 Pressure Drop Alert ({} hPa within {} h)", drop_hpa, window_hours),
+        AlertType::AirQuality { min_epa_index, max_pm2_5 } => match max_pm2_5 {
+            Some(pm2_5) => format!("🫁 Air Quality Alert (US EPA ≥ {} or PM2.5 > {} µg/m³)", min_epa_index, pm2_5),
+            None => format!("🫁 Air Quality Alert (US EPA ≥ {}: {})", min_epa_index, weather_api::epa_index_label(*min_epa_index)),
+        },
+    }
+}
+
+/// Города, для которых можно создать алерт из пресета: (ключ для кнопки, город, домашний ли).
+/// Ключ зависит только от названия города, поэтому устаревшая кнопка не попадет в другой город
+fn preset_locations(user_data: &crate::state::UserData) -> Vec<(String, String, bool)> {
+    let mut towns = Vec::new();
+    if let Some(home) = &user_data.home_town {
+        towns.push((preset_location_key(home), home.clone(), true));
+    }
+    for town in &user_data.interested_towns {
+        if user_data.home_town.as_ref().is_none_or(|home| !home.eq_ignore_ascii_case(town)) {
+            towns.push((preset_location_key(town), town.clone(), false));
         }
     }
-    
-    Ok(())
+    towns
+}
+
+/// Короткий стабильный ключ города для callback data (лимит Telegram - 64 байта): FNV-1a от нормализованного названия
+fn preset_location_key(town: &str) -> String {
+    let hash = weather_api::normalize_location(town)
+        .bytes()
+        .fold(0x811c9dc5u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x01000193));
+    format!("{:08x}", hash)
+}
+
+/// Создает алерт по пресету. Название пресета становится описанием алерта
+async fn apply_alert_preset(bot: &Bot, state: &SharedState, chat_id: ChatId, preset: &AlertPreset, city: &str) -> HandlerResult {
+    let parsed = match preset.instantiate(city) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            log::warn!("Alert preset '{}' failed for '{}': {}", preset.id, city, e.message);
+            bot.send_message(chat_id, "Sorry, this preset can't be set up right now.")
+                .reply_markup(make_alert_presets_keyboard())
+                .await?;
+            return Ok(());
+        }
+    };
+    
+    let mut alert = parsed.into_alert();
+    alert.description = preset.title.clone();
+    alert.custom_description = true;
+    let text = format!(
+        "✅ Alert created!\n\n{} {} — {}\n🕐 {} hours ahead\n⌨️ {}",
+        alert.alert_type.emoji(),
+        alert.description,
+        alert.city,
+        alert.hours_ahead,
+        format_alert_command(&alert).unwrap_or_default()
+    );
+    update_user_data(state, chat_id, |user_data| {
+        user_data.weather_alerts.push(alert);
+    });
+    
+    bot.send_message(chat_id, text)
+        .reply_markup(make_alerts_menu_keyboard(state, chat_id))
+        .await?;
+    Ok(())
+}
+
+/// Числовой шаг мастера: допустимый диапазон, кнопки с типовыми значениями и можно ли его пропустить
+#[derive(Clone, Copy)]
+struct NumericInput {
+    min: f32,
+    max: f32,
+    unit: &'static str,
+    presets: &'static [f32],
+    skippable: bool,
+    integer: bool,
+}
+
+impl NumericInput {
+    /// Ok(None) - шаг пропущен. Err - подсказка с допустимым диапазоном для повторного ввода
+    fn parse(&self, text: &str) -> Result<Option<f32>, String> {
+        if self.skippable && text.trim().eq_ignore_ascii_case("skip") {
+            return Ok(None);
+        }
+        self.parse_value(text).map(Some)
+    }
+
+    fn parse_value(&self, text: &str) -> Result<f32, String> {
+        match text.trim().replace(',', ".").parse::<f32>() {
+            Ok(value) if (self.min..=self.max).contains(&value) && (!self.integer || value.fract() == 0.0) => Ok(value),
+            _ => Err(self.range_hint()),
+        }
+    }
+
+    fn range_hint(&self) -> String {
+        let kind = if self.integer { "a whole number" } else { "a number" };
+        let skip = if self.skippable { " or 'skip'" } else { "" };
+        format!("⚠️ Please enter {} from {} to {}{}{}:", kind, self.min, self.max, self.unit, skip)
+    }
+
+    /// Типовые значения, "Skip" (если шаг необязательный) и отмена
+    fn keyboard(&self) -> InlineKeyboardMarkup {
+        let mut keyboard = vec![];
+
+        if !self.presets.is_empty() {
+            keyboard.push(self.presets.iter()
+                .map(|value| InlineKeyboardButton::callback(format!("{}{}", value, self.unit), format!("input_{}", value)))
+                .collect());
+        }
+
+        let mut last_row = vec![];
+        if self.skippable {
+            last_row.push(InlineKeyboardButton::callback("⏭️ Skip", "input_skip"));
+        }
+        last_row.push(InlineKeyboardButton::callback("Cancel", "cancel"));
+        keyboard.push(last_row);
+
+        InlineKeyboardMarkup::new(keyboard)
+    }
+}
+
+const HOURS_INPUT: NumericInput = NumericInput { min: 1.0, max: 72.0, unit: " h", presets: &[6.0, 12.0, 24.0, 48.0, 72.0], skippable: false, integer: true };
+const TEMPERATURE_MIN_INPUT: NumericInput = NumericInput { min: -80.0, max: 60.0, unit: "°C", presets: &[-20.0, -10.0, -5.0, 0.0], skippable: true, integer: false };
+const TEMPERATURE_MAX_INPUT: NumericInput = NumericInput { min: -80.0, max: 60.0, unit: "°C", presets: &[25.0, 30.0, 35.0, 40.0], skippable: true, integer: false };
+const WIND_INPUT: NumericInput = NumericInput { min: 1.0, max: 250.0, unit: " km/h", presets: &[30.0, 50.0, 70.0, 90.0], skippable: false, integer: false };
+const HUMIDITY_MIN_INPUT: NumericInput = NumericInput { min: 0.0, max: 100.0, unit: "%", presets: &[20.0, 30.0], skippable: true, integer: true };
+const HUMIDITY_MAX_INPUT: NumericInput = NumericInput { min: 0.0, max: 100.0, unit: "%", presets: &[70.0, 80.0, 90.0], skippable: true, integer: true };
+const PRECIP_CHANCE_INPUT: NumericInput = NumericInput { min: 0.0, max: 100.0, unit: "%", presets: &[30.0, 50.0, 70.0], skippable: true, integer: true };
+const PRECIP_MM_INPUT: NumericInput = NumericInput { min: 0.0, max: 500.0, unit: " mm", presets: &[1.0, 5.0, 10.0], skippable: true, integer: false };
+const UV_INPUT: NumericInput = NumericInput { min: 1.0, max: 20.0, unit: "", presets: &[3.0, 6.0, 8.0, 11.0], skippable: false, integer: false };
+const AQI_INDEX_INPUT: NumericInput = NumericInput { min: 1.0, max: 6.0, unit: "", presets: &[2.0, 3.0, 4.0, 5.0], skippable: false, integer: true };
+const PM25_INPUT: NumericInput = NumericInput { min: 1.0, max: 1000.0, unit: " µg/m³", presets: &[35.0, 55.0], skippable: true, integer: false };
+const FEELS_MIN_INPUT: NumericInput = NumericInput { min: -80.0, max: 60.0, unit: "°C", presets: &[-25.0, -15.0, -5.0], skippable: true, integer: false };
+const FEELS_MAX_INPUT: NumericInput = NumericInput { min: -80.0, max: 60.0, unit: "°C", presets: &[27.0, 32.0, 40.0], skippable: true, integer: false };
+const SNOW_INPUT: NumericInput = NumericInput { min: 0.5, max: 200.0, unit: " cm", presets: &[2.0, 5.0, 10.0], skippable: true, integer: false };
+const FROST_INPUT: NumericInput = NumericInput { min: -30.0, max: 10.0, unit: "°C", presets: &[0.0, 2.0, 4.0], skippable: true, integer: false };
+const PRESSURE_DROP_INPUT: NumericInput = NumericInput { min: 0.5, max: 50.0, unit: " hPa", presets: &[3.0, 6.0, 10.0], skippable: false, integer: false };
+const PRESSURE_WINDOW_INPUT: NumericInput = NumericInput { min: 1.0, max: 24.0, unit: " h", presets: &[3.0, 6.0, 12.0], skippable: false, integer: true };
+const SWING_INPUT: NumericInput = NumericInput { min: 1.0, max: 40.0, unit: "°C", presets: &[5.0, 8.0, 10.0, 15.0], skippable: false, integer: false };
+const GOOD_TEMP_MIN_INPUT: NumericInput = NumericInput { min: -30.0, max: 45.0, unit: "°C", presets: &[5.0, 10.0, 15.0, 18.0], skippable: false, integer: false };
+const GOOD_TEMP_MAX_INPUT: NumericInput = NumericInput { min: -30.0, max: 45.0, unit: "°C", presets: &[20.0, 22.0, 25.0, 28.0], skippable: false, integer: false };
+const GOOD_WIND_INPUT: NumericInput = NumericInput { min: 1.0, max: 100.0, unit: " km/h", presets: &[10.0, 15.0, 20.0, 25.0], skippable: false, integer: false };
+const GOOD_WINDOW_INPUT: NumericInput = NumericInput { min: 1.0, max: 12.0, unit: " h", presets: &[2.0, 3.0, 4.0, 6.0], skippable: false, integer: true };
+
+/// Значение условия составного правила: диапазон и кнопки берутся из показателя
+fn rule_value_input(metric: Metric) -> NumericInput {
+    let range = metric.range();
+    NumericInput { min: range.min, max: range.max, unit: metric.unit(), presets: range.presets, skippable: false, integer: range.integer }
+}
+
+/// Числовой шаг, которого сейчас ждёт мастер. None - ждём не число (или ничего)
+fn awaited_numeric_input(user_data: &crate::state::UserData) -> Option<NumericInput> {
+    if user_data.waiting_for_alert_rule_value {
+        return user_data.pending_rule_metric.map(rule_value_input);
+    }
+    
+    let steps = [
+        (user_data.waiting_for_alert_hours_input || user_data.waiting_for_edit_hours, &HOURS_INPUT),
+        (user_data.waiting_for_alert_temperature_min, &TEMPERATURE_MIN_INPUT),
+        (user_data.waiting_for_alert_temperature_max, &TEMPERATURE_MAX_INPUT),
+        (user_data.waiting_for_alert_wind_speed, &WIND_INPUT),
+        (user_data.waiting_for_alert_humidity_min, &HUMIDITY_MIN_INPUT),
+        (user_data.waiting_for_alert_humidity_max, &HUMIDITY_MAX_INPUT),
+        (user_data.waiting_for_alert_precip_chance, &PRECIP_CHANCE_INPUT),
+        (user_data.waiting_for_alert_precip_mm, &PRECIP_MM_INPUT),
+        (user_data.waiting_for_alert_uv_max, &UV_INPUT),
+        (user_data.waiting_for_alert_aqi_index, &AQI_INDEX_INPUT),
+        (user_data.waiting_for_alert_aqi_pm25, &PM25_INPUT),
+        (user_data.waiting_for_alert_feels_min, &FEELS_MIN_INPUT),
+        (user_data.waiting_for_alert_feels_max, &FEELS_MAX_INPUT),
+        (user_data.waiting_for_alert_snow_cm, &SNOW_INPUT),
+        (user_data.waiting_for_alert_frost_temp, &FROST_INPUT),
+        (user_data.waiting_for_alert_pressure_drop, &PRESSURE_DROP_INPUT),
+        (user_data.waiting_for_alert_pressure_window, &PRESSURE_WINDOW_INPUT),
+        (user_data.waiting_for_alert_swing_delta, &SWING_INPUT),
+        (user_data.waiting_for_alert_good_temp_min, &GOOD_TEMP_MIN_INPUT),
+        (user_data.waiting_for_alert_good_temp_max, &GOOD_TEMP_MAX_INPUT),
+        (user_data.waiting_for_alert_good_wind, &GOOD_WIND_INPUT),
+        (user_data.waiting_for_alert_good_window, &GOOD_WINDOW_INPUT),
+    ];
+    steps.into_iter().find(|(waiting, _)| *waiting).map(|(_, input)| *input)
+}
+
+/// Сколько записей истории показывать на одной странице
+const HISTORY_PAGE_SIZE: usize = 5;
+
+/// Час местного утра, до которого откладывает кнопка "до завтра"
+const SNOOZE_MORNING_HOUR: u32 = 7;
+
+/// Ближайшее утро в городе алерта (ночью - это же утро, а не следующее).
+/// None, если не удалось узнать часовой пояс города
+async fn next_local_morning(city: &str) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    let offset = weather_api::get_current_weather(city).await.ok()?.location.utc_offset()?;
+    let local_now = chrono::Utc::now().with_timezone(&offset);
+    let date = if local_now.hour() < SNOOZE_MORNING_HOUR {
+        local_now.date_naive()
+    } else {
+        local_now.date_naive().succ_opt()?
+    };
+    date.and_hms_opt(SNOOZE_MORNING_HOUR, 0, 0)?
+        .and_local_timezone(offset)
+        .single()
+}
+
+fn format_alert_run_status(alert: &crate::state::WeatherAlert) -> String {
+    match alert.snoozed_until {
+        _ if !alert.is_active => "⏸️ Paused".to_string(),
+        Some(until) if until > chrono::Utc::now() => format!("😴 Snoozed until {} UTC", until.format("%Y-%m-%d %H:%M")),
+        _ => "▶️ Active".to_string(),
+    }
 }
 
-pub fn make_main_menu_keyboard(_state: &SharedState, _chat_id: ChatId) -> InlineKeyboardMarkup {
-    let mut keyboard = vec![];
+/// Формат ввода расписания алерта (мастер и правка)
+const SCHEDULE_INPUT_HINT: &str = "Send e.g. \"every 30m cooldown 2h\" - either part can be left out. \
+Checks run every 5m to 1d, cooldown is up to 7d.";
 
-    // Красиво организованное главное меню
-    keyboard.push(vec![InlineKeyboardButton::callback(
-        "Current weather",
-        "current_weather_menu",
-    )]);
+/// Частота проверки и пауза между уведомлениями одной строкой
+fn format_schedule_minutes(check_interval: Option<u32>, cooldown: Option<u32>) -> String {
+    format!(
+        "checked {}, notifies {}",
+        format_minutes_setting(check_interval, "every", "on the default schedule"),
+        format_minutes_setting(cooldown, "at most once per", "with the default cooldown"),
+    )
+}
+
+fn format_alert_schedule(alert: &crate::state::WeatherAlert) -> String {
+    format_schedule_minutes(alert.check_interval_minutes, alert.cooldown_minutes)
+}
+
+/// Справка по синтаксису команды /alert
+const ALERT_COMMAND_HELP: &str = "⌨️ Create an alert in one line:\n\
+/alert <city> <condition> [within <N>h] [every <period>] [cooldown <period>]\n\n\
+Examples:\n\
+/alert Kyiv temp<-5 or wind>50 within 24h\n\
+/alert Lviv (temp>25 and humidity>70) or not rain<50\n\
+/alert \"New York\" gust>=60\n\
+/alert Warsaw frost within 12h every 30m cooldown 6h\n\
+/alert Berlin aqi>=4\n\
+/alert Paris standard\n\
+/alert Oslo standard thunder freezing blizzard\n\
+/alert Riga good temp>=8 temp<=18 wind<15 hours>=2\n\n\
+Metrics: temp, feels, wind, gust, humidity, precip, rain, snow, snowfall, uv, pressure, dewpoint, hour\n\
+Operators: < <= > >= =, joined with and / or / not and parentheses\n\
+Keywords: standard, frost, ice, aqi, good\n\
+Standard categories: thunder, freezing, blizzard, heavysnow, heavyrain, fog, heat, cold, storm\n\
+Forecast window: 1-72 hours, 24h by default.\n\
+Periods: 30m, 2h, 1d. Checks run every 5m-1d, cooldown up to 7d; omitted parts use the alert type defaults.";
+
+/// Список алертов в виде команд /alert (или описаний, если команду не построить)
+fn format_alerts_listing(alerts: &[crate::state::WeatherAlert]) -> String {
+    if alerts.is_empty() {
+        return String::new();
+    }
+    let lines = alerts.iter()
+        .map(|alert| {
+            let text = format_alert_command(alert).unwrap_or_else(|| alert.description.clone());
+            format!("{} {}", alert.alert_type.emoji(), text)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!("\n{}\n", lines)
+}
+
+fn format_alert_history(description: &str, entries: &[crate::state::AlertHistoryEntry], page: usize, total: usize) -> String {
+    if total == 0 {
+        return format!("📜 History: {}\n\nThis alert has never fired yet.", description);
+    }
     
-    keyboard.push(vec![InlineKeyboardButton::callback(
-        "Forecast", 
-        "forecast_menu"
-    )]);
+    let pages = total.div_ceil(HISTORY_PAGE_SIZE);
+    let mut message = format!("📜 History: {}\n(page {} of {}, {} events)\n", description, page + 1, pages, total);
     
-    keyboard.push(vec![InlineKeyboardButton::callback("Interested towns", "my_towns")]);
+    for entry in entries {
+        let kind = match entry.kind {
+            crate::state::AlertEventKind::Triggered => "🚨 Triggered",
+            crate::state::AlertEventKind::Cleared => "✅ All clear",
+            crate::state::AlertEventKind::Escalated => "🔔 Reminder sent",
+            crate::state::AlertEventKind::Acknowledged => "👀 Acknowledged",
+        };
+        message.push_str(&format!("\n{} — {}\n", kind, entry.timestamp.format("%Y-%m-%d %H:%M UTC")));
+        if let Some(target_time) = &entry.target_time {
+            message.push_str(&format!("🎯 Forecast for: {}\n", target_time));
+        }
+        for line in &entry.observed {
+            message.push_str(&format!("• {}\n", line));
+        }
+        if let Some(acknowledged_by) = &entry.acknowledged_by {
+            message.push_str(&format!("👤 By {}\n", acknowledged_by));
+            continue;
+        }
+        match &entry.error {
+            None => message.push_str("📬 Delivered\n"),
+            Some(error) => message.push_str(&format!("⚠️ Not delivered: {}\n", error)),
+        }
+    }
     
-    keyboard.push(vec![InlineKeyboardButton::callback("🚨 Weather Alerts", "alerts_menu")]);
+    message
+}
+
+/// Форматирует настройку в минутах ("every 15 min", "every 24 h") или значение по умолчанию
+fn format_minutes_setting(minutes: Option<u32>, prefix: &str, default: &str) -> String {
+    match minutes {
+        Some(m) if m >= 60 && m % 60 == 0 => format!("{} {} h", prefix, m / 60),
+        Some(m) => format!("{} {} min", prefix, m),
+        None => default.to_string(),
+    }
+}
+
+pub fn make_main_menu_keyboard(_state: &SharedState, _chat_id: ChatId) -> InlineKeyboardMarkup {
+    let keyboard = vec![
+        // Красиво организованное главное меню
+        vec![InlineKeyboardButton::callback(
+            "Current weather",
+            "current_weather_menu",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "Forecast", 
+            "forecast_menu"
+        )],
+        vec![InlineKeyboardButton::callback("Interested towns", "my_towns")],
+        vec![InlineKeyboardButton::callback("🚨 Weather Alerts", "alerts_menu")],
+    ];
 
     InlineKeyboardMarkup::new(keyboard)
 }
@@ -909,7 +2791,7 @@ pub fn make_my_towns_keyboard(state: &SharedState, chat_id: ChatId) -> InlineKey
     // Home town section
     if let Some(home_town) = &user_data.home_town {
         keyboard.push(vec![InlineKeyboardButton::callback(
-            &format!("🏠 Home: {} (View Weather)", home_town),
+            format!("🏠 Home: {} (View Weather)", home_town),
             "view_home_weather",
         )]);
         keyboard.push(vec![InlineKeyboardButton::callback(
@@ -932,8 +2814,8 @@ pub fn make_my_towns_keyboard(state: &SharedState, chat_id: ChatId) -> InlineKey
         
         for town in &user_data.interested_towns {
             keyboard.push(vec![InlineKeyboardButton::callback(
-                &format!("🌍 {}", town),
-                &format!("town_{}", town),
+                format!("🌍 {}", town),
+                format!("town_{}", town),
             )]);
         }
     }
@@ -951,6 +2833,16 @@ pub fn make_my_towns_keyboard(state: &SharedState, chat_id: ChatId) -> InlineKey
         )]);
     }
     
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        if user_data.weekly_digest_enabled { "📬 Weekly Digest: ON" } else { "📭 Weekly Digest: OFF" },
+        "toggle_weekly_digest",
+    )]);
+    
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        if user_data.official_warnings_enabled { "🚨 Official Warnings: ON" } else { "🔕 Official Warnings: OFF" },
+        "toggle_official_warnings",
+    )]);
+    
     keyboard.push(vec![InlineKeyboardButton::callback(
         "← Back to Main Menu",
         "back_to_main",
@@ -971,8 +2863,8 @@ pub fn make_remove_towns_keyboard(state: &SharedState, chat_id: ChatId) -> Inlin
         
         for town in &user_data.interested_towns {
             keyboard.push(vec![InlineKeyboardButton::callback(
-                &format!("🌍 {}", town),
-                &format!("remove_town_{}", town),
+                format!("🌍 {}", town),
+                format!("remove_town_{}", town),
             )]);
         }
     }
@@ -994,43 +2886,39 @@ pub fn make_cancel_keyboard() -> InlineKeyboardMarkup {
 }
 
 pub fn make_current_weather_keyboard(_state: &SharedState, _chat_id: ChatId) -> InlineKeyboardMarkup {
-    let mut keyboard = vec![];
-
-    keyboard.push(vec![InlineKeyboardButton::callback(
-        "For any city",
-        "get_weather_for",
-    )]);
-    
-    keyboard.push(vec![InlineKeyboardButton::callback(
-        "For home",
-        "get_weather_home",
-    )]);
-    
-    keyboard.push(vec![InlineKeyboardButton::callback(
-        "← Back to Main Menu",
-        "back_to_main",
-    )]);
+    let keyboard = vec![
+        vec![InlineKeyboardButton::callback(
+            "For any city",
+            "get_weather_for",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "For home",
+            "get_weather_home",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "← Back to Main Menu",
+            "back_to_main",
+        )],
+    ];
 
     InlineKeyboardMarkup::new(keyboard)
 }
 
 pub fn make_forecast_keyboard(_state: &SharedState, _chat_id: ChatId) -> InlineKeyboardMarkup {
-    let mut keyboard = vec![];
-
-    keyboard.push(vec![InlineKeyboardButton::callback(
-        "For any city",
-        "get_forecast_for",
-    )]);
-    
-    keyboard.push(vec![InlineKeyboardButton::callback(
-        "For home",
-        "get_forecast_home",
-    )]);
-    
-    keyboard.push(vec![InlineKeyboardButton::callback(
-        "← Back to Main Menu",
-        "back_to_main",
-    )]);
+    let keyboard = vec![
+        vec![InlineKeyboardButton::callback(
+            "For any city",
+            "get_forecast_for",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "For home",
+            "get_forecast_home",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "← Back to Main Menu",
+            "back_to_main",
+        )],
+    ];
 
     InlineKeyboardMarkup::new(keyboard)
 }
@@ -1047,24 +2935,17 @@ pub fn make_alerts_menu_keyboard(state: &SharedState, chat_id: ChatId) -> Inline
         )]);
         
         for alert in &user_data.weather_alerts {
-            let alert_type_emoji = match &alert.alert_type {
-                AlertType::StandardWeatherAlert => "🚨",
-                AlertType::TemperatureThreshold { .. } => "🌡️",
-                AlertType::WindSpeed { .. } => "💨",
-                AlertType::Humidity { .. } => "💧",
+            let status = if !alert.is_active {
+                "⏸️"
+            } else if alert.is_snoozed(chrono::Utc::now()) {
+                "😴"
+            } else {
+                "✅"
             };
             
-            let status = if alert.is_active { "✅" } else { "❌" };
-            
             keyboard.push(vec![InlineKeyboardButton::callback(
-                &format!("{} {} - {} {}", alert_type_emoji, alert.city, 
-                        match &alert.alert_type {
-                            AlertType::StandardWeatherAlert => "Standard",
-                            AlertType::TemperatureThreshold { .. } => "Temperature",
-                            AlertType::WindSpeed { .. } => "Wind",
-                            AlertType::Humidity { .. } => "Humidity",
-                        }, status),
-                &format!("check_alert_{}", alert.id),
+                format!("{} {} - {} {}", alert.alert_type.emoji(), alert.city, alert.alert_type.short_name(), status),
+                format!("check_alert_{}", alert.id),
             )]);
         }
     }
@@ -1091,33 +2972,444 @@ pub fn make_alerts_menu_keyboard(state: &SharedState, chat_id: ChatId) -> Inline
 }
 
 pub fn make_add_alert_keyboard() -> InlineKeyboardMarkup {
+    let keyboard = vec![
+        vec![InlineKeyboardButton::callback(
+            "📦 Ready-made Alerts",
+            "alert_presets",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "🚨 Standard Weather Alert",
+            "add_standard_alert",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "🌡️ Temperature Alert",
+            "add_temperature_alert",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "💨 Wind Speed Alert",
+            "add_wind_alert",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "💧 Humidity Alert",
+            "add_humidity_alert",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "🌧️ Precipitation Alert",
+            "add_precipitation_alert",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "☀️ UV Index Alert",
+            "add_uv_alert",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "🫁 Air Quality Alert",
+            "add_air_quality_alert",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "📉 Pressure Drop Alert",
+            "add_pressure_drop_alert",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "🥶 Frost Alert",
+            "add_frost_alert",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "🧊 Snow & Ice Alert",
+            "add_snow_ice_alert",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "🥵 Feels Like Alert",
+            "add_feels_like_alert",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "🧩 Custom Rule (AND/OR)",
+            "add_composite_alert",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "🎢 Temperature Swing Alert",
+            "add_temperature_swing_alert",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "🌤️ Good Weather Window",
+            "add_good_weather_alert",
+        )],
+        vec![InlineKeyboardButton::callback(
+            "← Back to Alerts Menu",
+            "alerts_menu",
+        )],
+    ];
+
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub fn make_alert_details_keyboard(alert: &crate::state::WeatherAlert) -> InlineKeyboardMarkup {
     let mut keyboard = vec![];
     
+    if alert.is_snoozed(chrono::Utc::now()) {
+        keyboard.push(vec![InlineKeyboardButton::callback(
+            "🔔 Resume now",
+            format!("resume_alert_{}", alert.id),
+        )]);
+    } else if alert.is_active {
+        keyboard.push(make_snooze_buttons(&alert.id));
+    }
+    
     keyboard.push(vec![InlineKeyboardButton::callback(
-        "🚨 Standard Weather Alert",
-        "add_standard_alert",
+        if alert.is_active { "⏸️ Pause" } else { "▶️ Resume" },
+        format!("toggle_alert_{}", alert.id),
     )]);
     
+    keyboard.push(vec![
+        InlineKeyboardButton::callback("✏️ Edit", format!("edit_alert_{}", alert.id)),
+        InlineKeyboardButton::callback("📜 History", format!("alert_history_{}_0", alert.id)),
+    ]);
+    
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub fn make_schedule_keyboard(defaults_label: &str) -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![
+        vec![InlineKeyboardButton::callback(defaults_label, "schedule_default")],
+        vec![InlineKeyboardButton::callback("Cancel", "cancel")],
+    ])
+}
+
+pub fn make_edit_alert_keyboard(alert: &crate::state::WeatherAlert) -> InlineKeyboardMarkup {
+    let mut keyboard = vec![];
+    
+    keyboard.push(vec![
+        InlineKeyboardButton::callback("🏙️ City", format!("edit_city_{}", alert.id)),
+        InlineKeyboardButton::callback("🎚️ Thresholds", format!("edit_params_{}", alert.id)),
+    ]);
+    
+    keyboard.push(vec![
+        InlineKeyboardButton::callback("🕐 Lead time", format!("edit_hours_{}", alert.id)),
+        InlineKeyboardButton::callback("📝 Description", format!("edit_desc_{}", alert.id)),
+    ]);
+    
     keyboard.push(vec![InlineKeyboardButton::callback(
-        "🌡️ Temperature Alert",
-        "add_temperature_alert",
+        "🔁 Check frequency & cooldown",
+        format!("edit_schedule_{}", alert.id),
     )]);
     
     keyboard.push(vec![InlineKeyboardButton::callback(
-        "💨 Wind Speed Alert",
-        "add_wind_alert",
+        "← Back to Alert",
+        format!("check_alert_{}", alert.id),
     )]);
     
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+/// Открепляет напоминание о неподтвержденном алерте (если оно было закреплено)
+pub async fn unpin_escalation(bot: &Bot, chat_id: ChatId, alert_id: &str, message_id: Option<i32>) {
+    let Some(message_id) = message_id else {
+        return;
+    };
+    if let Err(e) = bot.unpin_chat_message(chat_id).message_id(teloxide::types::MessageId(message_id)).await {
+        log::debug!("Failed to unpin escalation for alert {}: {}", alert_id, e);
+    }
+}
+
+/// Кнопки под доставленным уведомлением алерта. Критичные алерты дополнительно просят подтверждения
+pub fn make_delivered_alert_keyboard(alert_id: &str, needs_ack: bool) -> InlineKeyboardMarkup {
+    let mut keyboard = vec![];
+    
+    if needs_ack {
+        keyboard.push(vec![InlineKeyboardButton::callback("✅ Got it", format!("ack_alert_{}", alert_id))]);
+    }
+    
+    keyboard.push(make_snooze_buttons(alert_id));
+    
+    keyboard.push(vec![
+        InlineKeyboardButton::callback("⏸️ Pause", format!("pause_alert_{}", alert_id)),
+        InlineKeyboardButton::callback("🔍 Details", format!("check_alert_{}", alert_id)),
+    ]);
+    
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+fn make_snooze_buttons(alert_id: &str) -> Vec<InlineKeyboardButton> {
+    vec![
+        InlineKeyboardButton::callback("😴 6h", format!("snooze_6h_{}", alert_id)),
+        InlineKeyboardButton::callback("😴 24h", format!("snooze_24h_{}", alert_id)),
+        InlineKeyboardButton::callback("🌅 Until tomorrow", format!("snooze_tmr_{}", alert_id)),
+    ]
+}
+
+pub fn make_alert_history_keyboard(alert_id: &str, page: usize, total: usize) -> InlineKeyboardMarkup {
+    let mut keyboard = vec![];
+    
+    let mut navigation = vec![];
+    if page > 0 {
+        navigation.push(InlineKeyboardButton::callback(
+            "◀ Newer",
+            format!("alert_history_{}_{}", alert_id, page - 1),
+        ));
+    }
+    if (page + 1) * HISTORY_PAGE_SIZE < total {
+        navigation.push(InlineKeyboardButton::callback(
+            "Older ▶",
+            format!("alert_history_{}_{}", alert_id, page + 1),
+        ));
+    }
+    if !navigation.is_empty() {
+        keyboard.push(navigation);
+    }
+    
     keyboard.push(vec![InlineKeyboardButton::callback(
-        "💧 Humidity Alert",
-        "add_humidity_alert",
+        "← Back to Alert",
+        format!("check_alert_{}", alert_id),
     )]);
     
     keyboard.push(vec![InlineKeyboardButton::callback(
         "← Back to Alerts Menu",
         "alerts_menu",
     )]);
+    
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub fn make_precipitation_kind_keyboard() -> InlineKeyboardMarkup {
+    let keyboard = vec![
+        vec![
+            InlineKeyboardButton::callback("🌧️ Rain", "precip_kind_rain"),
+            InlineKeyboardButton::callback("❄️ Snow", "precip_kind_snow"),
+        ],
+        vec![InlineKeyboardButton::callback(
+            "🌨️ Any precipitation",
+            "precip_kind_any",
+        )],
+        vec![InlineKeyboardButton::callback("Cancel", "cancel")],
+    ];
+    
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub fn make_rule_metric_keyboard() -> InlineKeyboardMarkup {
+    let mut keyboard = vec![];
+    
+    for row in Metric::ALL.chunks(2) {
+        keyboard.push(row.iter()
+            .map(|metric| InlineKeyboardButton::callback(metric.label(), format!("rule_metric_{}", metric.code())))
+            .collect());
+    }
+    
+    keyboard.push(vec![InlineKeyboardButton::callback("Cancel", "cancel")]);
+    
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub fn make_rule_op_keyboard() -> InlineKeyboardMarkup {
+    let mut keyboard = vec![];
+    
+    keyboard.push(Comparison::ALL.iter()
+        .map(|op| InlineKeyboardButton::callback(op.symbol(), format!("rule_op_{}", op.code())))
+        .collect());
+    
+    keyboard.push(vec![InlineKeyboardButton::callback("Cancel", "cancel")]);
+    
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub fn make_rule_next_keyboard(rule: &RuleExpr) -> InlineKeyboardMarkup {
+    let mut keyboard = vec![];
+    
+    if rule.conditions().len() < MAX_RULE_CONDITIONS {
+        keyboard.push(vec![
+            InlineKeyboardButton::callback("➕ AND", "rule_and"),
+            InlineKeyboardButton::callback("➕ OR", "rule_or"),
+        ]);
+    }
+    
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        "🔄 Negate last condition (NOT)",
+        "rule_not",
+    )]);
+    
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        "✅ Done",
+        "rule_done",
+    )]);
+    
+    keyboard.push(vec![InlineKeyboardButton::callback("Cancel", "cancel")]);
+    
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub fn make_alert_presets_keyboard() -> InlineKeyboardMarkup {
+    let mut keyboard = vec![];
+    
+    for preset in alert_presets() {
+        keyboard.push(vec![InlineKeyboardButton::callback(
+            preset.title.clone(),
+            format!("alert_preset_{}", preset.id),
+        )]);
+    }
+    
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        "← Back",
+        "add_alert",
+    )]);
+    
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub fn make_preset_location_keyboard(preset: &AlertPreset, towns: &[(String, String, bool)]) -> InlineKeyboardMarkup {
+    let mut keyboard = vec![];
+    
+    for (key, city, is_home) in towns {
+        let label = if *is_home { format!("🏠 {}", city) } else { format!("📍 {}", city) };
+        keyboard.push(vec![InlineKeyboardButton::callback(
+            label,
+            format!("apply_preset_{}_{}", key, preset.id),
+        )]);
+    }
+    
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        "← Back to Presets",
+        "alert_presets",
+    )]);
+    
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub fn make_feels_like_preset_keyboard() -> InlineKeyboardMarkup {
+    let mut keyboard = vec![];
+    
+    for preset in &FEELS_LIKE_PRESETS {
+        keyboard.push(vec![InlineKeyboardButton::callback(
+            preset.label,
+            format!("feels_preset_{}", preset.id),
+        )]);
+    }
+    
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        "✏️ Custom thresholds",
+        "feels_custom",
+    )]);
+    
+    keyboard.push(vec![InlineKeyboardButton::callback("Cancel", "cancel")]);
+    
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub fn make_standard_categories_keyboard(selected: &[SevereCategory]) -> InlineKeyboardMarkup {
+    let mut keyboard = vec![];
+    
+    // По две категории в строке, выбранные отмечены галочкой
+    for row in SevereCategory::ALL.chunks(2) {
+        keyboard.push(row.iter()
+            .map(|category| {
+                let mark = if selected.contains(category) { "✅" } else { "⬜" };
+                InlineKeyboardButton::callback(format!("{} {}", mark, category.label()), format!("std_cat_{}", category.code()))
+            })
+            .collect());
+    }
+    
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        "✔️ Done",
+        "std_cat_done",
+    )]);
+    
+    keyboard.push(vec![InlineKeyboardButton::callback("Cancel", "cancel")]);
+    
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub fn make_swing_direction_keyboard() -> InlineKeyboardMarkup {
+    let keyboard = vec![
+        vec![
+            InlineKeyboardButton::callback("📉 Colder", "swing_dir_drop"),
+            InlineKeyboardButton::callback("📈 Warmer", "swing_dir_rise"),
+        ],
+        vec![InlineKeyboardButton::callback(
+            "↕️ Either way",
+            "swing_dir_any",
+        )],
+        vec![InlineKeyboardButton::callback("Cancel", "cancel")],
+    ];
+    
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub fn make_swing_mode_keyboard() -> InlineKeyboardMarkup {
+    let keyboard = vec![
+        vec![InlineKeyboardButton::callback(
+            "📅 Each day vs the day before",
+            "swing_mode_day",
+        )],
+        vec![
+            InlineKeyboardButton::callback("⏱️ Within 6 h", "swing_mode_6"),
+            InlineKeyboardButton::callback("⏱️ Within 12 h", "swing_mode_12"),
+            InlineKeyboardButton::callback("⏱️ Within 24 h", "swing_mode_24"),
+        ],
+        vec![InlineKeyboardButton::callback("Cancel", "cancel")],
+    ];
+    
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub fn make_good_weather_preset_keyboard() -> InlineKeyboardMarkup {
+    let mut keyboard = vec![];
+    
+    for preset in &GOOD_WEATHER_PRESETS {
+        keyboard.push(vec![InlineKeyboardButton::callback(
+            preset.label,
+            format!("good_preset_{}", preset.id),
+        )]);
+    }
+    
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        "✏️ Custom conditions",
+        "good_custom",
+    )]);
+    
+    keyboard.push(vec![InlineKeyboardButton::callback("Cancel", "cancel")]);
+    
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub fn make_wind_gusts_keyboard() -> InlineKeyboardMarkup {
+    let keyboard = vec![
+        vec![
+            InlineKeyboardButton::callback("💨 Sustained wind", "wind_gusts_no"),
+            InlineKeyboardButton::callback("🌬️ Gusts", "wind_gusts_yes"),
+        ],
+        vec![InlineKeyboardButton::callback("Cancel", "cancel")],
+    ];
+    
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+pub fn make_wind_direction_keyboard() -> InlineKeyboardMarkup {
+    let mut keyboard = vec![];
+    
+    // Роза ветров: две строки по четыре румба
+    for row in WindSector::ALL.chunks(4) {
+        keyboard.push(row.iter()
+            .map(|sector| InlineKeyboardButton::callback(sector.code(), format!("wind_dir_{}", sector.code())))
+            .collect());
+    }
+    
+    keyboard.push(vec![InlineKeyboardButton::callback(
+        "🧭 Any direction",
+        "wind_dir_any",
+    )]);
+    
+    keyboard.push(vec![InlineKeyboardButton::callback("Cancel", "cancel")]);
+    
+    InlineKeyboardMarkup::new(keyboard)
+}
 
+pub fn make_snow_ice_keyboard() -> InlineKeyboardMarkup {
+    let keyboard = vec![
+        vec![
+            InlineKeyboardButton::callback("🧊 Yes, warn about ice", "snow_ice_yes"),
+            InlineKeyboardButton::callback("❄️ Snow only", "snow_ice_no"),
+        ],
+        vec![InlineKeyboardButton::callback("Cancel", "cancel")],
+    ];
+    
     InlineKeyboardMarkup::new(keyboard)
 }
 
@@ -1132,19 +3424,12 @@ pub fn make_remove_alerts_keyboard(state: &SharedState, chat_id: ChatId) -> Inli
         )]);
         
         for alert in &user_data.weather_alerts {
-            let alert_type_str = match &alert.alert_type {
-                AlertType::StandardWeatherAlert => "🚨 Standard",
-                AlertType::TemperatureThreshold { .. } => "🌡️ Temperature",
-                AlertType::WindSpeed { .. } => "💨 Wind",
-                AlertType::Humidity { .. } => "💧 Humidity",
-            };
-            
-            let button_text = format!("{} - {} ({})", alert_type_str, alert.city, 
-                                    if alert.is_active { "Active" } else { "Inactive" });
+            let button_text = format!("{} {} - {} ({})", alert.alert_type.emoji(), alert.alert_type.short_name(), alert.city, 
+                                    format_alert_run_status(alert));
             
             keyboard.push(vec![InlineKeyboardButton::callback(
-                &button_text,
-                &format!("remove_alert_{}", alert.id),
+                button_text,
+                format!("remove_alert_{}", alert.id),
             )]);
         }
     }
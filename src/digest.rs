@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use chrono::{DateTime, Datelike, Timelike, Utc, Weekday};
+use crate::state::{UserData, WeatherAlert};
+use crate::alerts::AlertChecker;
+use crate::weather_api::{normalize_location, ForecastDay, ForecastResponse};
+
+/// День недели и час (UTC), когда рассылается недельный дайджест
+pub const DIGEST_WEEKDAY: Weekday = Weekday::Sun;
+pub const DIGEST_HOUR_UTC: u32 = 18;
+/// Сегодняшний день + 7 дней предстоящей недели. WeatherAPI отдает не больше дней, чем позволяет тариф
+/// (на бесплатном - 3, то есть 2 дня после сегодняшнего), поэтому реальный охват пишется в заголовке
+pub const DIGEST_FORECAST_DAYS: u8 = 8;
+
+/// Прогнозы городов дайджеста по нормализованному названию. None - прогноз получить не удалось
+pub type DigestForecasts = HashMap<String, Option<ForecastResponse>>;
+
+/// Человекочитаемое расписание рассылки, например "Sunday at 18:00 UTC"
+pub fn describe_schedule() -> String {
+    let day = match DIGEST_WEEKDAY {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    };
+    format!("{} at {:02}:00 UTC", day, DIGEST_HOUR_UTC)
+}
+
+/// Пора ли отправлять дайджест пользователю
+pub fn is_digest_due(user_data: &UserData, now: DateTime<Utc>) -> bool {
+    if !user_data.weekly_digest_enabled {
+        return false;
+    }
+    if now.weekday() != DIGEST_WEEKDAY || now.hour() < DIGEST_HOUR_UTC {
+        return false;
+    }
+    // Не больше одного дайджеста в неделю
+    match user_data.last_weekly_digest {
+        Some(last_sent) => (now - last_sent).num_days() >= 6,
+        None => true,
+    }
+}
+
+/// Города пользователя для дайджеста: домашний и интересующие, без повторов
+pub fn digest_towns(user_data: &UserData) -> Vec<String> {
+    let mut towns: Vec<String> = Vec::new();
+    for town in user_data.home_town.iter().chain(user_data.interested_towns.iter()) {
+        if !towns.iter().any(|t| normalize_location(t) == normalize_location(town)) {
+            towns.push(town.clone());
+        }
+    }
+    towns
+}
+
+/// Собирает текст недельного дайджеста из заранее загруженных прогнозов. Возвращает None, если городов нет
+pub fn build_weekly_digest(user_data: &UserData, forecasts: &DigestForecasts) -> Option<String> {
+    let towns = digest_towns(user_data);
+    if towns.is_empty() {
+        return None;
+    }
+    
+    let mut body = String::new();
+    let mut covered_days = 0;
+    
+    for town in &towns {
+        match forecasts.get(&normalize_location(town)).and_then(Option::as_ref) {
+            Some(forecast) => {
+                let town_alerts: Vec<&WeatherAlert> = user_data.weather_alerts.iter()
+                    .filter(|a| a.is_active && normalize_location(&a.city) == normalize_location(town))
+                    .collect();
+                covered_days = covered_days.max(upcoming_days(forecast).len());
+                body.push('\n');
+                body.push_str(&format_town_digest(forecast, &town_alerts));
+            }
+            None => body.push_str(&format!("\n🌍 {}\n❌ Forecast unavailable\n", town)),
+        }
+    }
+    
+    // Сколько дней реально покрывает прогноз, зависит от тарифа API - пишем как есть
+    let header = match covered_days {
+        0 => "📬 WEEKLY OUTLOOK\n".to_string(),
+        1 => "📬 WEEKLY OUTLOOK · next 1 day\n".to_string(),
+        days => format!("📬 WEEKLY OUTLOOK · next {} days\n", days),
+    };
+    
+    Some(header + &body)
+}
+
+/// Дни прогноза без сегодняшнего - дайджест про предстоящие дни
+fn upcoming_days(forecast: &ForecastResponse) -> &[ForecastDay] {
+    let all_days = &forecast.forecast.forecast_day;
+    if all_days.len() > 1 { &all_days[1..] } else { all_days }
+}
+
+pub fn format_town_digest(forecast: &ForecastResponse, alerts: &[&WeatherAlert]) -> String {
+    let days = upcoming_days(forecast);
+    
+    let mut text = format!("🌍 {}, {}\n", forecast.location.name, forecast.location.country);
+    
+    if days.is_empty() {
+        text.push_str("No forecast data\n");
+        return text;
+    }
+    
+    for day in days {
+        text.push_str(&format!(
+            "📆 {}: {}°C - {}°C, {}, 🌧 {} mm\n",
+            format_digest_date(&day.date),
+            day.day.min_temp,
+            day.day.max_temp,
+            day.day.condition.text,
+            day.day.total_precip
+        ));
+    }
+    
+    let warmest = days.iter().max_by(|a, b| a.day.max_temp.total_cmp(&b.day.max_temp));
+    let coldest = days.iter().min_by(|a, b| a.day.min_temp.total_cmp(&b.day.min_temp));
+    let wettest = days.iter().max_by(|a, b| a.day.total_precip.total_cmp(&b.day.total_precip));
+    
+    if let Some(day) = warmest {
+        text.push_str(&format!("🔥 Warmest: {} (up to {}°C)\n", format_digest_date(&day.date), day.day.max_temp));
+    }
+    if let Some(day) = coldest {
+        text.push_str(&format!("🥶 Coldest: {} (down to {}°C)\n", format_digest_date(&day.date), day.day.min_temp));
+    }
+    match wettest {
+        Some(day) if day.day.total_precip > 0.0 => {
+            text.push_str(&format!("☔ Wettest: {} ({} mm)\n", format_digest_date(&day.date), day.day.total_precip));
+        }
+        _ => text.push_str("☀️ No rain expected\n"),
+    }
+    
+    // Дни, в которые сработали бы алерты пользователя
+    for alert in alerts {
+        let alert_days: Vec<String> = days.iter()
+            .filter(|day| AlertChecker::check_forecast_day(day, &alert.alert_type).unwrap_or(false))
+            .map(|day| format_digest_date(&day.date))
+            .collect();
+        if !alert_days.is_empty() {
+            let mark = if alert.alert_type.is_opportunity() { "🌤️" } else { "⚠️" };
+            text.push_str(&format!("{} {}: {}\n", mark, alert.description, alert_days.join(", ")));
+        }
+    }
+    
+    text
+}
+
+fn format_digest_date(date: &str) -> String {
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|d| d.format("%a %d %b").to_string())
+        .unwrap_or_else(|_| date.to_string())
+}
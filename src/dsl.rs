@@ -0,0 +1,973 @@
+//! Текстовый язык алертов для команды /alert.
+//!
+//! Формат: `/alert <город> <выражение> [within <N>h] [every <период>] [cooldown <период>]`, например
+//! `/alert Kyiv temp<-5 or wind>50 within 24h every 30m cooldown 2h`.
+//!
+//! Выражение - условия `<показатель> <сравнение> <число>` (коды показателей из rules::Metric),
+//! объединенные через `and` / `or` / `not` и скобки. Одиночные условия вида `temp<-5`, `wind>50`,
+//! `uv>6` и ключевые слова `standard [категории]`, `frost`, `ice`, `aqi>=N`, `good [параметры]` превращаются в обычные типы алертов,
+//! остальное - в составное правило (AlertType::Composite). Составное правило, которое совпало бы
+//! с обычным типом, записывается в скобках: `(temp > 25)`.
+
+use crate::alerts::{create_alert, DEFAULT_FROST_AIR_TEMP_C};
+use crate::rules::{Comparison, Metric, RuleCondition, RuleExpr};
+use crate::state::{AlertType, SevereCategory, WeatherAlert};
+
+/// Параметры `good` по умолчанию - как в мастере: 18-25°C, ветер слабее 15 км/ч, 3 ч. подряд
+const DEFAULT_GOOD_WEATHER: (f32, f32, f32, u8) = (18.0, 25.0, 15.0, 3);
+const MAX_GOOD_WEATHER_HOURS: u8 = 12;
+
+/// Горизонт предупреждения, если `within` не указан
+pub const DEFAULT_DSL_HOURS: u8 = 24;
+pub const MAX_DSL_HOURS: u8 = 72;
+/// Допустимая частота проверки (`every`) и пауза между уведомлениями (`cooldown`), в минутах
+pub const MIN_CHECK_INTERVAL_MINUTES: u32 = 5;
+pub const MAX_CHECK_INTERVAL_MINUTES: u32 = 24 * 60;
+pub const MAX_COOLDOWN_MINUTES: u32 = 7 * 24 * 60;
+
+/// Собственное расписание алерта. None - значение по умолчанию для типа алерта
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AlertSchedule {
+    pub check_interval_minutes: Option<u32>,
+    pub cooldown_minutes: Option<u32>,
+}
+
+impl AlertSchedule {
+    /// Переносит заданные значения в алерт, остальные не трогает
+    pub fn apply(&self, alert: &mut WeatherAlert) {
+        if let Some(minutes) = self.check_interval_minutes {
+            alert.check_interval_minutes = Some(minutes);
+        }
+        if let Some(minutes) = self.cooldown_minutes {
+            alert.cooldown_minutes = Some(minutes);
+        }
+    }
+}
+
+/// Разобранная команда /alert
+#[derive(Debug, Clone)]
+pub struct ParsedAlert {
+    pub city: String,
+    pub alert_type: AlertType,
+    pub hours_ahead: u8,
+    pub schedule: AlertSchedule,
+}
+
+impl ParsedAlert {
+    pub fn into_alert(self) -> WeatherAlert {
+        let mut alert = create_alert(self.city, self.alert_type, self.hours_ahead);
+        self.schedule.apply(&mut alert);
+        alert
+    }
+}
+
+/// Ошибка разбора с позицией проблемного фрагмента во входной строке (в байтах)
+#[derive(Debug, Clone)]
+pub struct DslError {
+    pub message: String,
+    pub span: Option<(usize, usize)>,
+}
+
+impl DslError {
+    fn at(message: impl Into<String>, span: (usize, usize)) -> Self {
+        Self { message: message.into(), span: Some(span) }
+    }
+
+    fn general(message: impl Into<String>) -> Self {
+        Self { message: message.into(), span: None }
+    }
+
+    /// Текст ошибки с указателем на проблемный фрагмент: `temp<-5 or 👉wnd👈>50`
+    pub fn render(&self, input: &str) -> String {
+        match self.span {
+            Some((start, end)) if end <= input.len() && start <= end => {
+                let fragment = if start == end { "…".to_string() } else { input[start..end].to_string() };
+                format!(
+                    "❌ {}\n\n{}👉{}👈{}",
+                    self.message,
+                    &input[..start],
+                    fragment,
+                    &input[end..]
+                )
+            }
+            _ => format!("❌ {}", self.message),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Word(String),
+    Quoted(String),
+    Number(f32),
+    Op(Comparison),
+    LParen,
+    RParen,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    span: (usize, usize),
+}
+
+impl Token {
+    /// Слово в нижнем регистре (для ключевых слов и кодов показателей)
+    fn word(&self) -> Option<String> {
+        match &self.kind {
+            TokenKind::Word(word) => Some(word.to_lowercase()),
+            _ => None,
+        }
+    }
+
+    fn is_word(&self, expected: &str) -> bool {
+        self.word().is_some_and(|word| word == expected)
+    }
+}
+
+/// Единицы измерения, которые можно писать сразу после числа: `wind>50km/h`, `rain>=60%`
+const NUMBER_UNITS: [&str; 8] = ["km/h", "kmh", "°c", "hpa", "mm", "cm", "c", "%"];
+
+fn parse_number(word: &str) -> Option<f32> {
+    let lower = word.to_lowercase();
+    let digits = NUMBER_UNITS.iter()
+        .find_map(|unit| lower.strip_suffix(unit))
+        .unwrap_or(&lower);
+    digits.parse::<f32>().ok().filter(|value| value.is_finite())
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, DslError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(start, ch)) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        match ch {
+            '(' | ')' => {
+                chars.next();
+                let kind = if ch == '(' { TokenKind::LParen } else { TokenKind::RParen };
+                tokens.push(Token { kind, span: (start, start + 1) });
+            }
+            '<' | '>' | '=' | '!' => {
+                chars.next();
+                let mut end = start + 1;
+                let mut symbol = ch.to_string();
+                if let Some(&(next_index, '=')) = chars.peek() {
+                    chars.next();
+                    symbol.push('=');
+                    end = next_index + 1;
+                }
+                let op = match symbol.as_str() {
+                    "==" => Comparison::Equal,
+                    other => Comparison::from_code(other)
+                        .ok_or_else(|| DslError::at(format!("Unknown comparison '{}'. Use <, <=, >, >= or =", other), (start, end)))?,
+                };
+                tokens.push(Token { kind: TokenKind::Op(op), span: (start, end) });
+            }
+            '"' => {
+                chars.next();
+                let mut text = String::new();
+                let mut end = None;
+                for (index, c) in chars.by_ref() {
+                    if c == '"' {
+                        end = Some(index + 1);
+                        break;
+                    }
+                    text.push(c);
+                }
+                let end = end.ok_or_else(|| DslError::at("Missing closing quote", (start, input.len())))?;
+                tokens.push(Token { kind: TokenKind::Quoted(text.trim().to_string()), span: (start, end) });
+            }
+            _ => {
+                let mut end = start;
+                while let Some(&(index, c)) = chars.peek() {
+                    if c.is_whitespace() || "()<>=!\"".contains(c) {
+                        break;
+                    }
+                    end = index + c.len_utf8();
+                    chars.next();
+                }
+                let word = &input[start..end];
+                let kind = match parse_number(word) {
+                    Some(value) => TokenKind::Number(value),
+                    None => TokenKind::Word(word.to_string()),
+                };
+                tokens.push(Token { kind, span: (start, end) });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Ключевые слова, которые задают готовый тип алерта целиком
+const TYPE_KEYWORDS: [&str; 5] = ["standard", "frost", "ice", "aqi", "good"];
+/// Ключевые слова хвоста команды, идут после выражения в любом порядке
+const CLAUSE_KEYWORDS: [&str; 3] = ["within", "every", "cooldown"];
+
+fn is_clause_keyword(token: &Token) -> bool {
+    token.word().is_some_and(|word| CLAUSE_KEYWORDS.contains(&word.as_str()))
+}
+
+/// Может ли с этого токена начинаться выражение (все до него - название города)
+fn starts_expression(tokens: &[Token], index: usize) -> bool {
+    let token = &tokens[index];
+    if matches!(token.kind, TokenKind::LParen) {
+        return true;
+    }
+    let Some(word) = token.word() else {
+        return false;
+    };
+    if word == "not" || TYPE_KEYWORDS.contains(&word.as_str()) {
+        return true;
+    }
+    Metric::from_code(&word).is_some()
+        && matches!(tokens.get(index + 1).map(|t| &t.kind), Some(TokenKind::Op(_)))
+}
+
+/// Разбирает аргументы команды /alert (все, что после самой команды)
+pub fn parse_alert_command(input: &str) -> Result<ParsedAlert, DslError> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Err(DslError::general("Empty command. Example: /alert Kyiv temp<-5 or wind>50 within 24h"));
+    }
+
+    // Город: строка в кавычках или слова до начала выражения
+    let (city, expression_start) = match &tokens[0].kind {
+        TokenKind::Quoted(city) => (city.clone(), 1),
+        _ => {
+            let start = (0..tokens.len())
+                .find(|&index| starts_expression(&tokens, index))
+                .unwrap_or(tokens.len());
+            let city = match (tokens.first(), start.checked_sub(1).and_then(|last| tokens.get(last))) {
+                (Some(first), Some(last)) if start > 0 => input[first.span.0..last.span.1].trim().to_string(),
+                _ => String::new(),
+            };
+            (city, start)
+        }
+    };
+    if city.is_empty() {
+        let span = tokens.first().map(|t| (t.span.0, t.span.0)).unwrap_or((0, 0));
+        return Err(DslError::at("Expected a city before the conditions", span));
+    }
+
+    // Хвост "within 24h every 30m cooldown 2h"
+    let expression_end = (expression_start..tokens.len())
+        .find(|&index| is_clause_keyword(&tokens[index]))
+        .unwrap_or(tokens.len());
+    let (hours_ahead, schedule) = parse_clauses(&tokens[expression_end..], input.len())?;
+
+    let expression = &tokens[expression_start..expression_end];
+    if expression.is_empty() {
+        let position = tokens.get(expression_end).map(|t| t.span.0).unwrap_or(input.len());
+        return Err(DslError::at("Expected conditions after the city, e.g. temp<-5", (position, position)));
+    }
+
+    let alert_type = parse_expression(expression, input.len())?;
+    Ok(ParsedAlert { city, alert_type, hours_ahead: hours_ahead.unwrap_or(DEFAULT_DSL_HOURS), schedule })
+}
+
+/// Разбирает только расписание: `every 30m cooldown 2h` (любая часть может отсутствовать)
+pub fn parse_schedule(input: &str) -> Result<AlertSchedule, DslError> {
+    let tokens = tokenize(input)?;
+    match tokens.first() {
+        None => Err(DslError::general("Expected a schedule like: every 30m cooldown 2h")),
+        Some(first) if !first.is_word("every") && !first.is_word("cooldown") => {
+            Err(DslError::at("Start with 'every' or 'cooldown', e.g. every 30m cooldown 2h", first.span))
+        }
+        Some(_) => match parse_clauses(&tokens, input.len())? {
+            (Some(_), _) => {
+                let within = tokens.iter().find(|t| t.is_word("within")).map(|t| t.span).unwrap_or((0, 0));
+                Err(DslError::at("The warning period is set separately - use only 'every' and 'cooldown' here", within))
+            }
+            (None, schedule) => Ok(schedule),
+        },
+    }
+}
+
+/// Хвост команды: каждое ключевое слово из CLAUSE_KEYWORDS со своим значением, не больше одного раза
+fn parse_clauses(tokens: &[Token], input_len: usize) -> Result<(Option<u8>, AlertSchedule), DslError> {
+    let mut hours_ahead = None;
+    let mut schedule = AlertSchedule::default();
+    let mut index = 0;
+    while index < tokens.len() {
+        let keyword = &tokens[index];
+        let end = (index + 1..tokens.len())
+            .find(|&next| is_clause_keyword(&tokens[next]))
+            .unwrap_or(tokens.len());
+        let value = &tokens[index + 1..end];
+        let repeated = || DslError::at("This setting is given twice", keyword.span);
+
+        match keyword.word().as_deref() {
+            Some("within") if hours_ahead.is_none() => {
+                hours_ahead = Some(parse_within(value, keyword.span, input_len)?);
+            }
+            Some("every") if schedule.check_interval_minutes.is_none() => {
+                schedule.check_interval_minutes = Some(parse_period(
+                    value, keyword.span, input_len,
+                    MIN_CHECK_INTERVAL_MINUTES..=MAX_CHECK_INTERVAL_MINUTES, "every 30m",
+                )?);
+            }
+            Some("cooldown") if schedule.cooldown_minutes.is_none() => {
+                schedule.cooldown_minutes = Some(parse_period(
+                    value, keyword.span, input_len,
+                    0..=MAX_COOLDOWN_MINUTES, "cooldown 2h",
+                )?);
+            }
+            Some("within" | "every" | "cooldown") => return Err(repeated()),
+            _ => return Err(DslError::at("Expected 'within', 'every' or 'cooldown'", keyword.span)),
+        }
+        index = end;
+    }
+    Ok((hours_ahead, schedule))
+}
+
+/// Минут в единице периода: `m`, `min`, `h`, `hours`, `d`...
+fn period_unit_minutes(unit: &str) -> Option<u32> {
+    match unit {
+        "m" | "min" | "mins" | "minute" | "minutes" => Some(1),
+        "h" | "hr" | "hour" | "hours" => Some(60),
+        "d" | "day" | "days" => Some(24 * 60),
+        _ => None,
+    }
+}
+
+/// Период с единицей: `30m`, `2h`, `1d`, `45 min`. Возвращает минуты
+fn parse_period(
+    rest: &[Token],
+    keyword_span: (usize, usize),
+    input_len: usize,
+    allowed: std::ops::RangeInclusive<u32>,
+    example: &str,
+) -> Result<u32, DslError> {
+    let Some(first) = rest.first() else {
+        return Err(DslError::at(format!("Expected a period after the keyword, e.g. {}", example), (keyword_span.1, input_len)));
+    };
+    let (value, unit, consumed) = match &first.kind {
+        TokenKind::Word(word) => {
+            let lower = word.to_lowercase();
+            let split = lower.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(lower.len());
+            (lower[..split].parse::<f32>().ok(), Some(lower[split..].to_string()), 1)
+        }
+        TokenKind::Number(value) => (Some(*value), rest.get(1).and_then(Token::word), 2),
+        _ => (None, None, 1),
+    };
+
+    let span = rest.get(consumed - 1).map_or(first.span, |last| (first.span.0, last.span.1));
+    let (Some(value), Some(unit_minutes)) = (value, unit.as_deref().and_then(period_unit_minutes)) else {
+        return Err(DslError::at(format!("Expected a period with a unit (m, h or d), e.g. {}", example), span));
+    };
+    let minutes = value * unit_minutes as f32;
+    if minutes.fract() != 0.0 || minutes < *allowed.start() as f32 || minutes > *allowed.end() as f32 {
+        return Err(DslError::at(
+            format!("The period must be whole minutes from {} to {}", format_period(*allowed.start()), format_period(*allowed.end())),
+            span,
+        ));
+    }
+    if let Some(extra) = rest.get(consumed) {
+        return Err(DslError::at("Unexpected text after the period", extra.span));
+    }
+    Ok(minutes as u32)
+}
+
+/// Период в самых крупных целых единицах: `90m`, `2h`, `1d`
+pub fn format_period(minutes: u32) -> String {
+    match minutes {
+        0 => "0m".to_string(),
+        m if m % (24 * 60) == 0 => format!("{}d", m / (24 * 60)),
+        m if m % 60 == 0 => format!("{}h", m / 60),
+        m => format!("{}m", m),
+    }
+}
+
+/// `within 24h`, `within 24 h`, `within 24 hours`
+fn parse_within(rest: &[Token], within_span: (usize, usize), input_len: usize) -> Result<u8, DslError> {
+    let Some(first) = rest.first() else {
+        return Err(DslError::at("Expected the number of hours after 'within', e.g. within 24h", (within_span.1, input_len)));
+    };
+    let (hours, consumed) = match &first.kind {
+        TokenKind::Word(word) => {
+            let lower = word.to_lowercase();
+            let digits = lower.strip_suffix("hours").or_else(|| lower.strip_suffix('h')).unwrap_or(&lower);
+            (digits.parse::<f32>().ok(), 1)
+        }
+        TokenKind::Number(value) => {
+            let unit = rest.get(1).and_then(Token::word);
+            let consumed = if matches!(unit.as_deref(), Some("h" | "hour" | "hours")) { 2 } else { 1 };
+            (Some(*value), consumed)
+        }
+        _ => (None, 1),
+    };
+
+    let hours = hours
+        .filter(|h| h.fract() == 0.0 && *h >= 1.0 && *h <= MAX_DSL_HOURS as f32)
+        .ok_or_else(|| DslError::at(format!("Hours must be a whole number from 1 to {}", MAX_DSL_HOURS), first.span))?;
+    if let Some(extra) = rest.get(consumed) {
+        return Err(DslError::at("Unexpected text after the warning period", extra.span));
+    }
+    Ok(hours as u8)
+}
+
+fn parse_expression(tokens: &[Token], input_len: usize) -> Result<AlertType, DslError> {
+    // Ключевое слово готового типа должно быть единственным в выражении
+    if let Some(keyword) = tokens[0].word().filter(|word| TYPE_KEYWORDS.contains(&word.as_str())) {
+        return parse_keyword(&keyword, tokens);
+    }
+    if let Some(token) = tokens.iter().find(|t| t.word().is_some_and(|w| TYPE_KEYWORDS.contains(&w.as_str()))) {
+        return Err(DslError::at("This keyword can't be combined with other conditions - use it on its own", token.span));
+    }
+
+    let mut parser = Parser { tokens, position: 0, input_len, outer_group_closed_at: None };
+    let rule = parser.parse_or()?;
+    if let Some(token) = parser.peek() {
+        return Err(DslError::at("Expected 'and', 'or' or the end of the rule", token.span));
+    }
+
+    // Выражение в скобках целиком всегда остается составным правилом
+    let wrapped = matches!(tokens.first().map(|t| &t.kind), Some(TokenKind::LParen))
+        && parser.outer_group_closed_at == Some(tokens.len() - 1);
+    if !wrapped {
+        if let Some(alert_type) = dedicated_type(&rule) {
+            return Ok(alert_type);
+        }
+    }
+    Ok(AlertType::Composite { rule })
+}
+
+fn parse_keyword(keyword: &str, tokens: &[Token]) -> Result<AlertType, DslError> {
+    let threshold = |rest: &[Token], expected: Comparison, example: &str| -> Result<Option<f32>, DslError> {
+        match rest {
+            [] => Ok(None),
+            [op, value] => match (&op.kind, &value.kind) {
+                (TokenKind::Op(found), TokenKind::Number(number)) if *found == expected => Ok(Some(*number)),
+                (TokenKind::Op(_), TokenKind::Number(_)) => {
+                    Err(DslError::at(format!("Only '{}' is supported here, e.g. {}", expected.symbol(), example), op.span))
+                }
+                _ => Err(DslError::at(format!("Expected a threshold like {}", example), (op.span.0, value.span.1))),
+            },
+            [first, ..] => Err(DslError::at(format!("Expected a threshold like {}", example), first.span)),
+        }
+    };
+    let rest = &tokens[1..];
+
+    match keyword {
+        "standard" => {
+            // Без списка категорий - набор по умолчанию
+            if rest.is_empty() {
+                return Ok(AlertType::StandardWeatherAlert { categories: SevereCategory::DEFAULTS.to_vec() });
+            }
+            let mut categories = rest.iter()
+                .map(|token| token.word().and_then(|word| SevereCategory::from_code(&word)).ok_or_else(|| {
+                    let codes: Vec<&str> = SevereCategory::ALL.iter().map(|category| category.code()).collect();
+                    DslError::at(format!("Unknown weather category. Use any of: {}", codes.join(", ")), token.span)
+                }))
+                .collect::<Result<Vec<_>, _>>()?;
+            categories.sort();
+            categories.dedup();
+            Ok(AlertType::StandardWeatherAlert { categories })
+        }
+        "frost" => Ok(AlertType::Frost {
+            max_temp: threshold(rest, Comparison::LessOrEqual, "frost<=1")?.unwrap_or(DEFAULT_FROST_AIR_TEMP_C),
+        }),
+        "ice" => match rest.first() {
+            None => Ok(AlertType::SnowIce { min_snow_cm: None, ice_risk: true }),
+            Some(token) => Err(DslError::at("'ice' doesn't take any parameters", token.span)),
+        },
+        "aqi" => {
+            let index = threshold(rest, Comparison::GreaterOrEqual, "aqi>=4")?
+                .ok_or_else(|| DslError::at("Expected an index like aqi>=4", tokens[0].span))?;
+            if index.fract() != 0.0 || !(1.0..=6.0).contains(&index) {
+                return Err(DslError::at("US EPA index must be a whole number from 1 to 6", rest[1].span));
+            }
+            Ok(AlertType::AirQuality { min_epa_index: index as u8, max_pm2_5: None })
+        }
+        "good" => parse_good_weather(rest, tokens[0].span),
+        _ => Err(DslError::at("Unknown keyword", tokens[0].span)),
+    }
+}
+
+/// Окно хорошей погоды: `good temp>=8 temp<=18 wind<15 hours>=2`, любой параметр можно опустить
+fn parse_good_weather(rest: &[Token], keyword_span: (usize, usize)) -> Result<AlertType, DslError> {
+    use Comparison::{GreaterOrEqual, Less, LessOrEqual};
+
+    const EXAMPLE: &str = "good temp>=8 temp<=18 wind<15 hours>=2";
+    let (mut min_temp, mut max_temp, mut max_wind, mut min_hours) = DEFAULT_GOOD_WEATHER;
+
+    for part in rest.chunks(3) {
+        let name = &part[0];
+        if !name.word().is_some_and(|word| ["temp", "wind", "hours"].contains(&word.as_str())) {
+            return Err(DslError::at(format!("Expected temp, wind or hours, e.g. {}", EXAMPLE), name.span));
+        }
+        let (Some(op), Some(value)) = (part.get(1), part.get(2)) else {
+            return Err(DslError::at(format!("Expected a parameter like {}", EXAMPLE), (name.span.0, part[part.len() - 1].span.1)));
+        };
+        let (TokenKind::Op(op), TokenKind::Number(number)) = (&op.kind, &value.kind) else {
+            return Err(DslError::at(format!("Expected a parameter like {}", EXAMPLE), (name.span.0, value.span.1)));
+        };
+        match (name.word().as_deref(), op) {
+            (Some("temp"), GreaterOrEqual) => min_temp = *number,
+            (Some("temp"), LessOrEqual) => max_temp = *number,
+            (Some("wind"), Less) => max_wind = *number,
+            (Some("hours"), GreaterOrEqual) if number.fract() == 0.0 && (1.0..=MAX_GOOD_WEATHER_HOURS as f32).contains(number) => {
+                min_hours = *number as u8;
+            }
+            (Some("hours"), GreaterOrEqual) => {
+                return Err(DslError::at(format!("Hours must be a whole number from 1 to {}", MAX_GOOD_WEATHER_HOURS), value.span));
+            }
+            _ => return Err(DslError::at("Use temp>=, temp<=, wind< or hours>= here", (name.span.0, value.span.1))),
+        }
+    }
+
+    if min_temp > max_temp {
+        return Err(DslError::at("The lowest temperature must not be above the highest", keyword_span));
+    }
+    if max_wind <= 0.0 {
+        return Err(DslError::at("Wind limit must be above 0", keyword_span));
+    }
+    Ok(AlertType::GoodWeather { min_temp, max_temp, max_wind, min_hours })
+}
+
+/// Рекурсивный спуск: or -> and -> not -> (группа | условие)
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    input_len: usize,
+    /// Индекс закрывающей скобки группы, открытой первым токеном
+    outer_group_closed_at: Option<usize>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn end_span(&self) -> (usize, usize) {
+        let end = self.tokens.last().map(|t| t.span.1).unwrap_or(self.input_len);
+        (end, end)
+    }
+
+    fn parse_or(&mut self) -> Result<RuleExpr, DslError> {
+        let mut children = vec![self.parse_and()?];
+        while self.peek().is_some_and(|t| t.is_word("or")) {
+            self.position += 1;
+            children.push(self.parse_and()?);
+        }
+        Ok(if children.len() == 1 { children.remove(0) } else { RuleExpr::Or(children) })
+    }
+
+    fn parse_and(&mut self) -> Result<RuleExpr, DslError> {
+        let mut children = vec![self.parse_not()?];
+        while self.peek().is_some_and(|t| t.is_word("and")) {
+            self.position += 1;
+            children.push(self.parse_not()?);
+        }
+        Ok(if children.len() == 1 { children.remove(0) } else { RuleExpr::And(children) })
+    }
+
+    fn parse_not(&mut self) -> Result<RuleExpr, DslError> {
+        if self.peek().is_some_and(|t| t.is_word("not")) {
+            self.position += 1;
+            return Ok(RuleExpr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<RuleExpr, DslError> {
+        let Some(token) = self.peek().cloned() else {
+            return Err(DslError::at("Unexpected end of the rule: expected a condition", self.end_span()));
+        };
+
+        if token.kind == TokenKind::LParen {
+            let open_index = self.position;
+            self.position += 1;
+            let inner = self.parse_or()?;
+            match self.peek() {
+                Some(t) if t.kind == TokenKind::RParen => {
+                    if open_index == 0 {
+                        self.outer_group_closed_at = Some(self.position);
+                    }
+                    self.position += 1;
+                    Ok(inner)
+                }
+                Some(t) => Err(DslError::at("Expected ')'", t.span)),
+                None => Err(DslError::at("Missing ')' for this '('", token.span)),
+            }
+        } else {
+            self.parse_condition()
+        }
+    }
+
+    fn parse_condition(&mut self) -> Result<RuleExpr, DslError> {
+        let metric_token = self.tokens[self.position].clone();
+        let metric = match &metric_token.kind {
+            TokenKind::Word(word) => Metric::from_code(word).ok_or_else(|| {
+                let known = Metric::ALL.iter().map(|m| m.code()).collect::<Vec<_>>().join(", ");
+                DslError::at(format!("Unknown metric '{}'. Known metrics: {}", word, known), metric_token.span)
+            })?,
+            _ => return Err(DslError::at("Expected a metric such as temp, wind or rain", metric_token.span)),
+        };
+        self.position += 1;
+
+        let op = match self.peek().map(|t| (&t.kind, t.span)) {
+            Some((TokenKind::Op(op), _)) => *op,
+            Some((_, span)) => return Err(DslError::at("Expected a comparison (<, <=, >, >=, =)", span)),
+            None => return Err(DslError::at(format!("Expected a comparison after '{}'", metric.code()), self.end_span())),
+        };
+        self.position += 1;
+
+        let value = match self.peek().map(|t| (&t.kind, t.span)) {
+            Some((TokenKind::Number(value), span)) => {
+                let range = metric.range();
+                if !range.contains(*value) {
+                    let kind = if range.integer { "a whole number" } else { "a number" };
+                    return Err(DslError::at(
+                        format!("'{}' must be {} from {} to {}{}", metric.code(), kind, range.min, range.max, metric.unit()),
+                        span,
+                    ));
+                }
+                *value
+            }
+            Some((_, span)) => return Err(DslError::at("Expected a number", span)),
+            None => return Err(DslError::at(format!("Expected a number after '{} {}'", metric.code(), op.symbol()), self.end_span())),
+        };
+        self.position += 1;
+
+        Ok(RuleExpr::Condition(RuleCondition { metric, op, value }))
+    }
+}
+
+/// Обычный тип алерта, точно совпадающий по смыслу с выражением
+fn dedicated_type(rule: &RuleExpr) -> Option<AlertType> {
+    use Comparison::{Greater, Less};
+
+    let condition = |expr: &RuleExpr| match expr {
+        RuleExpr::Condition(condition) => Some((condition.metric, condition.op, condition.value)),
+        _ => None,
+    };
+    let whole_percent = |value: f32| (value.fract() == 0.0 && (0.0..=100.0).contains(&value)).then_some(value as u32);
+
+    match rule {
+        RuleExpr::Condition(_) => match condition(rule)? {
+            (Metric::Temperature, Less, value) => Some(AlertType::TemperatureThreshold { min: Some(value), max: None }),
+            (Metric::Temperature, Greater, value) => Some(AlertType::TemperatureThreshold { min: None, max: Some(value) }),
+            (Metric::FeelsLike, Less, value) => Some(AlertType::FeelsLike { min: Some(value), max: None }),
+            (Metric::FeelsLike, Greater, value) => Some(AlertType::FeelsLike { min: None, max: Some(value) }),
+            (Metric::Humidity, Less, value) => Some(AlertType::Humidity { min: Some(whole_percent(value)?), max: None }),
+            (Metric::Humidity, Greater, value) => Some(AlertType::Humidity { min: None, max: Some(whole_percent(value)?) }),
+            (Metric::WindSpeed, Greater, value) => Some(AlertType::WindSpeed { max: value, use_gusts: false, direction: None }),
+            (Metric::WindGust, Greater, value) => Some(AlertType::WindSpeed { max: value, use_gusts: true, direction: None }),
+            (Metric::Uv, Greater, value) => Some(AlertType::UvIndex { max: value }),
+            _ => None,
+        },
+        // Диапазон "ниже min или выше max" одного показателя
+        RuleExpr::Or(children) if children.len() == 2 => {
+            let (low_metric, low_op, low) = condition(&children[0])?;
+            let (high_metric, high_op, high) = condition(&children[1])?;
+            if low_metric != high_metric || low_op != Less || high_op != Greater || low >= high {
+                return None;
+            }
+            match low_metric {
+                Metric::Temperature => Some(AlertType::TemperatureThreshold { min: Some(low), max: Some(high) }),
+                Metric::FeelsLike => Some(AlertType::FeelsLike { min: Some(low), max: Some(high) }),
+                Metric::Humidity => Some(AlertType::Humidity { min: Some(whole_percent(low)?), max: Some(whole_percent(high)?) }),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Диапазон "metric < min OR metric > max" в тексте правила
+fn format_range(metric: Metric, min: Option<String>, max: Option<String>) -> Option<String> {
+    match (min, max) {
+        (Some(min), Some(max)) => Some(format!("{code} < {} OR {code} > {}", min, max, code = metric.code())),
+        (Some(min), None) => Some(format!("{} < {}", metric.code(), min)),
+        (None, Some(max)) => Some(format!("{} > {}", metric.code(), max)),
+        (None, None) => None,
+    }
+}
+
+/// Выражение для типа алерта. None, если тип нельзя записать на языке правил
+pub fn format_alert_expression(alert_type: &AlertType) -> Option<String> {
+    match alert_type {
+        AlertType::StandardWeatherAlert { categories } if categories.as_slice() == SevereCategory::DEFAULTS => Some("standard".to_string()),
+        // Пустой список при разборе превратился бы в набор по умолчанию
+        AlertType::StandardWeatherAlert { categories } if categories.is_empty() => None,
+        AlertType::StandardWeatherAlert { categories } => {
+            let codes: Vec<&str> = categories.iter().map(|category| category.code()).collect();
+            Some(format!("standard {}", codes.join(" ")))
+        }
+        AlertType::TemperatureThreshold { min, max } => {
+            format_range(Metric::Temperature, min.map(|v| v.to_string()), max.map(|v| v.to_string()))
+        }
+        AlertType::FeelsLike { min, max } => {
+            format_range(Metric::FeelsLike, min.map(|v| v.to_string()), max.map(|v| v.to_string()))
+        }
+        AlertType::Humidity { min, max } => {
+            format_range(Metric::Humidity, min.map(|v| v.to_string()), max.map(|v| v.to_string()))
+        }
+        AlertType::WindSpeed { max, use_gusts, direction: None } => {
+            let metric = if *use_gusts { Metric::WindGust } else { Metric::WindSpeed };
+            Some(format!("{} > {}", metric.code(), max))
+        }
+        AlertType::UvIndex { max } => Some(format!("{} > {}", Metric::Uv.code(), max)),
+        AlertType::Frost { max_temp } if *max_temp == DEFAULT_FROST_AIR_TEMP_C => Some("frost".to_string()),
+        AlertType::Frost { max_temp } => Some(format!("frost <= {}", max_temp)),
+        AlertType::SnowIce { min_snow_cm: None, ice_risk: true } => Some("ice".to_string()),
+        AlertType::AirQuality { min_epa_index, max_pm2_5: None } => Some(format!("aqi >= {}", min_epa_index)),
+        AlertType::GoodWeather { min_temp, max_temp, max_wind, min_hours } => {
+            Some(format!("good temp >= {} temp <= {} wind < {} hours >= {}", min_temp, max_temp, max_wind, min_hours))
+        }
+        AlertType::Composite { rule } if rule.is_empty() => None,
+        AlertType::Composite { rule } => {
+            // Правило, совпадающее с обычным типом, берем в скобки, чтобы оно осталось составным
+            let text = rule.describe();
+            Some(if dedicated_type(rule).is_some() { format!("({})", text) } else { text })
+        }
+        _ => None,
+    }
+}
+
+/// Команда /alert, которая создаст такой же алерт. None, если тип нельзя записать на языке правил
+pub fn format_alert_command(alert: &WeatherAlert) -> Option<String> {
+    let expression = format_alert_expression(&alert.alert_type)?;
+    let city = alert.city.trim();
+    if city.is_empty() || city.contains('"') {
+        return None;
+    }
+
+    // Название города в кавычках, если его слова можно спутать с началом выражения
+    // (или они вообще не разбираются как слова, например "Saint-Louis-du-Ha! Ha!")
+    let ambiguous = match tokenize(city) {
+        Ok(city_tokens) => (0..city_tokens.len()).any(|index| starts_expression(&city_tokens, index))
+            || city_tokens.iter().any(|t| is_clause_keyword(t) || !matches!(t.kind, TokenKind::Word(_) | TokenKind::Number(_))),
+        Err(_) => true,
+    };
+    let city = if ambiguous { format!("\"{}\"", city) } else { city.to_string() };
+
+    // Расписание печатаем, только если оно отличается от умолчаний типа
+    let mut command = format!("/alert {} {} within {}h", city, expression, alert.hours_ahead);
+    if let Some(minutes) = alert.check_interval_minutes.filter(|m| Some(*m) != alert.alert_type.default_check_interval_minutes()) {
+        command.push_str(&format!(" every {}", format_period(minutes)));
+    }
+    if let Some(minutes) = alert.cooldown_minutes.filter(|m| Some(*m) != alert.alert_type.default_cooldown_minutes()) {
+        command.push_str(&format!(" cooldown {}", format_period(minutes)));
+    }
+    Some(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedule_clauses_follow_the_expression_in_any_order() {
+        let parsed = parse_alert_command("Kyiv wind > 40 cooldown 2h within 12h every 30m").unwrap();
+        assert_eq!(parsed.hours_ahead, 12);
+        assert_eq!(parsed.schedule, AlertSchedule { check_interval_minutes: Some(30), cooldown_minutes: Some(120) });
+
+        let alert = parsed.into_alert();
+        assert_eq!(alert.check_interval_minutes, Some(30));
+        assert_eq!(alert.cooldown_minutes, Some(120));
+        assert_eq!(format_alert_command(&alert).unwrap(), "/alert Kyiv wind > 40 within 12h every 30m cooldown 2h");
+    }
+
+    #[test]
+    fn omitted_schedule_keeps_type_defaults() {
+        let alert = parse_alert_command("Lviv standard").unwrap().into_alert();
+        assert_eq!(alert.check_interval_minutes, alert.alert_type.default_check_interval_minutes());
+        assert_eq!(format_alert_command(&alert).unwrap(), "/alert Lviv standard within 24h");
+    }
+
+    #[test]
+    fn periods_accept_several_unit_spellings() {
+        assert_eq!(parse_schedule("every 45 min").unwrap().check_interval_minutes, Some(45));
+        assert_eq!(parse_schedule("every 1.5h").unwrap().check_interval_minutes, Some(90));
+        assert_eq!(parse_schedule("cooldown 1d").unwrap().cooldown_minutes, Some(24 * 60));
+        assert_eq!(parse_schedule("cooldown 0m").unwrap().cooldown_minutes, Some(0));
+        assert_eq!(format_period(90), "90m");
+        assert_eq!(format_period(24 * 60), "1d");
+    }
+
+    #[test]
+    fn invalid_schedules_are_rejected() {
+        for input in ["every 30", "every 2m", "every 2d", "cooldown 8d", "every 30m every 1h", "within 12h", "every 30m within 12h", ""] {
+            assert!(parse_schedule(input).is_err(), "{:?} should be rejected", input);
+        }
+        assert!(parse_alert_command("Kyiv frost every 10x").is_err());
+    }
+
+    /// Печатает алерт командой /alert, разбирает ее обратно и сравнивает с исходным
+    fn assert_round_trip(alert: &WeatherAlert) {
+        let command = format_alert_command(alert).unwrap_or_else(|| panic!("{:?} should be printable", alert.alert_type));
+        let args = command.strip_prefix("/alert ").unwrap();
+        let parsed = parse_alert_command(args).unwrap_or_else(|e| panic!("{:?} should parse: {}", command, e.message));
+        assert_eq!(parsed.city, alert.city, "city of {:?}", command);
+        assert_eq!(parsed.alert_type, alert.alert_type, "type of {:?}", command);
+        assert_eq!(parsed.hours_ahead, alert.hours_ahead, "hours of {:?}", command);
+
+        // Повторная печать дает ту же команду
+        assert_eq!(format_alert_command(&parsed.into_alert()).as_deref(), Some(command.as_str()));
+    }
+
+    fn condition(metric: Metric, op: Comparison, value: f32) -> RuleExpr {
+        RuleExpr::Condition(RuleCondition { metric, op, value })
+    }
+
+    #[test]
+    fn every_printable_alert_type_round_trips() {
+        let types = [
+            AlertType::StandardWeatherAlert { categories: SevereCategory::DEFAULTS.to_vec() },
+            AlertType::StandardWeatherAlert { categories: vec![SevereCategory::Thunderstorm, SevereCategory::Fog] },
+            AlertType::TemperatureThreshold { min: Some(-5.0), max: None },
+            AlertType::TemperatureThreshold { min: None, max: Some(30.5) },
+            AlertType::TemperatureThreshold { min: Some(-10.0), max: Some(-2.0) },
+            AlertType::FeelsLike { min: Some(-25.0), max: Some(35.0) },
+            AlertType::FeelsLike { min: None, max: Some(40.0) },
+            AlertType::Humidity { min: Some(20), max: Some(85) },
+            AlertType::Humidity { min: None, max: Some(90) },
+            AlertType::WindSpeed { max: 50.0, use_gusts: false, direction: None },
+            AlertType::WindSpeed { max: 70.0, use_gusts: true, direction: None },
+            AlertType::UvIndex { max: 6.5 },
+            AlertType::Frost { max_temp: DEFAULT_FROST_AIR_TEMP_C },
+            AlertType::Frost { max_temp: -1.5 },
+            AlertType::SnowIce { min_snow_cm: None, ice_risk: true },
+            AlertType::AirQuality { min_epa_index: 4, max_pm2_5: None },
+            AlertType::GoodWeather { min_temp: 8.0, max_temp: 18.0, max_wind: 15.0, min_hours: 2 },
+            AlertType::GoodWeather { min_temp: -5.5, max_temp: 0.0, max_wind: 10.0, min_hours: 12 },
+            AlertType::Composite { rule: condition(Metric::Pressure, Comparison::LessOrEqual, 995.0) },
+        ];
+        for alert_type in types {
+            assert_round_trip(&create_alert("Kyiv".to_string(), alert_type, 12));
+        }
+    }
+
+    #[test]
+    fn composite_rules_round_trip_with_nesting() {
+        let rules = [
+            // Совпадает с обычным типом - печатается в скобках и остается составным
+            condition(Metric::Temperature, Comparison::Greater, 25.0),
+            RuleExpr::Or(vec![
+                condition(Metric::Temperature, Comparison::Less, -5.0),
+                condition(Metric::Temperature, Comparison::Greater, 30.0),
+            ]),
+            RuleExpr::Not(Box::new(RuleExpr::Or(vec![
+                condition(Metric::ChanceOfRain, Comparison::GreaterOrEqual, 50.0),
+                condition(Metric::WindGust, Comparison::Greater, 40.0),
+            ]))),
+            RuleExpr::Or(vec![
+                RuleExpr::And(vec![
+                    condition(Metric::Temperature, Comparison::Greater, 25.0),
+                    condition(Metric::Humidity, Comparison::Greater, 70.0),
+                ]),
+                RuleExpr::Not(Box::new(condition(Metric::DewPoint, Comparison::Less, -3.5))),
+            ]),
+            RuleExpr::And(vec![
+                RuleExpr::Not(Box::new(RuleExpr::Not(Box::new(condition(Metric::SnowCm, Comparison::GreaterOrEqual, 2.0))))),
+                RuleExpr::Or(vec![
+                    condition(Metric::LocalHour, Comparison::GreaterOrEqual, 22.0),
+                    condition(Metric::LocalHour, Comparison::Equal, 6.0),
+                ]),
+            ]),
+        ];
+        for rule in rules {
+            assert_round_trip(&create_alert("Lviv".to_string(), AlertType::Composite { rule }, 24));
+        }
+    }
+
+    #[test]
+    fn cities_that_look_like_conditions_are_quoted() {
+        for city in ["Frost", "Ice Lake", "Not", "Temp > Town", "Frankfurt (Oder)", "Within", "Saint-Louis-du-Ha! Ha!"] {
+            let alert = create_alert(city.to_string(), AlertType::UvIndex { max: 8.0 }, 6);
+            assert!(format_alert_command(&alert).unwrap().contains(&format!("\"{}\"", city)), "{} should be quoted", city);
+            assert_round_trip(&alert);
+        }
+
+        // Метрика без сравнения после нее - еще часть названия
+        let alert = create_alert("Temp Bay".to_string(), AlertType::TemperatureThreshold { min: Some(-3.0), max: None }, 24);
+        assert_eq!(format_alert_command(&alert).unwrap(), "/alert Temp Bay temp < -3 within 24h");
+        assert_round_trip(&alert);
+    }
+
+    #[test]
+    fn unprintable_alerts_return_none() {
+        let quoted = create_alert("The \"Big\" City".to_string(), AlertType::UvIndex { max: 8.0 }, 24);
+        assert_eq!(format_alert_command(&quoted), None);
+
+        for alert_type in [
+            AlertType::StandardWeatherAlert { categories: Vec::new() },
+            AlertType::WindSpeed { max: 40.0, use_gusts: false, direction: Some(crate::state::WindSector::N) },
+            AlertType::AirQuality { min_epa_index: 3, max_pm2_5: Some(35.0) },
+            AlertType::SnowIce { min_snow_cm: Some(5.0), ice_risk: false },
+            AlertType::Composite { rule: RuleExpr::default() },
+        ] {
+            assert_eq!(format_alert_expression(&alert_type), None, "{:?}", alert_type);
+        }
+    }
+
+    #[test]
+    fn negative_thresholds_parse_as_numbers() {
+        let parsed = parse_alert_command("Oslo temp<-12.5").unwrap();
+        assert_eq!(parsed.alert_type, AlertType::TemperatureThreshold { min: Some(-12.5), max: None });
+
+        let parsed = parse_alert_command("Oslo frost<=-2").unwrap();
+        assert_eq!(parsed.alert_type, AlertType::Frost { max_temp: -2.0 });
+
+        let parsed = parse_alert_command("Oslo feels < -20 or feels > -1").unwrap();
+        assert_eq!(parsed.alert_type, AlertType::FeelsLike { min: Some(-20.0), max: Some(-1.0) });
+    }
+
+    #[test]
+    fn good_weather_parameters_default_to_the_wizard_values() {
+        let parsed = parse_alert_command("Kyiv good").unwrap();
+        assert_eq!(parsed.alert_type, AlertType::GoodWeather { min_temp: 18.0, max_temp: 25.0, max_wind: 15.0, min_hours: 3 });
+
+        let parsed = parse_alert_command("Kyiv good hours>=2 temp<=18 within 48h").unwrap();
+        assert_eq!(parsed.alert_type, AlertType::GoodWeather { min_temp: 18.0, max_temp: 18.0, max_wind: 15.0, min_hours: 2 });
+        assert!(parsed.into_alert().alert_type.is_opportunity());
+    }
+
+    #[test]
+    fn malformed_commands_point_at_the_problem() {
+        let cases = [
+            ("", None),
+            ("temp<5", Some("")),
+            ("Kyiv", Some("")),
+            ("Kyiv temp<-5 or wnd>50", Some("wnd")),
+            ("Kyiv temp<", Some("")),
+            ("Kyiv temp<-5 and", Some("")),
+            ("Kyiv (temp<-5 or wind>50", Some("(")),
+            ("Kyiv temp<-5)", Some(")")),
+            ("Kyiv temp => 5", Some(">")),
+            ("Kyiv frost and temp<0", Some("and")),
+            ("Kyiv temp<0 or ice", Some("ice")),
+            ("Kyiv ice<1", Some("<")),
+            ("Kyiv frost>=1", Some(">=")),
+            ("Kyiv aqi>=7", Some("7")),
+            ("Kyiv humidity>5000", Some("5000")),
+            ("Kyiv hour=99", Some("99")),
+            ("Kyiv hour>=7.5", Some("7.5")),
+            ("Kyiv temp<0 and uv>-40", Some("-40")),
+            ("Kyiv standard tornado", Some("tornado")),
+            ("Kyiv good temp>=20 temp<=10", Some("good")),
+            ("Kyiv good wind>10", Some("wind>10")),
+            ("Kyiv good hours>=13", Some("13")),
+            ("Kyiv good temp>=", Some("temp>=")),
+            ("Kyiv good and temp>5", Some("and")),
+            ("\"Kyiv temp<0", Some("\"Kyiv temp<0")),
+            ("Kyiv temp<0 within 100h", Some("100h")),
+            ("Kyiv temp<0 within 12h within 6h", Some("within")),
+        ];
+        for (input, fragment) in cases {
+            let error = match parse_alert_command(input) {
+                Ok(parsed) => panic!("{:?} should be rejected, got {:?}", input, parsed.alert_type),
+                Err(error) => error,
+            };
+            let highlighted = error.span.map(|(start, end)| &input[start..end]);
+            assert_eq!(highlighted, fragment, "{:?}: {}", input, error.message);
+        }
+    }
+}
@@ -4,6 +4,10 @@ pub mod state;
 pub mod storage;
 pub mod alerts;
 pub mod scheduler;
+pub mod digest;
+pub mod rules;
+pub mod dsl;
+pub mod presets;
 
 use teloxide::{
     dispatching::{UpdateFilterExt, UpdateHandler},
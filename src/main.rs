@@ -1,5 +1,5 @@
 use teloxide::prelude::*;
-use weather_tgbot::{initialize_bot, handler_tree, state::create_shared_state, storage::Storage, scheduler::AlertScheduler};
+use weather_tgbot::{initialize_bot, handler_tree, state::create_shared_state_with_data, storage::Storage, scheduler::AlertScheduler};
 
 #[tokio::main]
 async fn main() {
@@ -19,20 +19,12 @@ async fn main() {
     };
     
     // Загружаем существующие данные пользователей
+    // Нечитаемые записи пропускаются внутри load_all_users; сюда доходят только ошибки самой базы
     let shared_state = match storage.load_all_users() {
-        Ok(loaded_data) => {
-            use weather_tgbot::state::create_shared_state_with_data;
-            create_shared_state_with_data(storage.clone(), loaded_data)
-        },
+        Ok(loaded_data) => create_shared_state_with_data(storage.clone(), loaded_data),
         Err(e) => {
             log::error!("Failed to load user data: {}", e);
-            match create_shared_state() {
-                Ok(state) => state,
-                Err(e) => {
-                    log::error!("Failed to create shared state: {}", e);
-                    return;
-                }
-            }
+            create_shared_state_with_data(storage.clone(), Default::default())
         }
     };
     
@@ -0,0 +1,127 @@
+//! Каталог готовых алертов ("Heatwave", "Storm watch" и т.п.).
+//!
+//! Пресеты описаны в JSON-файле (ALERT_PRESETS_FILE, по умолчанию `alert_presets.json`) на языке
+//! команды /alert, поэтому новый пресет добавляется без изменения кода. Если файла нет,
+//! используется копия, встроенная при сборке.
+
+use std::sync::OnceLock;
+use serde::Deserialize;
+use crate::dsl::{parse_alert_command, DslError, ParsedAlert, DEFAULT_DSL_HOURS};
+
+const PRESETS_FILE_ENV: &str = "ALERT_PRESETS_FILE";
+const DEFAULT_PRESETS_FILE: &str = "alert_presets.json";
+const BUILTIN_PRESETS: &str = include_str!("../alert_presets.json");
+
+/// id попадает в callback_data, длина которой в Telegram ограничена 64 байтами
+const MAX_PRESET_ID_LEN: usize = 32;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlertPreset {
+    pub id: String,
+    /// Текст кнопки
+    pub title: String,
+    /// Условие в синтаксисе /alert без города, например "temp<-5 or wind>50"
+    pub rule: String,
+    #[serde(default = "default_hours_ahead")]
+    pub hours_ahead: u8,
+    /// Частота проверки и пауза между уведомлениями в синтаксисе /alert ("30m", "2h"), по умолчанию - как у типа алерта
+    #[serde(default)]
+    pub every: Option<String>,
+    #[serde(default)]
+    pub cooldown: Option<String>,
+}
+
+fn default_hours_ahead() -> u8 {
+    DEFAULT_DSL_HOURS
+}
+
+impl AlertPreset {
+    /// Алерт по пресету для конкретного города
+    pub fn instantiate(&self, city: &str) -> Result<ParsedAlert, DslError> {
+        let city = city.replace('"', "");
+        let mut command = format!("\"{}\" {} within {}h", city, self.rule, self.hours_ahead);
+        if let Some(every) = &self.every {
+            command.push_str(&format!(" every {}", every));
+        }
+        if let Some(cooldown) = &self.cooldown {
+            command.push_str(&format!(" cooldown {}", cooldown));
+        }
+        parse_alert_command(&command)
+    }
+}
+
+/// Пресеты, загруженные при первом обращении. Пресеты с ошибками пропускаются с записью в лог
+pub fn alert_presets() -> &'static [AlertPreset] {
+    static PRESETS: OnceLock<Vec<AlertPreset>> = OnceLock::new();
+    PRESETS.get_or_init(load_presets)
+}
+
+pub fn find_preset(id: &str) -> Option<&'static AlertPreset> {
+    alert_presets().iter().find(|preset| preset.id == id)
+}
+
+fn load_presets() -> Vec<AlertPreset> {
+    let path = std::env::var(PRESETS_FILE_ENV).unwrap_or_else(|_| DEFAULT_PRESETS_FILE.to_string());
+    let json = match std::fs::read_to_string(&path) {
+        Ok(json) => json,
+        Err(e) => {
+            log::info!("Alert presets file '{}' not available ({}), using built-in presets", path, e);
+            BUILTIN_PRESETS.to_string()
+        }
+    };
+
+    let presets: Vec<AlertPreset> = match serde_json::from_str(&json) {
+        Ok(presets) => presets,
+        Err(e) => {
+            log::error!("Failed to parse alert presets from '{}': {}", path, e);
+            return Vec::new();
+        }
+    };
+
+    let mut valid: Vec<AlertPreset> = Vec::new();
+    for preset in presets {
+        if preset.id.is_empty() || preset.id.len() > MAX_PRESET_ID_LEN || valid.iter().any(|p| p.id == preset.id) {
+            log::warn!("Skipping alert preset with invalid or duplicate id '{}'", preset.id);
+            continue;
+        }
+        // Проверяем правило на условном городе, чтобы ошибка в файле не всплыла у пользователя
+        if let Err(e) = preset.instantiate("Kyiv") {
+            log::warn!("Skipping alert preset '{}': {}", preset.id, e.message);
+            continue;
+        }
+        valid.push(preset);
+    }
+    log::info!("Loaded {} alert presets", valid.len());
+    valid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builtin_presets() -> Vec<AlertPreset> {
+        serde_json::from_str(BUILTIN_PRESETS).unwrap()
+    }
+
+    #[test]
+    fn builtin_presets_are_valid_alert_commands() {
+        for preset in builtin_presets() {
+            assert!(preset.id.len() <= MAX_PRESET_ID_LEN, "{}", preset.id);
+            let parsed = preset.instantiate("Kyiv \"Center\"")
+                .unwrap_or_else(|e| panic!("preset '{}' should parse: {}", preset.id, e.message));
+            assert_eq!(parsed.city, "Kyiv Center");
+            assert_eq!(parsed.hours_ahead, preset.hours_ahead);
+        }
+    }
+
+    #[test]
+    fn running_preset_announces_good_weather() {
+        let running = builtin_presets().into_iter().find(|preset| preset.id == "running").unwrap();
+        let alert = running.instantiate("Kyiv").unwrap().into_alert();
+        assert!(alert.alert_type.is_opportunity());
+
+        // Утренняя поездка - не дальше следующего утра
+        let commute = builtin_presets().into_iter().find(|preset| preset.id == "commute").unwrap();
+        assert!((12..=24).contains(&commute.hours_ahead));
+    }
+}
@@ -0,0 +1,484 @@
+use serde::{Deserialize, Serialize};
+use crate::weather_api::Hour;
+
+/// Максимальное число условий в составном правиле, собираемом в мастере
+pub const MAX_RULE_CONDITIONS: usize = 8;
+
+/// Допустимые пороги показателя и типовые значения для кнопок мастера
+#[derive(Debug, Clone, Copy)]
+pub struct MetricRange {
+    pub min: f32,
+    pub max: f32,
+    pub integer: bool,
+    pub presets: &'static [f32],
+}
+
+impl MetricRange {
+    pub fn contains(&self, value: f32) -> bool {
+        (self.min..=self.max).contains(&value) && (!self.integer || value.fract() == 0.0)
+    }
+}
+
+/// Показатель почасового прогноза, по которому можно строить условие
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Metric {
+    Temperature,
+    FeelsLike,
+    WindSpeed,
+    WindGust,
+    Humidity,
+    PrecipMm,
+    ChanceOfRain,
+    ChanceOfSnow,
+    SnowCm,
+    Uv,
+    Pressure,
+    DewPoint,
+    LocalHour,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 13] = [
+        Metric::Temperature, Metric::FeelsLike, Metric::WindSpeed, Metric::WindGust,
+        Metric::Humidity, Metric::PrecipMm, Metric::ChanceOfRain, Metric::ChanceOfSnow,
+        Metric::SnowCm, Metric::Uv, Metric::Pressure, Metric::DewPoint, Metric::LocalHour,
+    ];
+
+    /// Короткий код показателя (используется в callback-данных и в тексте правила)
+    pub fn code(&self) -> &'static str {
+        match self {
+            Metric::Temperature => "temp",
+            Metric::FeelsLike => "feels",
+            Metric::WindSpeed => "wind",
+            Metric::WindGust => "gust",
+            Metric::Humidity => "humidity",
+            Metric::PrecipMm => "precip",
+            Metric::ChanceOfRain => "rain",
+            Metric::ChanceOfSnow => "snow",
+            Metric::SnowCm => "snowfall",
+            Metric::Uv => "uv",
+            Metric::Pressure => "pressure",
+            Metric::DewPoint => "dewpoint",
+            Metric::LocalHour => "hour",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|metric| metric.code().eq_ignore_ascii_case(code))
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Metric::Temperature => "🌡️ Temperature",
+            Metric::FeelsLike => "🥵 Feels like",
+            Metric::WindSpeed => "💨 Wind speed",
+            Metric::WindGust => "🌬️ Wind gusts",
+            Metric::Humidity => "💧 Humidity",
+            Metric::PrecipMm => "🌧️ Precipitation (mm)",
+            Metric::ChanceOfRain => "☔ Chance of rain",
+            Metric::ChanceOfSnow => "🌨️ Chance of snow",
+            Metric::SnowCm => "❄️ Snowfall (cm)",
+            Metric::Uv => "☀️ UV index",
+            Metric::Pressure => "📉 Pressure",
+            Metric::DewPoint => "💦 Dew point",
+            Metric::LocalHour => "🕐 Local hour",
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Metric::Temperature | Metric::FeelsLike | Metric::DewPoint => "°C",
+            Metric::WindSpeed | Metric::WindGust => " km/h",
+            Metric::Humidity | Metric::ChanceOfRain | Metric::ChanceOfSnow => "%",
+            Metric::PrecipMm => " mm",
+            Metric::SnowCm => " cm",
+            Metric::Pressure => " hPa",
+            Metric::Uv | Metric::LocalHour => "",
+        }
+    }
+
+    /// Пороги, которые имеет смысл сравнивать с показателем (и в мастере, и в /alert)
+    pub fn range(&self) -> MetricRange {
+        let (min, max, integer, presets): (f32, f32, bool, &'static [f32]) = match self {
+            Metric::Temperature | Metric::FeelsLike => (-80.0, 60.0, false, &[-10.0, 0.0, 25.0, 30.0]),
+            Metric::DewPoint => (-80.0, 40.0, false, &[0.0, 16.0, 20.0]),
+            Metric::WindSpeed => (0.0, 250.0, false, &[20.0, 40.0, 60.0]),
+            Metric::WindGust => (0.0, 300.0, false, &[40.0, 60.0, 80.0]),
+            Metric::Humidity | Metric::ChanceOfRain | Metric::ChanceOfSnow => (0.0, 100.0, true, &[30.0, 50.0, 70.0, 90.0]),
+            Metric::PrecipMm => (0.0, 500.0, false, &[0.0, 1.0, 5.0, 10.0]),
+            Metric::SnowCm => (0.0, 200.0, false, &[1.0, 5.0, 10.0]),
+            Metric::Uv => (0.0, 20.0, false, &[3.0, 6.0, 8.0]),
+            Metric::Pressure => (870.0, 1090.0, false, &[990.0, 1000.0, 1020.0]),
+            Metric::LocalHour => (0.0, 23.0, true, &[7.0, 9.0, 18.0, 22.0]),
+        };
+        MetricRange { min, max, integer, presets }
+    }
+    
+    /// Значение показателя в часе прогноза. None, если провайдер его не прислал
+    pub fn value(&self, hour: &Hour) -> Option<f32> {
+        match self {
+            Metric::Temperature => Some(hour.temperature),
+            Metric::FeelsLike => Some(hour.feels_like.unwrap_or(hour.temperature)),
+            Metric::WindSpeed => Some(hour.wind_speed),
+            Metric::WindGust => Some(hour.gust_speed.max(hour.wind_speed)),
+            Metric::Humidity => Some(hour.humidity as f32),
+            Metric::PrecipMm => Some(hour.precip_mm),
+            Metric::ChanceOfRain => Some(hour.chance_of_rain as f32),
+            Metric::ChanceOfSnow => Some(hour.chance_of_snow as f32),
+            Metric::SnowCm => Some(hour.snow_cm),
+            Metric::Uv => Some(hour.uv),
+            Metric::Pressure => Some(hour.pressure).filter(|pressure| *pressure > 0.0),
+            Metric::DewPoint => Some(hour.dew_point),
+            Metric::LocalHour => hour.local_hour().map(|h| h as f32),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+}
+
+impl Comparison {
+    pub const ALL: [Comparison; 5] = [
+        Comparison::Less, Comparison::LessOrEqual, Comparison::Greater,
+        Comparison::GreaterOrEqual, Comparison::Equal,
+    ];
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Equal => "=",
+        }
+    }
+
+    /// Код для callback-данных (символы сравнения неудобны в callback_data)
+    pub fn code(&self) -> &'static str {
+        match self {
+            Comparison::Less => "lt",
+            Comparison::LessOrEqual => "le",
+            Comparison::Greater => "gt",
+            Comparison::GreaterOrEqual => "ge",
+            Comparison::Equal => "eq",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|op| op.code() == code || op.symbol() == code)
+    }
+
+    pub fn apply(&self, value: f32, threshold: f32) -> bool {
+        match self {
+            Comparison::Less => value < threshold,
+            Comparison::LessOrEqual => value <= threshold,
+            Comparison::Greater => value > threshold,
+            Comparison::GreaterOrEqual => value >= threshold,
+            Comparison::Equal => (value - threshold).abs() < 1e-3,
+        }
+    }
+}
+
+/// Элементарное условие: показатель, сравнение и порог
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleCondition {
+    pub metric: Metric,
+    pub op: Comparison,
+    pub value: f32,
+}
+
+impl RuleCondition {
+    /// Условие не выполняется, если показателя нет в прогнозе
+    pub fn matches(&self, hour: &Hour) -> bool {
+        self.metric.value(hour).is_some_and(|value| self.op.apply(value, self.value))
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{} {} {}", self.metric.code(), self.op.symbol(), self.value)
+    }
+    
+    /// Условие со сдвинутым порогом: relax - выполняется с запасом `margin`, иначе - строже на столько же.
+    /// Равенство не сдвигается
+    fn shifted(&self, margin: f32, relax: bool) -> RuleCondition {
+        let delta = if relax { margin } else { -margin };
+        let value = match self.op {
+            Comparison::Greater | Comparison::GreaterOrEqual => self.value - delta,
+            Comparison::Less | Comparison::LessOrEqual => self.value + delta,
+            Comparison::Equal => self.value,
+        };
+        RuleCondition { value, ..self.clone() }
+    }
+}
+
+/// Как новое условие присоединяется к уже собранному правилу
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleJoin {
+    And,
+    Or,
+}
+
+/// Дерево условий составного алерта. Правило проверяется по каждому часу прогноза отдельно
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RuleExpr {
+    Condition(RuleCondition),
+    And(Vec<RuleExpr>),
+    Or(Vec<RuleExpr>),
+    Not(Box<RuleExpr>),
+}
+
+impl Default for RuleExpr {
+    /// Пустое правило (пустое И), с которого мастер начинает сборку
+    fn default() -> Self {
+        RuleExpr::And(Vec::new())
+    }
+}
+
+impl RuleExpr {
+    pub fn is_empty(&self) -> bool {
+        matches!(self, RuleExpr::And(children) if children.is_empty())
+    }
+
+    pub fn evaluate(&self, hour: &Hour) -> bool {
+        match self {
+            RuleExpr::Condition(condition) => condition.matches(hour),
+            RuleExpr::And(children) => !children.is_empty() && children.iter().all(|child| child.evaluate(hour)),
+            RuleExpr::Or(children) => children.iter().any(|child| child.evaluate(hour)),
+            RuleExpr::Not(inner) => !inner.evaluate(hour),
+        }
+    }
+
+    /// Все элементарные условия правила
+    pub fn conditions(&self) -> Vec<&RuleCondition> {
+        match self {
+            RuleExpr::Condition(condition) => vec![condition],
+            RuleExpr::And(children) | RuleExpr::Or(children) => {
+                children.iter().flat_map(|child| child.conditions()).collect()
+            }
+            RuleExpr::Not(inner) => inner.conditions(),
+        }
+    }
+
+    /// Правило для уже сработавшего алерта: каждое условие смягчено на margin(показатель),
+    /// чтобы правило перестало выполняться только при уходе значений за порог с запасом.
+    /// Под NOT условие, наоборот, ужесточается - так смягчается все отрицание
+    pub fn relaxed(&self, margin: &impl Fn(Metric) -> f32) -> RuleExpr {
+        self.shifted(margin, true)
+    }
+    
+    fn shifted(&self, margin: &impl Fn(Metric) -> f32, relax: bool) -> RuleExpr {
+        match self {
+            RuleExpr::Condition(condition) => RuleExpr::Condition(condition.shifted(margin(condition.metric), relax)),
+            RuleExpr::And(children) => RuleExpr::And(children.iter().map(|child| child.shifted(margin, relax)).collect()),
+            RuleExpr::Or(children) => RuleExpr::Or(children.iter().map(|child| child.shifted(margin, relax)).collect()),
+            RuleExpr::Not(inner) => RuleExpr::Not(Box::new(inner.shifted(margin, !relax))),
+        }
+    }
+    
+    /// Текст правила, например "(temp > 25 AND humidity > 70) OR NOT rain >= 50"
+    pub fn describe(&self) -> String {
+        self.describe_nested(false)
+    }
+
+    fn describe_nested(&self, nested: bool) -> String {
+        let join = |children: &[RuleExpr], separator: &str| {
+            let text = children.iter()
+                .map(|child| child.describe_nested(true))
+                .collect::<Vec<_>>()
+                .join(separator);
+            if nested && children.len() > 1 { format!("({})", text) } else { text }
+        };
+
+        match self {
+            RuleExpr::Condition(condition) => condition.describe(),
+            RuleExpr::And(children) if children.is_empty() => "(empty rule)".to_string(),
+            RuleExpr::And(children) => join(children, " AND "),
+            RuleExpr::Or(children) => join(children, " OR "),
+            RuleExpr::Not(inner) => format!("NOT {}", inner.describe_nested(true)),
+        }
+    }
+
+    /// Пояснение по часу прогноза: какие условия выполнились, а какие нет
+    pub fn explain(&self, hour: &Hour) -> Vec<String> {
+        self.conditions().into_iter()
+            .map(|condition| {
+                let mark = if condition.matches(hour) { "✅" } else { "❌" };
+                let actual = condition.metric.value(hour)
+                    .map(|value| format!("{}{}", value, condition.metric.unit()))
+                    .unwrap_or_else(|| "нет данных".to_string());
+                format!("{} {} (факт: {})", mark, condition.describe(), actual)
+            })
+            .collect()
+    }
+
+    /// Присоединяет условие слева направо: (a AND b) OR c
+    pub fn join(self, join: RuleJoin, next: RuleExpr) -> RuleExpr {
+        match (self, join) {
+            (current, _) if current.is_empty() => next,
+            (RuleExpr::And(mut children), RuleJoin::And) => {
+                children.push(next);
+                RuleExpr::And(children)
+            }
+            (RuleExpr::Or(mut children), RuleJoin::Or) => {
+                children.push(next);
+                RuleExpr::Or(children)
+            }
+            (current, RuleJoin::And) => RuleExpr::And(vec![current, next]),
+            (current, RuleJoin::Or) => RuleExpr::Or(vec![current, next]),
+        }
+    }
+
+    /// Инвертирует последнее добавленное условие (кнопка NOT в мастере)
+    pub fn negate_last(self) -> RuleExpr {
+        match self {
+            RuleExpr::And(mut children) if !children.is_empty() => {
+                let last = children.pop().map(RuleExpr::negate_last).unwrap_or_default();
+                children.push(last);
+                RuleExpr::And(children)
+            }
+            RuleExpr::Or(mut children) if !children.is_empty() => {
+                let last = children.pop().map(RuleExpr::negate_last).unwrap_or_default();
+                children.push(last);
+                RuleExpr::Or(children)
+            }
+            RuleExpr::Not(inner) => *inner,
+            expr @ RuleExpr::Condition(_) => RuleExpr::Not(Box::new(expr)),
+            empty => empty,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn condition(metric: Metric, op: Comparison, value: f32) -> RuleExpr {
+        RuleExpr::Condition(RuleCondition { metric, op, value })
+    }
+
+    #[test]
+    fn rules_evaluate_against_a_single_hour() {
+        let mut hour = Hour::sample(14, 27.0);
+        hour.humidity = 75;
+        hour.chance_of_rain = 20;
+
+        let muggy = RuleExpr::And(vec![
+            condition(Metric::Temperature, Comparison::Greater, 25.0),
+            condition(Metric::Humidity, Comparison::Greater, 70.0),
+        ]);
+        assert!(muggy.evaluate(&hour));
+
+        let wet_or_windy = RuleExpr::Or(vec![
+            condition(Metric::ChanceOfRain, Comparison::GreaterOrEqual, 50.0),
+            condition(Metric::WindSpeed, Comparison::Greater, 40.0),
+        ]);
+        assert!(!wet_or_windy.evaluate(&hour));
+        assert!(RuleExpr::Not(Box::new(wet_or_windy)).evaluate(&hour));
+
+        assert!(condition(Metric::LocalHour, Comparison::Equal, 14.0).evaluate(&hour));
+        assert!(!condition(Metric::LocalHour, Comparison::GreaterOrEqual, 22.0).evaluate(&hour));
+
+        // Пустое правило никогда не срабатывает
+        assert!(!RuleExpr::default().evaluate(&hour));
+    }
+
+    #[test]
+    fn missing_metric_never_matches() {
+        let mut hour = Hour::sample(3, 10.0);
+        hour.pressure = 0.0;
+
+        assert!(!condition(Metric::Pressure, Comparison::Less, 2000.0).evaluate(&hour));
+        assert!(!condition(Metric::Pressure, Comparison::Greater, 0.0).evaluate(&hour));
+        assert!(condition(Metric::Pressure, Comparison::Less, 1000.0).explain(&hour)[0].contains("нет данных"));
+    }
+
+    #[test]
+    fn describe_parenthesizes_nested_groups_only() {
+        let rule = RuleExpr::Or(vec![
+            RuleExpr::And(vec![
+                condition(Metric::Temperature, Comparison::Greater, 25.0),
+                condition(Metric::Humidity, Comparison::Greater, 70.0),
+            ]),
+            RuleExpr::Not(Box::new(condition(Metric::ChanceOfRain, Comparison::Less, 50.0))),
+        ]);
+        assert_eq!(rule.describe(), "(temp > 25 AND humidity > 70) OR NOT rain < 50");
+
+        let negated_group = RuleExpr::Not(Box::new(RuleExpr::Or(vec![
+            condition(Metric::WindGust, Comparison::Greater, 40.0),
+            condition(Metric::Temperature, Comparison::Less, -5.5),
+        ])));
+        assert_eq!(negated_group.describe(), "NOT (gust > 40 OR temp < -5.5)");
+        assert_eq!(RuleExpr::default().describe(), "(empty rule)");
+    }
+
+    #[test]
+    fn join_builds_left_to_right() {
+        let a = condition(Metric::Temperature, Comparison::Greater, 25.0);
+        let b = condition(Metric::Humidity, Comparison::Greater, 70.0);
+        let c = condition(Metric::Uv, Comparison::Greater, 6.0);
+
+        let rule = RuleExpr::default()
+            .join(RuleJoin::And, a.clone())
+            .join(RuleJoin::And, b.clone())
+            .join(RuleJoin::Or, c.clone());
+        assert_eq!(rule, RuleExpr::Or(vec![RuleExpr::And(vec![a.clone(), b.clone()]), c.clone()]));
+        assert_eq!(rule.conditions().len(), 3);
+
+        // Та же связка продолжает текущую группу, а не вкладывает новую
+        let rule = rule.join(RuleJoin::Or, a.clone());
+        assert!(matches!(&rule, RuleExpr::Or(children) if children.len() == 3));
+    }
+
+    #[test]
+    fn negate_last_toggles_only_the_latest_condition() {
+        let a = condition(Metric::Temperature, Comparison::Greater, 25.0);
+        let b = condition(Metric::ChanceOfRain, Comparison::GreaterOrEqual, 50.0);
+        let rule = RuleExpr::And(vec![a.clone(), b.clone()]);
+
+        let negated = rule.clone().negate_last();
+        assert_eq!(negated, RuleExpr::And(vec![a.clone(), RuleExpr::Not(Box::new(b.clone()))]));
+        assert_eq!(negated.negate_last(), rule);
+
+        let nested = RuleExpr::Or(vec![a.clone(), RuleExpr::And(vec![a.clone(), b.clone()])]).negate_last();
+        assert_eq!(nested.describe(), "temp > 25 OR (temp > 25 AND NOT rain >= 50)");
+        assert_eq!(RuleExpr::default().negate_last(), RuleExpr::default());
+    }
+
+    #[test]
+    fn relaxed_rules_shift_thresholds_away_from_clearing() {
+        let rule = RuleExpr::Or(vec![
+            condition(Metric::Temperature, Comparison::Less, -5.0),
+            RuleExpr::Not(Box::new(condition(Metric::WindSpeed, Comparison::GreaterOrEqual, 20.0))),
+            condition(Metric::LocalHour, Comparison::Equal, 7.0),
+        ]);
+        let relaxed = rule.relaxed(&|metric| if metric == Metric::Temperature { 1.0 } else { 5.0 });
+        assert_eq!(relaxed.describe(), "temp < -4 OR NOT wind >= 25 OR hour = 7");
+    }
+
+    #[test]
+    fn metric_ranges_reject_impossible_thresholds() {
+        for metric in Metric::ALL {
+            let range = metric.range();
+            assert!(range.presets.iter().all(|value| range.contains(*value)), "{:?}", metric);
+        }
+        assert!(!Metric::Humidity.range().contains(5000.0));
+        assert!(!Metric::Humidity.range().contains(70.5));
+        assert!(!Metric::LocalHour.range().contains(24.0));
+        assert!(!Metric::Uv.range().contains(-40.0));
+        assert!(Metric::Temperature.range().contains(-12.5));
+    }
+
+    #[test]
+    fn equality_tolerates_float_noise() {
+        assert!(Comparison::Equal.apply(0.1 + 0.2, 0.3));
+        assert!(!Comparison::Equal.apply(0.31, 0.3));
+        assert_eq!(Comparison::from_code("ge"), Some(Comparison::GreaterOrEqual));
+        assert_eq!(Comparison::from_code(">="), Some(Comparison::GreaterOrEqual));
+        assert_eq!(Metric::from_code("TEMP"), Some(Metric::Temperature));
+    }
+}
@@ -1,33 +1,173 @@
-use std::time::Duration;
-use tokio::time::interval;
-use teloxide::{Bot, prelude::Requester};
-use crate::state::{SharedState, update_user_data};
-use crate::alerts::AlertChecker;
-use crate::weather_api::get_current_weather;
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use teloxide::{Bot, payloads::SendMessageSetters, prelude::Requester, types::ChatId};
+use crate::state::{AlertEventKind, AlertHistoryEntry, AlertState, SharedState, WeatherAlert, update_user_data};
+use crate::alerts::{AlertChecker, AlertEvaluation, format_official_warning_message};
+use crate::weather_api::{CurrentWeather, RateLimiter, get_current_weather, get_forecast, get_forecast_with_warnings, normalize_location};
+use crate::digest::{is_digest_due, build_weekly_digest, digest_towns, DigestForecasts, DIGEST_FORECAST_DAYS};
+use crate::bot::{make_delivered_alert_keyboard, unpin_escalation};
 
+/// Настройки планировщика. Значения по умолчанию можно переопределить переменными окружения
+#[derive(Debug, Clone)]
+pub struct SchedulerConfig {
+    /// Интервал проверки алертов без собственной частоты и максимальная пауза планировщика (ALERT_CHECK_INTERVAL_SECS)
+    pub check_interval: Duration,
+    /// Минимальное время между повторными уведомлениями по одному алерту (ALERT_COOLDOWN_MINUTES)
+    pub notification_cooldown: Duration,
+    /// Сколько локаций проверяется одновременно (ALERT_MAX_CONCURRENCY)
+    pub max_concurrency: usize,
+    /// Общий лимит запросов к Weather API в секунду (WEATHER_API_RPS)
+    pub requests_per_second: u32,
+    /// Как часто проверять официальные предупреждения для подписанных пользователей (OFFICIAL_WARNINGS_INTERVAL_MINUTES)
+    pub warnings_interval: Duration,
+    /// Через сколько напомнить о неподтвержденном критичном алерте (ALERT_ACK_TIMEOUT_MINUTES)
+    pub ack_timeout: Duration,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            check_interval: Duration::from_secs(300),
+            notification_cooldown: Duration::from_secs(60 * 60),
+            max_concurrency: 8,
+            requests_per_second: 5,
+            warnings_interval: Duration::from_secs(30 * 60),
+            ack_timeout: Duration::from_secs(30 * 60),
+        }
+    }
+}
+
+impl SchedulerConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            check_interval: Duration::from_secs(
+                env_or("ALERT_CHECK_INTERVAL_SECS", defaults.check_interval.as_secs()).max(MIN_SLEEP.as_secs()),
+            ),
+            notification_cooldown: Duration::from_secs(
+                env_or("ALERT_COOLDOWN_MINUTES", defaults.notification_cooldown.as_secs() / 60) * 60,
+            ),
+            max_concurrency: env_or("ALERT_MAX_CONCURRENCY", defaults.max_concurrency).max(1),
+            requests_per_second: env_or("WEATHER_API_RPS", defaults.requests_per_second).max(1),
+            warnings_interval: Duration::from_secs(
+                env_or("OFFICIAL_WARNINGS_INTERVAL_MINUTES", defaults.warnings_interval.as_secs() / 60).max(1) * 60,
+            ),
+            ack_timeout: Duration::from_secs(
+                env_or("ALERT_ACK_TIMEOUT_MINUTES", defaults.ack_timeout.as_secs() / 60).max(1) * 60,
+            ),
+        }
+    }
+}
+
+/// Минимальная пауза между циклами, чтобы планировщик не крутился вхолостую
+const MIN_SLEEP: Duration = Duration::from_secs(5);
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    match env::var(name) {
+        Ok(value) => value.parse().unwrap_or_else(|_| {
+            log::warn!("Invalid value '{}' for {}, using default", value, name);
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+#[derive(Clone)]
 pub struct AlertScheduler {
     bot: Bot,
     state: SharedState,
+    config: SchedulerConfig,
+    /// Время следующей проверки для каждого алерта (по id). После перезапуска пусто -
+    /// тогда срок считается от сохраненного last_checked алерта
+    next_due: Arc<Mutex<HashMap<String, DateTime<Utc>>>>,
+    rate_limiter: Arc<RateLimiter>,
+    concurrency: Arc<Semaphore>,
+    /// Когда последний раз проверялись официальные предупреждения
+    last_warnings_check: Arc<Mutex<Option<Instant>>>,
 }
 
 impl AlertScheduler {
     pub fn new(bot: Bot, state: SharedState) -> Self {
-        Self { bot, state }
+        Self::with_config(bot, state, SchedulerConfig::from_env())
+    }
+    
+    pub fn with_config(bot: Bot, state: SharedState, config: SchedulerConfig) -> Self {
+        log::info!("Alert scheduler config: {:?}", config);
+        Self {
+            bot,
+            state,
+            rate_limiter: Arc::new(RateLimiter::new(config.requests_per_second)),
+            concurrency: Arc::new(Semaphore::new(config.max_concurrency)),
+            last_warnings_check: Arc::new(Mutex::new(None)),
+            next_due: Arc::new(Mutex::new(HashMap::new())),
+            config,
+        }
     }
     
     pub async fn start(&self) {
-        let mut interval = interval(Duration::from_secs(300)); // Проверяем каждые 5 минут
-        
         loop {
-            interval.tick().await;
-            if let Err(e) = self.check_all_alerts().await {
-                log::error!("Error checking alerts: {}", e);
-            }
+            self.run_cycle().await;
+            
+            // Спим до ближайшей запланированной проверки, но не дольше check_interval
+            let sleep_for = self.time_until_next_due();
+            log::debug!("Next alert check in {:.0?}", sleep_for);
+            tokio::time::sleep(sleep_for).await;
         }
     }
     
+    fn time_until_next_due(&self) -> Duration {
+        let now = Utc::now();
+        let next_due = self.next_due.lock().unwrap();
+        let state_guard = self.state.data.lock().unwrap();
+        
+        let earliest = state_guard.values()
+            .flat_map(|user_data| user_data.weather_alerts.iter())
+            .filter(|alert| alert.is_active)
+            .map(|alert| {
+                // Отложенный алерт ждет окончания паузы
+                let due = self.due_time(&next_due, alert, now);
+                let due = alert.snoozed_until.map_or(due, |until| due.max(until));
+                // Напоминание о неподтвержденном уведомлении может понадобиться раньше проверки
+                alert.escalation_due(self.config.ack_timeout).map_or(due, |escalation| due.min(escalation))
+            })
+            .min();
+        
+        match earliest {
+            Some(due) => (due - now).to_std().unwrap_or(Duration::ZERO)
+                .clamp(MIN_SLEEP, self.config.check_interval),
+            None => self.config.check_interval,
+        }
+    }
+    
+    /// Циклы идут строго по очереди из единственного цикла start(): следующий начинается только после сна
+    async fn run_cycle(&self) {
+        let started = Instant::now();
+        if let Err(e) = self.check_all_alerts().await {
+            log::error!("Error checking alerts: {}", e);
+        }
+        self.escalate_unacknowledged_alerts().await;
+        self.send_weekly_digests().await;
+        self.forward_official_warnings().await;
+        log::info!("Scheduler cycle finished in {:.1?}", started.elapsed());
+    }
+    
+    /// Когда алерт нужно проверить: по расписанию в памяти, иначе через интервал после last_checked
+    fn due_time(&self, next_due: &HashMap<String, DateTime<Utc>>, alert: &WeatherAlert, now: DateTime<Utc>) -> DateTime<Utc> {
+        next_due.get(&alert.id).copied().unwrap_or_else(|| {
+            alert.last_checked
+                .map(|checked| checked + to_chrono(alert.check_interval(self.config.check_interval)))
+                .unwrap_or(now)
+        })
+    }
+    
     async fn check_all_alerts(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         log::info!("Starting alert check cycle...");
+        let started = Instant::now();
         
         // Получаем копию всех данных пользователей
         let users_data = {
@@ -35,60 +175,396 @@ impl AlertScheduler {
             state_guard.clone()
         };
         
+        let now = Utc::now();
+        let mut next_due = self.next_due.lock().unwrap().clone();
+        
+        // Забываем расписание удаленных алертов
+        next_due.retain(|id, _| users_data.values()
+            .any(|user_data| user_data.weather_alerts.iter().any(|a| &a.id == id)));
+        
+        // Группируем алерты, которым пора проверяться, по локации - каждый город запрашивается один раз за цикл
+        let mut alerts_by_location: HashMap<String, Vec<(ChatId, WeatherAlert)>> = HashMap::new();
+        let mut checked: HashMap<ChatId, Vec<String>> = HashMap::new();
         for (chat_id, user_data) in users_data {
-            for alert in &user_data.weather_alerts {
-                if !alert.is_active {
+            for alert in user_data.weather_alerts {
+                if !alert.is_running(now) {
                     continue;
                 }
-                
-                // Проверяем, не было ли недавно срабатывания (избегаем спама)
-                if let Some(last_triggered) = alert.last_triggered {
-                    let time_since_last = chrono::Utc::now() - last_triggered;
-                    if time_since_last.num_minutes() < 60 { // Минимум час между срабатываниями
-                        continue;
-                    }
+                if self.due_time(&next_due, &alert, now) > now {
+                    continue;
                 }
                 
-                match AlertChecker::check_alert(alert).await {
-                    Ok(true) => {
-                        log::info!("Alert triggered for user {} in city {}", chat_id, alert.city);
-                        
-                        // Получаем погодные данные для уведомления
-                        match get_current_weather(&alert.city).await {
-                            Ok(weather) => {
-                                let message = AlertChecker::format_alert_message(alert, &weather);
-                                
-                                if let Err(e) = self.bot.send_message(chat_id, message).await {
-                                    log::error!("Failed to send alert to user {}: {}", chat_id, e);
-                                } else {
-                                    // Обновляем время последнего срабатывания
-                                    update_user_data(&self.state, chat_id, |user_data| {
-                                        if let Some(alert_to_update) = user_data.weather_alerts.iter_mut()
-                                            .find(|a| a.id == alert.id) {
-                                            alert_to_update.last_triggered = Some(chrono::Utc::now());
-                                        }
-                                    });
-                                }
-                            }
-                            Err(e) => {
-                                log::error!("Failed to fetch weather data for alert in {}: {}", alert.city, e);
-                            }
+                let check_interval = to_chrono(alert.check_interval(self.config.check_interval));
+                next_due.insert(alert.id.clone(), now + check_interval);
+                checked.entry(chat_id).or_default().push(alert.id.clone());
+                
+                alerts_by_location
+                    .entry(normalize_location(&alert.city))
+                    .or_default()
+                    .push((chat_id, alert));
+            }
+        }
+        
+        *self.next_due.lock().unwrap() = next_due;
+        
+        // Запоминаем время проверки, чтобы перезапуск не сбивал расписание (одна запись на пользователя)
+        for (chat_id, alert_ids) in checked {
+            update_user_data(&self.state, chat_id, |user_data| {
+                for alert in user_data.weather_alerts.iter_mut().filter(|a| alert_ids.contains(&a.id)) {
+                    alert.last_checked = Some(now);
+                }
+            });
+        }
+        
+        log::info!(
+            "Checking {} due alerts in {} locations",
+            alerts_by_location.values().map(Vec::len).sum::<usize>(),
+            alerts_by_location.len()
+        );
+        
+        // Локации проверяются параллельно, но не больше max_concurrency одновременно
+        let mut tasks = JoinSet::new();
+        for (_, alerts) in alerts_by_location {
+            let scheduler = self.clone();
+            tasks.spawn(async move {
+                let Ok(_permit) = scheduler.concurrency.acquire().await else {
+                    return;
+                };
+                scheduler.check_location_alerts(&alerts).await;
+            });
+        }
+        
+        while let Some(result) = tasks.join_next().await {
+            if let Err(e) = result {
+                log::error!("Alert check task failed: {}", e);
+            }
+        }
+        
+        log::info!("Alert check cycle completed in {:.1?}", started.elapsed());
+        Ok(())
+    }
+    
+    /// Проверяет все алерты одной локации на общих данных прогноза
+    async fn check_location_alerts(&self, alerts: &[(ChatId, WeatherAlert)]) {
+        let Some((_, first_alert)) = alerts.first() else {
+            return;
+        };
+        let city = first_alert.city.trim();
+        
+        self.rate_limiter.acquire().await;
+        let forecast = match get_forecast(city, 3).await {
+            Ok(forecast) => forecast,
+            Err(e) => {
+                log::error!("Failed to fetch forecast for {}: {}", city, e);
+                return;
+            }
+        };
+        
+        // Текущая погода нужна только для уведомлений о срабатывании - запрашиваем лениво и один раз.
+        // Если запрос не удался, отбои и остальные переходы по прогнозу все равно обрабатываются
+        let mut current_weather: Option<CurrentWeather> = None;
+        let mut current_weather_fetched = false;
+        
+        for (chat_id, alert) in alerts {
+            let evaluation = match AlertChecker::evaluate_forecast(&forecast, alert) {
+                Ok(evaluation) => evaluation,
+                Err(e) => {
+                    log::error!("Error checking alert for {} in {}: {}", chat_id, alert.city, e);
+                    continue;
+                }
+            };
+            
+            // Уведомляем только о переходах между состояниями
+            match (alert.state, evaluation.triggered) {
+                (AlertState::Clear, true) => {
+                    // Проверяем, не было ли недавно срабатывания (избегаем спама)
+                    if let Some(last_triggered) = alert.last_triggered {
+                        let cooldown = to_chrono(alert.cooldown(self.config.notification_cooldown));
+                        if Utc::now() - last_triggered < cooldown {
+                            log::debug!("Alert {} re-triggered within cooldown, not notifying", alert.id);
+                            continue;
                         }
                     }
-                    Ok(false) => {
-                        // Алерт не сработал, это нормально
+                    
+                    log::info!("Alert triggered for user {} in city {}", chat_id, alert.city);
+                    
+                    if !current_weather_fetched {
+                        current_weather_fetched = true;
+                        self.rate_limiter.acquire().await;
+                        match get_current_weather(city).await {
+                            Ok(weather) => current_weather = Some(weather),
+                            Err(e) => log::error!("Failed to fetch weather data for alert in {}: {}", city, e),
+                        }
+                    }
+                    // Без текущей погоды уведомление не собрать: алерт остается Clear и сработает в следующем цикле
+                    let Some(weather) = current_weather.as_ref() else {
+                        log::warn!("Postponing alert {} for user {}: no current weather for {}", alert.id, chat_id, city);
+                        continue;
+                    };
+                    
+                    let message = AlertChecker::format_alert_message(alert, weather, &evaluation);
+                    let needs_ack = alert.alert_type.is_critical();
+                    
+                    let result = self.bot.send_message(*chat_id, message)
+                        .reply_markup(make_delivered_alert_keyboard(&alert.id, needs_ack))
+                        .await;
+                    if let Err(e) = &result {
+                        log::error!("Failed to send alert to user {}: {}", chat_id, e);
+                    } else {
+                        self.set_alert_state(*chat_id, &alert.id, AlertState::Triggered, Some(Utc::now()));
+                        if needs_ack {
+                            self.set_ack_request(*chat_id, &alert.id, Some(Utc::now())).await;
+                        }
+                    }
+                    self.record_history(*chat_id, &alert.id, AlertEventKind::Triggered, &evaluation, result.err());
+                }
+                (AlertState::Triggered, true) => {
+                    // Условие сохраняется - тихо переходим в "ongoing"
+                    self.set_alert_state(*chat_id, &alert.id, AlertState::Ongoing, None);
+                }
+                (AlertState::Ongoing, true) | (AlertState::Clear, false) => {
+                    // Ничего не изменилось
+                }
+                (AlertState::Triggered | AlertState::Ongoing, false) => {
+                    log::info!("Alert cleared for user {} in city {}", chat_id, alert.city);
+                    
+                    let message = AlertChecker::format_all_clear_message(alert, &forecast.location.name);
+                    
+                    let result = self.bot.send_message(*chat_id, message).await;
+                    if let Err(e) = &result {
+                        log::error!("Failed to send all-clear to user {}: {}", chat_id, e);
+                    } else {
+                        self.set_alert_state(*chat_id, &alert.id, AlertState::Clear, None);
+                        // После отбоя напоминать о предупреждении уже незачем
+                        self.set_ack_request(*chat_id, &alert.id, None).await;
                     }
+                    self.record_history(*chat_id, &alert.id, AlertEventKind::Cleared, &evaluation, result.err());
+                }
+            }
+        }
+    }
+    
+    fn record_history(&self, chat_id: ChatId, alert_id: &str, kind: AlertEventKind, evaluation: &AlertEvaluation, error: Option<teloxide::RequestError>) {
+        let entry = AlertHistoryEntry {
+            timestamp: Utc::now(),
+            kind,
+            target_time: evaluation.target_time.clone(),
+            observed: evaluation.observed.clone(),
+            delivered: error.is_none(),
+            error: error.map(|e| e.to_string()),
+            acknowledged_by: None,
+        };
+        if let Err(e) = self.state.storage.record_alert_event(chat_id, alert_id, &entry) {
+            log::error!("Failed to record history for alert {}: {}", alert_id, e);
+        }
+    }
+    
+    fn set_alert_state(&self, chat_id: ChatId, alert_id: &str, state: AlertState, triggered_at: Option<DateTime<Utc>>) {
+        update_user_data(&self.state, chat_id, |user_data| {
+            if let Some(alert_to_update) = user_data.weather_alerts.iter_mut()
+                .find(|a| a.id == alert_id) {
+                alert_to_update.state = state;
+                if triggered_at.is_some() {
+                    alert_to_update.last_triggered = triggered_at;
+                }
+            }
+        });
+    }
+    
+    /// Новый запрос подтверждения (или его снятие) заменяет прежний вместе с закрепленным напоминанием
+    async fn set_ack_request(&self, chat_id: ChatId, alert_id: &str, requested_at: Option<DateTime<Utc>>) {
+        let mut pinned_escalation = None;
+        update_user_data(&self.state, chat_id, |user_data| {
+            if let Some(alert) = user_data.weather_alerts.iter_mut().find(|a| a.id == alert_id) {
+                pinned_escalation = alert.clear_ack_request();
+                alert.ack_requested_at = requested_at;
+            }
+        });
+        unpin_escalation(&self.bot, chat_id, alert_id, pinned_escalation).await;
+    }
+    
+    /// Повторно и громче уведомляет о критичных алертах, которые не подтвердили за ack_timeout.
+    /// Напоминание отправляется один раз и закрепляется в чате
+    async fn escalate_unacknowledged_alerts(&self) {
+        let now = Utc::now();
+        let overdue: Vec<(ChatId, WeatherAlert)> = {
+            let state_guard = self.state.data.lock().unwrap();
+            state_guard.iter()
+                .flat_map(|(chat_id, user_data)| user_data.weather_alerts.iter().map(move |alert| (*chat_id, alert)))
+                .filter(|(_, alert)| alert.is_running(now))
+                .filter(|(_, alert)| alert.escalation_due(self.config.ack_timeout).is_some_and(|due| due <= now))
+                .map(|(chat_id, alert)| (chat_id, alert.clone()))
+                .collect()
+        };
+        
+        for (chat_id, alert) in overdue {
+            log::info!("Escalating unacknowledged alert {} for user {}", alert.id, chat_id);
+            
+            let message = AlertChecker::format_escalation_message(&alert);
+            let result = self.bot.send_message(chat_id, message)
+                .reply_markup(make_delivered_alert_keyboard(&alert.id, true))
+                .await;
+            let mut pinned = None;
+            match &result {
+                Ok(sent) => match self.bot.pin_chat_message(chat_id, sent.id).await {
+                    Ok(_) => pinned = Some(sent.id.0),
+                    Err(e) => log::warn!("Failed to pin escalation for alert {}: {}", alert.id, e),
+                },
+                Err(e) => log::error!("Failed to send escalation to user {}: {}", chat_id, e),
+            }
+            
+            // Отмечаем даже неудачную попытку, чтобы не повторять ее каждый цикл.
+            // Id закрепленного сообщения нужен, чтобы открепить его при подтверждении с любого сообщения
+            update_user_data(&self.state, chat_id, |user_data| {
+                if let Some(alert_to_update) = user_data.weather_alerts.iter_mut().find(|a| a.id == alert.id) {
+                    alert_to_update.escalated = true;
+                    alert_to_update.escalation_message_id = pinned;
+                }
+            });
+            self.record_history(chat_id, &alert.id, AlertEventKind::Escalated, &AlertEvaluation::default(), result.err());
+        }
+    }
+    
+    async fn send_weekly_digests(&self) {
+        let now = chrono::Utc::now();
+        let due_users: Vec<_> = {
+            let state_guard = self.state.data.lock().unwrap();
+            state_guard.iter()
+                .filter(|(_, user_data)| is_digest_due(user_data, now))
+                .map(|(chat_id, user_data)| (*chat_id, user_data.clone()))
+                .collect()
+        };
+        if due_users.is_empty() {
+            return;
+        }
+        
+        // Каждый город запрашивается один раз, даже если он в дайджесте у нескольких пользователей
+        let mut towns: HashMap<String, String> = HashMap::new();
+        for (_, user_data) in &due_users {
+            for town in digest_towns(user_data) {
+                towns.entry(normalize_location(&town)).or_insert_with(|| town.trim().to_string());
+            }
+        }
+        log::info!("Fetching weekly digest forecasts for {} locations", towns.len());
+        
+        // Как и при проверке алертов: параллельно, но не больше max_concurrency одновременно
+        let mut tasks = JoinSet::new();
+        for (location, town) in towns {
+            let scheduler = self.clone();
+            tasks.spawn(async move {
+                let Ok(_permit) = scheduler.concurrency.acquire().await else {
+                    return (location, None);
+                };
+                scheduler.rate_limiter.acquire().await;
+                match get_forecast(&town, DIGEST_FORECAST_DAYS).await {
+                    Ok(forecast) => (location, Some(forecast)),
                     Err(e) => {
-                        log::error!("Error checking alert for {} in {}: {}", chat_id, alert.city, e);
+                        log::warn!("Failed to fetch weekly forecast for {}: {}", town, e);
+                        (location, None)
                     }
                 }
-                
-                // Небольшая задержка между проверками чтобы не перегружать API
-                tokio::time::sleep(Duration::from_millis(100)).await;
+            });
+        }
+        
+        let mut forecasts = DigestForecasts::new();
+        while let Some(result) = tasks.join_next().await {
+            match result {
+                Ok((location, forecast)) => {
+                    forecasts.insert(location, forecast);
+                }
+                Err(e) => log::error!("Weekly digest fetch task failed: {}", e),
             }
         }
         
-        log::info!("Alert check cycle completed");
-        Ok(())
+        for (chat_id, user_data) in due_users {
+            if let Some(message) = build_weekly_digest(&user_data, &forecasts) {
+                if let Err(e) = self.bot.send_message(chat_id, message).await {
+                    log::error!("Failed to send weekly digest to user {}: {}", chat_id, e);
+                    continue;
+                }
+                log::info!("Weekly digest sent to user {}", chat_id);
+            }
+            
+            // Отмечаем отправку, даже если городов нет - чтобы не проверять каждые 5 минут
+            update_user_data(&self.state, chat_id, |user_data| {
+                user_data.last_weekly_digest = Some(now);
+            });
+        }
     }
-} 
\ No newline at end of file
+    
+    /// Пересылает подписанным пользователям новые официальные предупреждения по их городам.
+    /// Каждое предупреждение отправляется пользователю один раз
+    async fn forward_official_warnings(&self) {
+        {
+            let mut last_check = self.last_warnings_check.lock().unwrap();
+            if last_check.is_some_and(|checked| checked.elapsed() < self.config.warnings_interval) {
+                return;
+            }
+            *last_check = Some(Instant::now());
+        }
+        
+        // Группируем подписчиков по локации, чтобы запросить каждый город один раз
+        let mut subscribers_by_location: HashMap<String, (String, Vec<ChatId>)> = HashMap::new();
+        {
+            let state_guard = self.state.data.lock().unwrap();
+            for (chat_id, user_data) in state_guard.iter() {
+                if !user_data.official_warnings_enabled {
+                    continue;
+                }
+                for town in digest_towns(user_data) {
+                    subscribers_by_location
+                        .entry(normalize_location(&town))
+                        .or_insert_with(|| (town.trim().to_string(), Vec::new()))
+                        .1
+                        .push(*chat_id);
+                }
+            }
+        }
+        
+        if subscribers_by_location.is_empty() {
+            return;
+        }
+        log::info!("Checking official warnings in {} locations", subscribers_by_location.len());
+        
+        let now = Utc::now();
+        for (town, chat_ids) in subscribers_by_location.into_values() {
+            self.rate_limiter.acquire().await;
+            let forecast = match get_forecast_with_warnings(&town, 1).await {
+                Ok(forecast) => forecast,
+                Err(e) => {
+                    log::error!("Failed to fetch official warnings for {}: {}", town, e);
+                    continue;
+                }
+            };
+            
+            for warning in forecast.alerts.active(now) {
+                let fingerprint = warning.fingerprint();
+                for chat_id in &chat_ids {
+                    match self.state.storage.is_warning_seen(*chat_id, &fingerprint) {
+                        Ok(false) => {}
+                        Ok(true) => continue,
+                        Err(e) => {
+                            log::error!("Failed to check seen warnings for user {}: {}", chat_id, e);
+                            continue;
+                        }
+                    }
+                    
+                    let message = format_official_warning_message(warning, &forecast.location.name);
+                    if let Err(e) = self.bot.send_message(*chat_id, message).await {
+                        log::error!("Failed to send official warning to user {}: {}", chat_id, e);
+                        continue;
+                    }
+                    log::info!("Official warning '{}' sent to user {}", warning.title(), chat_id);
+                    
+                    if let Err(e) = self.state.storage.mark_warning_seen(*chat_id, &fingerprint, warning.expires_at()) {
+                        log::error!("Failed to mark warning as seen for user {}: {}", chat_id, e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn to_chrono(duration: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(duration).unwrap_or_else(|_| chrono::Duration::zero())
+}
@@ -2,13 +2,344 @@ use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use teloxide::types::ChatId;
 use crate::storage::Storage;
+use crate::rules::{Comparison, Metric, RuleExpr, RuleJoin};
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+/// Ощущаемая жара, начиная с которой алерт считается критичным (порог "extreme heat caution" NWS)
+pub const CRITICAL_FEELS_LIKE_HEAT_C: f32 = 32.0;
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum AlertType {
-    StandardWeatherAlert,
+    /// Опасная погода выбранных категорий (по коду условий провайдера и экстремальным значениям)
+    StandardWeatherAlert {
+        #[serde(default = "SevereCategory::default_selection")]
+        categories: Vec<SevereCategory>,
+    },
     TemperatureThreshold { min: Option<f32>, max: Option<f32> },
-    WindSpeed { max: f32 },
+    WindSpeed {
+        max: f32,
+        #[serde(default)]
+        use_gusts: bool,
+        #[serde(default)]
+        direction: Option<WindSector>,
+    },
     Humidity { min: Option<u32>, max: Option<u32> },
+    Precipitation { min_chance: Option<u32>, min_mm: Option<f32>, kind: PrecipitationKind },
+    UvIndex { max: f32 },
+    AirQuality { min_epa_index: u8, max_pm2_5: Option<f32> },
+    PressureDrop { drop_hpa: f32, window_hours: u8 },
+    Frost { max_temp: f32 },
+    SnowIce { min_snow_cm: Option<f32>, ice_risk: bool },
+    FeelsLike { min: Option<f32>, max: Option<f32> },
+    Composite { rule: RuleExpr },
+    /// Перепад температуры: за window_hours часов или, если окно не задано, между соседними днями
+    TemperatureSwing { delta: f32, direction: SwingDirection, window_hours: Option<u8> },
+    /// Окно приятной погоды: сухо, температура в диапазоне и слабый ветер не меньше min_hours часов подряд днем
+    GoodWeather { min_temp: f32, max_temp: f32, max_wind: f32, min_hours: u8 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum PrecipitationKind {
+    Any,
+    Rain,
+    Snow,
+}
+
+impl PrecipitationKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PrecipitationKind::Any => "rain or snow",
+            PrecipitationKind::Rain => "rain",
+            PrecipitationKind::Snow => "snow",
+        }
+    }
+}
+
+/// Сектор направления ветра (откуда дует), 8 румбов по 45°
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum WindSector {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl WindSector {
+    pub const ALL: [WindSector; 8] = [
+        WindSector::N, WindSector::NE, WindSector::E, WindSector::SE,
+        WindSector::S, WindSector::SW, WindSector::W, WindSector::NW,
+    ];
+    
+    pub fn code(&self) -> &'static str {
+        match self {
+            WindSector::N => "N",
+            WindSector::NE => "NE",
+            WindSector::E => "E",
+            WindSector::SE => "SE",
+            WindSector::S => "S",
+            WindSector::SW => "SW",
+            WindSector::W => "W",
+            WindSector::NW => "NW",
+        }
+    }
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            WindSector::N => "northerly",
+            WindSector::NE => "north-easterly",
+            WindSector::E => "easterly",
+            WindSector::SE => "south-easterly",
+            WindSector::S => "southerly",
+            WindSector::SW => "south-westerly",
+            WindSector::W => "westerly",
+            WindSector::NW => "north-westerly",
+        }
+    }
+    
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|sector| sector.code().eq_ignore_ascii_case(code))
+    }
+    
+    /// Сектор по направлению в градусах (0° - север, по часовой стрелке)
+    pub fn from_degree(degree: f32) -> Self {
+        let index = ((degree.rem_euclid(360.0) + 22.5) / 45.0) as usize % 8;
+        Self::ALL[index]
+    }
+    
+    /// Сектор по 16-румбовому обозначению провайдера ("N", "NNE", "NE", ...)
+    pub fn from_compass(direction: &str) -> Option<Self> {
+        const POINTS: [&str; 16] = [
+            "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE",
+            "S", "SSW", "SW", "WSW", "W", "WNW", "NW", "NNW",
+        ];
+        let index = POINTS.iter().position(|point| point.eq_ignore_ascii_case(direction.trim()))?;
+        Some(Self::from_degree(index as f32 * 22.5))
+    }
+}
+
+/// Категория опасной погоды для стандартного алерта
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub enum SevereCategory {
+    Thunderstorm,
+    FreezingRain,
+    Blizzard,
+    HeavySnow,
+    HeavyRain,
+    Fog,
+    ExtremeHeat,
+    ExtremeCold,
+    StormWind,
+}
+
+impl SevereCategory {
+    pub const ALL: [SevereCategory; 9] = [
+        SevereCategory::Thunderstorm, SevereCategory::FreezingRain, SevereCategory::Blizzard,
+        SevereCategory::HeavySnow, SevereCategory::HeavyRain, SevereCategory::Fog,
+        SevereCategory::ExtremeHeat, SevereCategory::ExtremeCold, SevereCategory::StormWind,
+    ];
+    
+    pub fn default_selection() -> Vec<SevereCategory> {
+        Self::DEFAULTS.to_vec()
+    }
+    
+    /// Категории нового стандартного алерта: туман слишком частый, его включают вручную
+    pub const DEFAULTS: [SevereCategory; 8] = [
+        SevereCategory::Thunderstorm, SevereCategory::FreezingRain, SevereCategory::Blizzard,
+        SevereCategory::HeavySnow, SevereCategory::HeavyRain,
+        SevereCategory::ExtremeHeat, SevereCategory::ExtremeCold, SevereCategory::StormWind,
+    ];
+    
+    pub fn code(&self) -> &'static str {
+        match self {
+            SevereCategory::Thunderstorm => "thunder",
+            SevereCategory::FreezingRain => "freezing",
+            SevereCategory::Blizzard => "blizzard",
+            SevereCategory::HeavySnow => "heavysnow",
+            SevereCategory::HeavyRain => "heavyrain",
+            SevereCategory::Fog => "fog",
+            SevereCategory::ExtremeHeat => "heat",
+            SevereCategory::ExtremeCold => "cold",
+            SevereCategory::StormWind => "storm",
+        }
+    }
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            SevereCategory::Thunderstorm => "⛈️ Thunderstorm",
+            SevereCategory::FreezingRain => "🧊 Freezing rain",
+            SevereCategory::Blizzard => "🌨️ Blizzard",
+            SevereCategory::HeavySnow => "❄️ Heavy snow",
+            SevereCategory::HeavyRain => "🌧️ Heavy rain",
+            SevereCategory::Fog => "🌫️ Fog",
+            SevereCategory::ExtremeHeat => "🔥 Extreme heat",
+            SevereCategory::ExtremeCold => "🥶 Extreme cold",
+            SevereCategory::StormWind => "💨 Storm wind",
+        }
+    }
+    
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|category| category.code().eq_ignore_ascii_case(code))
+    }
+    
+    /// Категория по коду условий WeatherAPI (condition.code). Жара, холод и ветер
+    /// определяются по значениям, а не по коду
+    pub fn from_condition_code(code: u32) -> Option<Self> {
+        match code {
+            1087 | 1273 | 1276 | 1279 | 1282 => Some(SevereCategory::Thunderstorm),
+            1072 | 1168 | 1171 | 1198 | 1201 | 1237 | 1261 | 1264 => Some(SevereCategory::FreezingRain),
+            1114 | 1117 => Some(SevereCategory::Blizzard),
+            1222 | 1225 | 1258 => Some(SevereCategory::HeavySnow),
+            1192 | 1195 | 1243 | 1246 => Some(SevereCategory::HeavyRain),
+            1135 | 1147 => Some(SevereCategory::Fog),
+            _ => None,
+        }
+    }
+}
+
+/// Направление перепада температуры, о котором нужно предупредить
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum SwingDirection {
+    Drop,
+    Rise,
+    Either,
+}
+
+impl SwingDirection {
+    pub const ALL: [SwingDirection; 3] = [SwingDirection::Drop, SwingDirection::Rise, SwingDirection::Either];
+    
+    pub fn code(&self) -> &'static str {
+        match self {
+            SwingDirection::Drop => "drop",
+            SwingDirection::Rise => "rise",
+            SwingDirection::Either => "any",
+        }
+    }
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            SwingDirection::Drop => "colder",
+            SwingDirection::Rise => "warmer",
+            SwingDirection::Either => "colder or warmer",
+        }
+    }
+    
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|direction| direction.code() == code)
+    }
+    
+    /// Величина изменения температуры в нужную сторону (отрицательная - перепад в обратную сторону)
+    pub fn magnitude(&self, change: f32) -> f32 {
+        match self {
+            SwingDirection::Drop => -change,
+            SwingDirection::Rise => change,
+            SwingDirection::Either => change.abs(),
+        }
+    }
+}
+
+impl AlertType {
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            AlertType::StandardWeatherAlert { .. } => "🚨",
+            AlertType::TemperatureThreshold { .. } => "🌡️",
+            AlertType::WindSpeed { .. } => "💨",
+            AlertType::Humidity { .. } => "💧",
+            AlertType::Precipitation { .. } => "🌧️",
+            AlertType::UvIndex { .. } => "☀️",
+            AlertType::AirQuality { .. } => "🫁",
+            AlertType::PressureDrop { .. } => "📉",
+            AlertType::Frost { .. } => "🥶",
+            AlertType::SnowIce { .. } => "🧊",
+            AlertType::FeelsLike { .. } => "🥵",
+            AlertType::Composite { .. } => "🧩",
+            AlertType::TemperatureSwing { .. } => "🎢",
+            AlertType::GoodWeather { .. } => "🌤️",
+        }
+    }
+    
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            AlertType::StandardWeatherAlert { .. } => "Standard",
+            AlertType::TemperatureThreshold { .. } => "Temperature",
+            AlertType::WindSpeed { .. } => "Wind",
+            AlertType::Humidity { .. } => "Humidity",
+            AlertType::Precipitation { .. } => "Precipitation",
+            AlertType::UvIndex { .. } => "UV Index",
+            AlertType::AirQuality { .. } => "Air Quality",
+            AlertType::PressureDrop { .. } => "Pressure Drop",
+            AlertType::Frost { .. } => "Frost",
+            AlertType::SnowIce { .. } => "Snow & Ice",
+            AlertType::FeelsLike { .. } => "Feels Like",
+            AlertType::Composite { .. } => "Custom Rule",
+            AlertType::TemperatureSwing { .. } => "Temperature Swing",
+            AlertType::GoodWeather { .. } => "Good Weather",
+        }
+    }
+    
+    /// Критичный алерт (шторм, сильная жара): доставленное уведомление нужно подтвердить,
+    /// иначе планировщик напомнит о нем еще раз
+    pub fn is_critical(&self) -> bool {
+        match self {
+            AlertType::StandardWeatherAlert { .. } | AlertType::PressureDrop { .. } => true,
+            AlertType::FeelsLike { max: Some(max), .. } => *max >= CRITICAL_FEELS_LIKE_HEAT_C,
+            _ => false,
+        }
+    }
+    
+    /// Алерт сообщает о возможности (хорошей погоде), а не предупреждает об опасности
+    pub fn is_opportunity(&self) -> bool {
+        matches!(self, AlertType::GoodWeather { .. })
+    }
+    
+    /// Частота проверки по умолчанию для типа алерта (None - глобальная настройка планировщика)
+    pub fn default_check_interval_minutes(&self) -> Option<u32> {
+        match self {
+            // Штормовые предупреждения проверяем чаще
+            AlertType::StandardWeatherAlert { .. } => Some(15),
+            // Влажность меняется медленно - раза в день достаточно
+            AlertType::Humidity { .. } => Some(24 * 60),
+            // УФ-индекс оцениваем раз в час
+            AlertType::UvIndex { .. } => Some(60),
+            // Падение давления - ранний признак шторма, проверяем почаще
+            AlertType::PressureDrop { .. } => Some(30),
+            _ => None,
+        }
+    }
+    
+    /// Пауза между повторными уведомлениями по умолчанию (None - глобальная настройка)
+    pub fn default_cooldown_minutes(&self) -> Option<u32> {
+        match self {
+            AlertType::Humidity { .. } => Some(24 * 60),
+            _ => None,
+        }
+    }
+}
+
+/// Состояние алерта: Clear -> Triggered -> Ongoing -> Clear (отбой)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub enum AlertState {
+    #[default]
+    Clear,
+    Triggered,
+    Ongoing,
+}
+
+impl AlertState {
+    /// Условие алерта сейчас выполняется
+    pub fn is_active(&self) -> bool {
+        !matches!(self, AlertState::Clear)
+    }
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            AlertState::Clear => "🟢 Clear",
+            AlertState::Triggered => "🔴 Triggered",
+            AlertState::Ongoing => "🟠 Ongoing",
+        }
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -21,6 +352,24 @@ pub struct WeatherAlert {
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub last_triggered: Option<chrono::DateTime<chrono::Utc>>,
     pub description: String,
+    #[serde(default)]
+    pub check_interval_minutes: Option<u32>, // Собственная частота проверки, None - по умолчанию
+    #[serde(default)]
+    pub cooldown_minutes: Option<u32>, // Пауза между повторными уведомлениями, None - по умолчанию
+    #[serde(default)]
+    pub state: AlertState,
+    #[serde(default)]
+    pub snoozed_until: Option<chrono::DateTime<chrono::Utc>>, // Алерт отложен до этого времени
+    #[serde(default)]
+    pub custom_description: bool, // Описание задано пользователем и не пересобирается при правках
+    #[serde(default)]
+    pub ack_requested_at: Option<chrono::DateTime<chrono::Utc>>, // Критичное уведомление ждет подтверждения с этого времени
+    #[serde(default)]
+    pub escalated: bool, // Напоминание о неподтвержденном уведомлении уже отправлено
+    #[serde(default)]
+    pub escalation_message_id: Option<i32>, // Закрепленное напоминание, которое снимается вместе с запросом подтверждения
+    #[serde(default)]
+    pub last_checked: Option<chrono::DateTime<chrono::Utc>>, // Последняя проверка планировщиком, от нее считается следующая после перезапуска
 }
 
 impl WeatherAlert {
@@ -28,17 +377,88 @@ impl WeatherAlert {
         Self {
             id,
             city,
+            check_interval_minutes: alert_type.default_check_interval_minutes(),
+            cooldown_minutes: alert_type.default_cooldown_minutes(),
             alert_type,
             is_active: true,
             hours_ahead,
             created_at: chrono::Utc::now(),
             last_triggered: None,
             description,
+            state: AlertState::Clear,
+            snoozed_until: None,
+            custom_description: false,
+            ack_requested_at: None,
+            escalated: false,
+            escalation_message_id: None,
+            last_checked: None,
         }
     }
+    
+    pub fn is_snoozed(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        self.snoozed_until.is_some_and(|until| until > now)
+    }
+    
+    /// Алерт проверяется планировщиком: не на паузе и не отложен
+    pub fn is_running(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        self.is_active && !self.is_snoozed(now)
+    }
+    
+    /// Когда напомнить о неподтвержденном критичном уведомлении (None - напоминать не нужно)
+    pub fn escalation_due(&self, ack_timeout: std::time::Duration) -> Option<chrono::DateTime<chrono::Utc>> {
+        if self.escalated {
+            return None;
+        }
+        let timeout = chrono::Duration::from_std(ack_timeout).unwrap_or(chrono::Duration::MAX);
+        self.ack_requested_at.and_then(|requested| requested.checked_add_signed(timeout))
+    }
+    
+    /// Снимает запрос подтверждения. Возвращает закрепленное напоминание, которое теперь нужно открепить
+    pub fn clear_ack_request(&mut self) -> Option<i32> {
+        self.ack_requested_at = None;
+        self.escalated = false;
+        self.escalation_message_id.take()
+    }
+    
+    pub fn check_interval(&self, default: std::time::Duration) -> std::time::Duration {
+        self.check_interval_minutes
+            .map(|minutes| std::time::Duration::from_secs(minutes as u64 * 60))
+            .unwrap_or(default)
+    }
+    
+    pub fn cooldown(&self, default: std::time::Duration) -> std::time::Duration {
+        self.cooldown_minutes
+            .map(|minutes| std::time::Duration::from_secs(minutes as u64 * 60))
+            .unwrap_or(default)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum AlertEventKind {
+    Triggered,
+    Cleared,
+    /// Повторное (громкое) уведомление о неподтвержденном критичном алерте
+    Escalated,
+    /// Пользователь нажал "Got it" на доставленном уведомлении
+    Acknowledged,
 }
 
+/// Запись в истории срабатываний алерта
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AlertHistoryEntry {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub kind: AlertEventKind,
+    pub target_time: Option<String>, // На какое время прогноза пришлось срабатывание
+    pub observed: Vec<String>,
+    pub delivered: bool,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub acknowledged_by: Option<String>, // Кто подтвердил уведомление (для Acknowledged)
+}
+
+/// Новые поля получают значения по умолчанию, поэтому записи старых версий читаются без миграции
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct UserData {
     pub home_town: Option<String>,
     pub interested_towns: Vec<String>,
@@ -52,41 +472,59 @@ pub struct UserData {
     pub waiting_for_alert_temperature_min: bool,
     pub waiting_for_alert_temperature_max: bool,
     pub waiting_for_alert_wind_speed: bool,
+    pub waiting_for_alert_wind_gusts: bool,
+    pub waiting_for_alert_wind_direction: bool,
     pub waiting_for_alert_humidity_min: bool,
     pub waiting_for_alert_humidity_max: bool,
+    pub waiting_for_alert_precip_chance: bool,
+    pub waiting_for_alert_precip_mm: bool,
+    pub waiting_for_alert_precip_kind: bool,
+    pub waiting_for_alert_uv_max: bool,
+    pub waiting_for_alert_aqi_index: bool,
+    pub waiting_for_alert_aqi_pm25: bool,
+    pub waiting_for_alert_pressure_drop: bool,
+    pub waiting_for_alert_pressure_window: bool,
+    pub waiting_for_alert_frost_temp: bool,
+    pub waiting_for_alert_snow_cm: bool,
+    pub waiting_for_alert_snow_ice: bool,
+    pub waiting_for_alert_feels_preset: bool,
+    pub waiting_for_alert_feels_min: bool,
+    pub waiting_for_alert_feels_max: bool,
+    pub waiting_for_alert_rule_metric: bool,
+    pub waiting_for_alert_rule_op: bool,
+    pub waiting_for_alert_rule_value: bool,
+    pub waiting_for_alert_rule_next: bool,
+    pub waiting_for_alert_standard_categories: bool,
+    pub waiting_for_alert_swing_delta: bool,
+    pub waiting_for_alert_swing_direction: bool,
+    pub waiting_for_alert_swing_mode: bool,
+    pub waiting_for_alert_good_preset: bool,
+    pub waiting_for_alert_good_temp_min: bool,
+    pub waiting_for_alert_good_temp_max: bool,
+    pub waiting_for_alert_good_wind: bool,
+    pub waiting_for_alert_good_window: bool,
     pub waiting_for_alert_hours_input: bool,
+    pub waiting_for_alert_schedule: bool,
+    pub editing_alert_id: Option<String>,
+    pub waiting_for_edit_city: bool,
+    pub waiting_for_edit_hours: bool,
+    pub waiting_for_edit_description: bool,
+    pub waiting_for_edit_schedule: bool,
     pub pending_alert_city: Option<String>,
     pub pending_alert_type: Option<AlertType>,
     pub pending_alert_hours: Option<u8>,
+    pub pending_rule_metric: Option<Metric>,
+    pub pending_rule_op: Option<Comparison>,
+    pub pending_rule_join: Option<RuleJoin>,
+    pub weekly_digest_enabled: bool,
+    pub last_weekly_digest: Option<chrono::DateTime<chrono::Utc>>,
+    pub official_warnings_enabled: bool,
 }
 
-impl Default for UserData {
-    fn default() -> Self {
-        Self {
-            home_town: None,
-            interested_towns: Vec::new(),
-            weather_alerts: Vec::new(),
-            waiting_for_city: false,
-            waiting_for_forecast_city: false,
-            waiting_for_home_town: false,
-            waiting_for_interested_town: false,
-            removing_interested_town: false,
-            waiting_for_alert_city: false,
-            waiting_for_alert_temperature_min: false,
-            waiting_for_alert_temperature_max: false,
-            waiting_for_alert_wind_speed: false,
-            waiting_for_alert_humidity_min: false,
-            waiting_for_alert_humidity_max: false,
-            waiting_for_alert_hours_input: false,
-            pending_alert_city: None,
-            pending_alert_type: None,
-            pending_alert_hours: None,
-        }
-    }
-}
+pub type UsersData = Arc<Mutex<HashMap<ChatId, UserData>>>;
 
 pub struct SharedState {
-    pub data: Arc<Mutex<HashMap<ChatId, UserData>>>,
+    pub data: UsersData,
     pub storage: Storage,
 }
 
@@ -105,7 +543,7 @@ pub fn create_shared_state() -> Result<SharedState, Box<dyn std::error::Error>>
     Ok(SharedState { data, storage })
 }
 
-pub fn create_shared_state_with_data(storage: Storage, loaded_data: Arc<Mutex<HashMap<ChatId, UserData>>>) -> SharedState {
+pub fn create_shared_state_with_data(storage: Storage, loaded_data: UsersData) -> SharedState {
     SharedState { data: loaded_data, storage }
 }
 
@@ -125,7 +563,7 @@ where
     F: FnOnce(&mut UserData),
 {
     let mut state_guard = state.data.lock().unwrap();
-    let user_data = state_guard.entry(chat_id).or_insert_with(UserData::default);
+    let user_data = state_guard.entry(chat_id).or_default();
     updater(user_data);
     
     // Автоматически сохраняем изменения в Sled
@@ -2,7 +2,43 @@ use std::sync::Arc;
 use sled::Db;
 use teloxide::types::ChatId;
 use teloxide::prelude::Requester;
-use crate::state::UserData;
+use crate::state::{AlertHistoryEntry, UserData, UsersData};
+
+/// Отдельное дерево Sled для истории срабатываний алертов
+const ALERT_HISTORY_TREE: &str = "alert_history";
+/// Ограничения хранения истории: не больше N записей на алерт и не старше M дней
+pub const ALERT_HISTORY_MAX_ENTRIES: usize = 50;
+pub const ALERT_HISTORY_MAX_AGE_DAYS: i64 = 90;
+/// Дерево уже отправленных пользователям официальных предупреждений
+const SEEN_WARNINGS_TREE: &str = "seen_warnings";
+/// Сколько помнить предупреждение без срока действия или после его окончания
+const SEEN_WARNING_RETENTION_DAYS: i64 = 14;
+/// Версия формата записей: байт версии и JSON, который переживает добавление полей с #[serde(default)].
+/// Записи без байта версии - bincode исходной структуры UserData (см. модуль legacy)
+const STORAGE_FORMAT_VERSION: u8 = 2;
+
+fn encode_record<T: serde::Serialize>(value: &T) -> Result<Vec<u8>, serde_json::Error> {
+    let mut bytes = vec![STORAGE_FORMAT_VERSION];
+    serde_json::to_writer(&mut bytes, value)?;
+    Ok(bytes)
+}
+
+fn decode_record<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> Result<T, Box<dyn std::error::Error>> {
+    match bytes.split_first() {
+        Some((&STORAGE_FORMAT_VERSION, json)) => Ok(serde_json::from_slice(json)?),
+        Some((version, _)) => Err(format!("unsupported record format version {}", version).into()),
+        None => Err("empty record".into()),
+    }
+}
+
+/// Bincode исходной UserData начинается с тега Option (0 или 1), поэтому не путается с байтом версии
+fn decode_user_data(bytes: &[u8]) -> Result<UserData, Box<dyn std::error::Error>> {
+    if bytes.first() == Some(&STORAGE_FORMAT_VERSION) {
+        decode_record(bytes)
+    } else {
+        Ok(bincode::deserialize::<legacy::UserData>(bytes)?.into())
+    }
+}
 
 #[derive(Clone)]
 pub struct Storage {
@@ -30,7 +66,7 @@ impl Storage {
     
     pub fn save_user_data(&self, chat_id: ChatId, user_data: &UserData) -> Result<(), Box<dyn std::error::Error>> {
         let key = chat_id.0.to_le_bytes();
-        let value = bincode::serialize(user_data)?;
+        let value = encode_record(user_data)?;
         self.db.insert(key, value)?;
         self.db.flush()?; // Ensure data is written to disk
         log::debug!("Saved data for user {}", chat_id);
@@ -40,7 +76,7 @@ impl Storage {
     pub fn load_user_data(&self, chat_id: ChatId) -> Result<Option<UserData>, Box<dyn std::error::Error>> {
         let key = chat_id.0.to_le_bytes();
         if let Some(value) = self.db.get(key)? {
-            let user_data: UserData = bincode::deserialize(&value)?;
+            let user_data = decode_user_data(&value)?;
             log::debug!("Loaded data for user {}", chat_id);
             Ok(Some(user_data))
         } else {
@@ -51,24 +87,47 @@ impl Storage {
     pub fn remove_user_data(&self, chat_id: ChatId) -> Result<(), Box<dyn std::error::Error>> {
         let key = chat_id.0.to_le_bytes();
         self.db.remove(key)?;
+        
+        // Вместе с пользователем удаляем и историю его алертов, и отметки о предупреждениях
+        for tree_name in [ALERT_HISTORY_TREE, SEEN_WARNINGS_TREE] {
+            let tree = self.db.open_tree(tree_name)?;
+            for result in tree.scan_prefix(chat_id.0.to_be_bytes()) {
+                let (key, _) = result?;
+                tree.remove(key)?;
+            }
+        }
+        
         self.db.flush()?;
         log::info!("Removed data for user {}", chat_id);
         Ok(())
     }
     
-    pub fn load_all_users(&self) -> Result<std::sync::Arc<std::sync::Mutex<std::collections::HashMap<ChatId, UserData>>>, Box<dyn std::error::Error>> {
+    pub fn load_all_users(&self) -> Result<UsersData, Box<dyn std::error::Error>> {
         let data = std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new()));
         let mut state_guard = data.lock().unwrap();
         
+        let mut skipped = 0;
         for result in self.db.iter() {
             let (key, value) = result?;
-            let chat_id_bytes: [u8; 8] = key.as_ref().try_into()?;
+            let Ok(chat_id_bytes) = <[u8; 8]>::try_from(key.as_ref()) else {
+                log::warn!("Skipping record with malformed key {:?}", key);
+                skipped += 1;
+                continue;
+            };
             let chat_id = ChatId(i64::from_le_bytes(chat_id_bytes));
-            let user_data: UserData = bincode::deserialize(&value)?;
-            state_guard.insert(chat_id, user_data);
+            // Одна нечитаемая запись не должна лишать алертов всех остальных пользователей
+            match decode_user_data(&value) {
+                Ok(user_data) => {
+                    state_guard.insert(chat_id, user_data);
+                }
+                Err(e) => {
+                    log::error!("Skipping unreadable data for user {}: {}", chat_id, e);
+                    skipped += 1;
+                }
+            }
         }
         
-        log::info!("Loaded {} users from Sled database", state_guard.len());
+        log::info!("Loaded {} users from Sled database ({} skipped)", state_guard.len(), skipped);
         drop(state_guard);
         Ok(data)
     }
@@ -86,6 +145,99 @@ impl Storage {
         Ok(chat_ids)
     }
     
+    /// Ключ истории: chat_id (big-endian) + id алерта + '/' + время в микросекундах (big-endian),
+    /// так записи одного алерта лежат подряд в хронологическом порядке
+    fn alert_history_prefix(chat_id: ChatId, alert_id: &str) -> Vec<u8> {
+        let mut prefix = chat_id.0.to_be_bytes().to_vec();
+        prefix.extend_from_slice(alert_id.as_bytes());
+        prefix.push(b'/');
+        prefix
+    }
+    
+    pub fn record_alert_event(&self, chat_id: ChatId, alert_id: &str, entry: &AlertHistoryEntry) -> Result<(), Box<dyn std::error::Error>> {
+        let history = self.db.open_tree(ALERT_HISTORY_TREE)?;
+        let prefix = Self::alert_history_prefix(chat_id, alert_id);
+        
+        let mut key = prefix.clone();
+        key.extend_from_slice(&entry.timestamp.timestamp_micros().to_be_bytes());
+        history.insert(key, encode_record(entry)?)?;
+        
+        // Ограничиваем размер истории: удаляем самые старые и слишком давние записи
+        let keys: Vec<sled::IVec> = history.scan_prefix(&prefix).keys().collect::<Result<_, _>>()?;
+        let min_micros = (chrono::Utc::now() - chrono::Duration::days(ALERT_HISTORY_MAX_AGE_DAYS)).timestamp_micros();
+        let excess = keys.len().saturating_sub(ALERT_HISTORY_MAX_ENTRIES);
+        for (index, key) in keys.iter().enumerate() {
+            let micros = key[prefix.len()..].try_into().map(i64::from_be_bytes).unwrap_or(0);
+            if index < excess || micros < min_micros {
+                history.remove(key)?;
+            }
+        }
+        
+        self.db.flush()?;
+        log::debug!("Recorded {:?} event for alert {} of user {}", entry.kind, alert_id, chat_id);
+        Ok(())
+    }
+    
+    /// Возвращает страницу истории алерта (от новых к старым) и общее число записей
+    pub fn load_alert_history(&self, chat_id: ChatId, alert_id: &str, offset: usize, limit: usize) -> Result<(Vec<AlertHistoryEntry>, usize), Box<dyn std::error::Error>> {
+        let history = self.db.open_tree(ALERT_HISTORY_TREE)?;
+        let prefix = Self::alert_history_prefix(chat_id, alert_id);
+        
+        let total = history.scan_prefix(&prefix).count();
+        let mut entries = Vec::new();
+        for result in history.scan_prefix(&prefix).values().rev().skip(offset).take(limit) {
+            match decode_record::<AlertHistoryEntry>(&result?) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => log::warn!("Skipping unreadable history entry for alert {}: {}", alert_id, e),
+            }
+        }
+        
+        Ok((entries, total))
+    }
+    
+    pub fn remove_alert_history(&self, chat_id: ChatId, alert_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let history = self.db.open_tree(ALERT_HISTORY_TREE)?;
+        for result in history.scan_prefix(Self::alert_history_prefix(chat_id, alert_id)) {
+            let (key, _) = result?;
+            history.remove(key)?;
+        }
+        self.db.flush()?;
+        Ok(())
+    }
+    
+    /// Ключ отметки: chat_id (big-endian) + отпечаток предупреждения
+    fn seen_warning_key(chat_id: ChatId, fingerprint: &str) -> Vec<u8> {
+        let mut key = chat_id.0.to_be_bytes().to_vec();
+        key.extend_from_slice(fingerprint.as_bytes());
+        key
+    }
+    
+    pub fn is_warning_seen(&self, chat_id: ChatId, fingerprint: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let seen = self.db.open_tree(SEEN_WARNINGS_TREE)?;
+        Ok(seen.contains_key(Self::seen_warning_key(chat_id, fingerprint))?)
+    }
+    
+    /// Отмечает официальное предупреждение как отправленное пользователю.
+    /// Значение - до какого момента (unix-время) помнить отметку
+    pub fn mark_warning_seen(&self, chat_id: ChatId, fingerprint: &str, expires: Option<chrono::DateTime<chrono::Utc>>) -> Result<(), Box<dyn std::error::Error>> {
+        let seen = self.db.open_tree(SEEN_WARNINGS_TREE)?;
+        let now = chrono::Utc::now();
+        
+        // Забываем предупреждения, которые давно закончились
+        for result in seen.scan_prefix(chat_id.0.to_be_bytes()) {
+            let (key, value) = result?;
+            let keep_until = value.as_ref().try_into().map(i64::from_be_bytes).unwrap_or(0);
+            if keep_until < now.timestamp() {
+                seen.remove(key)?;
+            }
+        }
+        
+        let keep_until = expires.unwrap_or(now).max(now) + chrono::Duration::days(SEEN_WARNING_RETENTION_DAYS);
+        seen.insert(Self::seen_warning_key(chat_id, fingerprint), &keep_until.timestamp().to_be_bytes())?;
+        self.db.flush()?;
+        Ok(())
+    }
+    
     pub async fn cleanup_blocked_users(
         &self,
         bot: &teloxide::Bot,
@@ -130,4 +282,162 @@ impl Storage {
         log::info!("Database stats: {} users, {} bytes on disk", len, size);
         Ok(())
     }
-} 
\ No newline at end of file
+}
+
+/// Структуры в том виде, в каком их хранили до появления версии формата (bincode без тегов)
+mod legacy {
+    use crate::state::{self, SevereCategory, WeatherAlert};
+    
+    #[derive(serde::Deserialize)]
+    #[cfg_attr(test, derive(serde::Serialize))]
+    pub enum AlertType {
+        StandardWeatherAlert,
+        TemperatureThreshold { min: Option<f32>, max: Option<f32> },
+        WindSpeed { max: f32 },
+        Humidity { min: Option<u32>, max: Option<u32> },
+    }
+    
+    #[derive(serde::Deserialize)]
+    #[cfg_attr(test, derive(serde::Serialize))]
+    pub struct Alert {
+        pub id: String,
+        pub city: String,
+        pub alert_type: AlertType,
+        pub is_active: bool,
+        pub hours_ahead: u8,
+        pub created_at: chrono::DateTime<chrono::Utc>,
+        pub last_triggered: Option<chrono::DateTime<chrono::Utc>>,
+        pub description: String,
+    }
+    
+    // Флаги диалога не переносим: незаконченный ввод после обновления начинается заново
+    #[derive(serde::Deserialize)]
+    #[cfg_attr(test, derive(serde::Serialize, Default))]
+    pub struct UserData {
+        pub home_town: Option<String>,
+        pub interested_towns: Vec<String>,
+        pub weather_alerts: Vec<Alert>,
+        pub waiting_for_city: bool,
+        pub waiting_for_forecast_city: bool,
+        pub waiting_for_home_town: bool,
+        pub waiting_for_interested_town: bool,
+        pub removing_interested_town: bool,
+        pub waiting_for_alert_city: bool,
+        pub waiting_for_alert_temperature_min: bool,
+        pub waiting_for_alert_temperature_max: bool,
+        pub waiting_for_alert_wind_speed: bool,
+        pub waiting_for_alert_humidity_min: bool,
+        pub waiting_for_alert_humidity_max: bool,
+        pub waiting_for_alert_hours_input: bool,
+        pub pending_alert_city: Option<String>,
+        pub pending_alert_type: Option<AlertType>,
+        pub pending_alert_hours: Option<u8>,
+    }
+    
+    impl From<AlertType> for state::AlertType {
+        fn from(alert_type: AlertType) -> Self {
+            match alert_type {
+                AlertType::StandardWeatherAlert => state::AlertType::StandardWeatherAlert { categories: SevereCategory::default_selection() },
+                AlertType::TemperatureThreshold { min, max } => state::AlertType::TemperatureThreshold { min, max },
+                AlertType::WindSpeed { max } => state::AlertType::WindSpeed { max, use_gusts: false, direction: None },
+                AlertType::Humidity { min, max } => state::AlertType::Humidity { min, max },
+            }
+        }
+    }
+    
+    impl From<Alert> for WeatherAlert {
+        fn from(alert: Alert) -> Self {
+            let mut migrated = WeatherAlert::new(alert.id, alert.city, alert.alert_type.into(), alert.description, alert.hours_ahead);
+            migrated.is_active = alert.is_active;
+            migrated.created_at = alert.created_at;
+            migrated.last_triggered = alert.last_triggered;
+            migrated
+        }
+    }
+    
+    impl From<UserData> for state::UserData {
+        fn from(user_data: UserData) -> Self {
+            state::UserData {
+                home_town: user_data.home_town,
+                interested_towns: user_data.interested_towns,
+                weather_alerts: user_data.weather_alerts.into_iter().map(WeatherAlert::from).collect(),
+                ..Default::default()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{AlertType, SevereCategory};
+    
+    fn temporary_storage() -> Storage {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        Storage { db: Arc::new(db) }
+    }
+    
+    #[test]
+    fn user_data_round_trips_through_versioned_record() {
+        let mut user_data = UserData { home_town: Some("Kyiv".to_string()), ..Default::default() };
+        user_data.weather_alerts.push(crate::alerts::create_wind_alert("Kyiv".to_string(), 40.0, true, Some(crate::state::WindSector::NW), 24));
+        
+        let bytes = encode_record(&user_data).unwrap();
+        assert_eq!(bytes[0], STORAGE_FORMAT_VERSION);
+        let decoded = decode_user_data(&bytes).unwrap();
+        assert_eq!(decoded.home_town.as_deref(), Some("Kyiv"));
+        assert_eq!(decoded.weather_alerts[0].alert_type, user_data.weather_alerts[0].alert_type);
+    }
+    
+    #[test]
+    fn missing_fields_fall_back_to_defaults() {
+        let json = br#"{"home_town":"Lviv","weather_alerts":[{"id":"a","city":"Lviv","alert_type":{"WindSpeed":{"max":30.0}},"is_active":true,"hours_ahead":12,"created_at":"2024-01-01T00:00:00Z","last_triggered":null,"description":"wind"}]}"#;
+        let mut bytes = vec![STORAGE_FORMAT_VERSION];
+        bytes.extend_from_slice(json);
+        
+        let decoded = decode_user_data(&bytes).unwrap();
+        let alert = &decoded.weather_alerts[0];
+        assert_eq!(alert.alert_type, AlertType::WindSpeed { max: 30.0, use_gusts: false, direction: None });
+        assert!(!alert.escalated);
+        assert!(decoded.interested_towns.is_empty());
+    }
+    
+    #[test]
+    fn legacy_bincode_records_are_migrated() {
+        let legacy_user = legacy::UserData {
+            home_town: Some("Odesa".to_string()),
+            weather_alerts: vec![legacy::Alert {
+                id: "old".to_string(),
+                city: "Odesa".to_string(),
+                alert_type: legacy::AlertType::StandardWeatherAlert,
+                is_active: false,
+                hours_ahead: 48,
+                created_at: chrono::Utc::now(),
+                last_triggered: None,
+                description: "storm".to_string(),
+            }],
+            ..Default::default()
+        };
+        
+        let decoded = decode_user_data(&bincode::serialize(&legacy_user).unwrap()).unwrap();
+        assert_eq!(decoded.home_town.as_deref(), Some("Odesa"));
+        let alert = &decoded.weather_alerts[0];
+        assert_eq!(alert.id, "old");
+        assert!(!alert.is_active);
+        assert_eq!(alert.hours_ahead, 48);
+        assert_eq!(alert.alert_type, AlertType::StandardWeatherAlert { categories: SevereCategory::default_selection() });
+    }
+    
+    #[test]
+    fn load_all_users_skips_unreadable_records() {
+        let storage = temporary_storage();
+        storage.save_user_data(ChatId(1), &UserData { home_town: Some("Kyiv".to_string()), ..Default::default() }).unwrap();
+        storage.db.insert(2i64.to_le_bytes(), &[STORAGE_FORMAT_VERSION, b'{'][..]).unwrap();
+        storage.db.insert(3i64.to_le_bytes(), &[7u8, 1, 2, 3][..]).unwrap();
+        
+        let users = storage.load_all_users().unwrap();
+        let users = users.lock().unwrap();
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[&ChatId(1)].home_town.as_deref(), Some("Kyiv"));
+    }
+}
@@ -15,6 +15,22 @@ pub struct Location {
     pub name: String,
     pub region: String,
     pub country: String,
+    /// Местное время города ("2024-01-15 9:05") и то же время в Unix-секундах
+    #[serde(default)]
+    pub localtime: String,
+    #[serde(default)]
+    pub localtime_epoch: i64,
+}
+
+impl Location {
+    /// Смещение местного времени города относительно UTC
+    pub fn utc_offset(&self) -> Option<chrono::FixedOffset> {
+        let local = chrono::NaiveDateTime::parse_from_str(&self.localtime, "%Y-%m-%d %H:%M").ok()?;
+        let utc = chrono::DateTime::from_timestamp(self.localtime_epoch, 0)?.naive_utc();
+        // localtime дается с точностью до минуты - округляем до четверти часа
+        let quarters = ((local - utc).num_seconds() as f64 / 900.0).round() as i32;
+        chrono::FixedOffset::east_opt(quarters * 900)
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -28,19 +44,125 @@ pub struct Current {
     pub wind_speed: f32,
     #[serde(rename = "wind_dir")]
     pub wind_direction: String,
+    #[serde(default)]
+    pub wind_degree: Option<u16>,
+    #[serde(rename = "gust_kph", default)]
+    pub gust_speed: f32,
     pub humidity: u32,
+    #[serde(rename = "pressure_mb", default)]
+    pub pressure: f32,
+    #[serde(default)]
+    pub uv: f32,
+    #[serde(default)]
+    pub air_quality: Option<AirQuality>,
+}
+
+/// Качество воздуха (концентрации в µg/m³). Провайдер иногда отдает null, поэтому все поля опциональны
+#[derive(Debug, Clone, Deserialize)]
+pub struct AirQuality {
+    pub co: Option<f32>,
+    pub no2: Option<f32>,
+    pub o3: Option<f32>,
+    pub so2: Option<f32>,
+    pub pm2_5: Option<f32>,
+    pub pm10: Option<f32>,
+    #[serde(rename = "us-epa-index")]
+    pub us_epa_index: Option<u8>,
+    #[serde(rename = "gb-defra-index")]
+    pub gb_defra_index: Option<u8>,
+}
+
+/// Название уровня по индексу US EPA (1-6)
+pub fn epa_index_label(index: u8) -> &'static str {
+    match index {
+        0 | 1 => "Good",
+        2 => "Moderate",
+        3 => "Unhealthy for sensitive groups",
+        4 => "Unhealthy",
+        5 => "Very unhealthy",
+        _ => "Hazardous",
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Condition {
     pub text: String,
     pub icon: String,
+    /// Код условий WeatherAPI (1000 - ясно, 1087 - гроза и т.д.), не зависит от языка ответа
+    #[serde(default)]
+    pub code: u32,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ForecastResponse {
     pub location: Location,
     pub forecast: Forecast,
+    #[serde(default)]
+    pub alerts: WeatherWarnings,
+}
+
+/// Официальные предупреждения метеослужб (массив `alerts.alert` в ответе провайдера)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WeatherWarnings {
+    #[serde(default)]
+    pub alert: Vec<WeatherWarning>,
+}
+
+impl WeatherWarnings {
+    /// Предупреждения, срок действия которых еще не истек
+    pub fn active(&self, now: chrono::DateTime<chrono::Utc>) -> Vec<&WeatherWarning> {
+        let mut seen = Vec::new();
+        self.alert.iter()
+            .filter(|warning| warning.is_active(now))
+            // Провайдер часто дублирует бюллетень для каждой зоны - оставляем один
+            .filter(|warning| {
+                let key = warning.fingerprint();
+                if seen.contains(&key) {
+                    false
+                } else {
+                    seen.push(key);
+                    true
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WeatherWarning {
+    pub headline: String,
+    pub severity: String,
+    pub urgency: String,
+    pub areas: String,
+    pub event: String,
+    pub effective: String,
+    pub expires: String,
+    pub desc: String,
+    pub instruction: String,
+}
+
+impl WeatherWarning {
+    /// Время окончания действия. None, если провайдер не указал его или формат не распознан
+    pub fn expires_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        chrono::DateTime::parse_from_rfc3339(self.expires.trim())
+            .ok()
+            .map(|time| time.with_timezone(&chrono::Utc))
+    }
+    
+    pub fn is_active(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        self.expires_at().is_none_or(|expires| expires > now)
+    }
+    
+    /// Идентификатор бюллетеня. У провайдера нет id, поэтому собираем его из стабильных полей
+    pub fn fingerprint(&self) -> String {
+        format!("{}|{}|{}|{}", self.event.trim(), self.headline.trim(), self.effective.trim(), self.expires.trim())
+    }
+    
+    /// Заголовок для показа: headline, а если его нет - тип события
+    pub fn title(&self) -> &str {
+        if self.headline.trim().is_empty() { self.event.trim() } else { self.headline.trim() }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -53,6 +175,8 @@ pub struct Forecast {
 pub struct ForecastDay {
     pub date: String,
     pub day: Day,
+    #[serde(default)]
+    pub hour: Vec<Hour>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -66,6 +190,139 @@ pub struct Day {
     pub avg_humidity: f32,
     #[serde(rename = "maxwind_kph")]
     pub max_wind: f32,
+    #[serde(rename = "totalprecip_mm", default)]
+    pub total_precip: f32,
+    #[serde(rename = "totalsnow_cm", default)]
+    pub total_snow: f32,
+    #[serde(default)]
+    pub daily_chance_of_rain: u32,
+    #[serde(default)]
+    pub daily_chance_of_snow: u32,
+    #[serde(default)]
+    pub uv: f32,
+    #[serde(default)]
+    pub air_quality: Option<AirQuality>,
+}
+
+/// Почасовой прогноз. Время `time` - локальное время города
+#[derive(Debug, Deserialize)]
+pub struct Hour {
+    pub time_epoch: i64,
+    pub time: String,
+    #[serde(rename = "temp_c")]
+    pub temperature: f32,
+    #[serde(rename = "feelslike_c", default)]
+    pub feels_like: Option<f32>,
+    #[serde(rename = "heatindex_c", default)]
+    pub heat_index: Option<f32>,
+    #[serde(rename = "windchill_c", default)]
+    pub wind_chill: Option<f32>,
+    pub condition: Condition,
+    #[serde(rename = "wind_kph")]
+    pub wind_speed: f32,
+    #[serde(rename = "wind_dir", default)]
+    pub wind_direction: String,
+    #[serde(default)]
+    pub wind_degree: Option<u16>,
+    #[serde(rename = "gust_kph", default)]
+    pub gust_speed: f32,
+    pub humidity: u32,
+    #[serde(rename = "pressure_mb", default)]
+    pub pressure: f32,
+    #[serde(rename = "dewpoint_c", default)]
+    pub dew_point: f32,
+    #[serde(default)]
+    pub precip_mm: f32,
+    #[serde(default)]
+    pub snow_cm: f32,
+    #[serde(default)]
+    pub chance_of_rain: u32,
+    #[serde(default)]
+    pub chance_of_snow: u32,
+    #[serde(default)]
+    pub uv: f32,
+    #[serde(default)]
+    pub is_day: u8,
+    #[serde(default)]
+    pub air_quality: Option<AirQuality>,
+}
+
+impl Hour {
+    /// Время в формате "HH:MM" из локального времени прогноза
+    pub fn clock_time(&self) -> &str {
+        self.time.split(' ').nth(1).unwrap_or(&self.time)
+    }
+    
+    /// Час (0-23) по местному времени города
+    pub fn local_hour(&self) -> Option<u32> {
+        self.clock_time().split(':').next()?.parse().ok()
+    }
+    
+    /// Дата по местному времени города ("YYYY-MM-DD")
+    pub fn local_date(&self) -> &str {
+        self.time.split(' ').next().unwrap_or(&self.time)
+    }
+    
+    /// Тихий ясный час прогноза через `index` часов после полуночи 2024-01-05 (UTC, время города совпадает)
+    #[cfg(test)]
+    pub(crate) fn sample(index: u32, temperature: f32) -> Self {
+        let time_epoch = 1_704_412_800 + index as i64 * 3600;
+        let time = chrono::DateTime::from_timestamp(time_epoch, 0).unwrap().format("%Y-%m-%d %H:%M").to_string();
+        Hour {
+            time_epoch,
+            time,
+            temperature,
+            feels_like: None,
+            heat_index: None,
+            wind_chill: None,
+            condition: Condition { text: "Clear".to_string(), icon: String::new(), code: 1000 },
+            wind_speed: 5.0,
+            wind_direction: String::new(),
+            wind_degree: None,
+            gust_speed: 0.0,
+            humidity: 50,
+            pressure: 1015.0,
+            dew_point: temperature - 5.0,
+            precip_mm: 0.0,
+            snow_cm: 0.0,
+            chance_of_rain: 0,
+            chance_of_snow: 0,
+            uv: 0.0,
+            is_day: 1,
+            air_quality: None,
+        }
+    }
+}
+
+/// Приводит название города к виду, пригодному для сравнения
+pub fn normalize_location(city: &str) -> String {
+    city.trim().to_lowercase()
+}
+
+/// Ограничитель частоты запросов к Weather API: выдает слоты не чаще заданного числа в секунду
+pub struct RateLimiter {
+    min_interval: std::time::Duration,
+    next_slot: tokio::sync::Mutex<tokio::time::Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: u32) -> Self {
+        Self {
+            min_interval: std::time::Duration::from_secs(1) / requests_per_second.max(1),
+            next_slot: tokio::sync::Mutex::new(tokio::time::Instant::now()),
+        }
+    }
+    
+    /// Ждет, пока не освободится следующий слот для запроса
+    pub async fn acquire(&self) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock().await;
+            let slot = (*next_slot).max(tokio::time::Instant::now());
+            *next_slot = slot + self.min_interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
 }
 
 pub async fn get_current_weather(city: &str) -> Result<CurrentWeather, Box<dyn std::error::Error + Send + Sync>> {
@@ -73,7 +330,7 @@ pub async fn get_current_weather(city: &str) -> Result<CurrentWeather, Box<dyn s
         .map_err(|_| "WEATHER_API_KEY environment variable not set")?;
     
     let url = format!(
-        "http://api.weatherapi.com/v1/current.json?key={}&q={}&aqi=no",
+        "http://api.weatherapi.com/v1/current.json?key={}&q={}&aqi=yes",
         api_key, city
     );
 
@@ -89,12 +346,33 @@ pub async fn get_current_weather(city: &str) -> Result<CurrentWeather, Box<dyn s
 }
 
 pub async fn get_forecast(city: &str, days: u8) -> Result<ForecastResponse, Box<dyn std::error::Error + Send + Sync>> {
+    fetch_forecast(city, days, false).await
+}
+
+/// Прогноз вместе с официальными предупреждениями - только там, где их показывают или рассылают
+pub async fn get_forecast_with_warnings(city: &str, days: u8) -> Result<ForecastResponse, Box<dyn std::error::Error + Send + Sync>> {
+    fetch_forecast(city, days, true).await
+}
+
+/// Действующие официальные предупреждения для города. current.json их не отдает, поэтому
+/// запрашиваем однодневный прогноз; при ошибке показываем погоду без предупреждений
+pub async fn get_weather_warnings(city: &str) -> WeatherWarnings {
+    match get_forecast_with_warnings(city, 1).await {
+        Ok(forecast) => forecast.alerts,
+        Err(e) => {
+            log::warn!("Failed to get weather warnings for {}: {}", city, e);
+            WeatherWarnings::default()
+        }
+    }
+}
+
+async fn fetch_forecast(city: &str, days: u8, with_warnings: bool) -> Result<ForecastResponse, Box<dyn std::error::Error + Send + Sync>> {
     let api_key = env::var("WEATHER_API_KEY")
         .map_err(|_| "WEATHER_API_KEY environment variable not set")?;
     
     let url = format!(
-        "http://api.weatherapi.com/v1/forecast.json?key={}&q={}&days={}&aqi=no&alerts=no",
-        api_key, city, days
+        "http://api.weatherapi.com/v1/forecast.json?key={}&q={}&days={}&aqi=yes&alerts={}",
+        api_key, city, days, if with_warnings { "yes" } else { "no" }
     );
 
     let client = reqwest::Client::new();
@@ -108,7 +386,7 @@ pub async fn get_forecast(city: &str, days: u8) -> Result<ForecastResponse, Box<
     Ok(forecast)
 }
 
-pub fn format_current_weather(weather: &CurrentWeather) -> String {
+pub fn format_current_weather(weather: &CurrentWeather, warnings: &WeatherWarnings) -> String {
     // Создаем URL-friendly версию названия города
     let city_url = urlencoding::encode(&weather.location.name).to_string();
     
@@ -117,7 +395,7 @@ pub fn format_current_weather(weather: &CurrentWeather) -> String {
 🌡️ *Temperature:* {}°C \\(feels like {}°C\\)
 ☁️ *Condition:* {}
 💨 *Wind:* {} km/h {}
-💧 *Humidity:* {}%
+💧 *Humidity:* {}%{}{}
 
 \\-\\-\\-
 🔗 *More weather info:*
@@ -134,6 +412,8 @@ pub fn format_current_weather(weather: &CurrentWeather) -> String {
         markdown::escape(&weather.current.wind_speed.to_string()),
         markdown::escape(&weather.current.wind_direction),
         weather.current.humidity,
+        weather.current.air_quality.as_ref().map(format_air_quality).unwrap_or_default(),
+        format_weather_warnings(warnings),
         city_url,
         city_url,
         city_url,
@@ -141,6 +421,47 @@ pub fn format_current_weather(weather: &CurrentWeather) -> String {
     )
 }
 
+/// Строка о качестве воздуха для format_current_weather (MarkdownV2)
+fn format_air_quality(air_quality: &AirQuality) -> String {
+    let value = |v: Option<f32>| v.map(|v| format!("{:.1}", v)).unwrap_or_else(|| "n/a".to_string());
+    let index = |v: Option<u8>| v.map(|v| v.to_string()).unwrap_or_else(|| "n/a".to_string());
+    
+    let level = air_quality.us_epa_index
+        .map(|idx| format!("{} ", epa_index_label(idx)))
+        .unwrap_or_default();
+    
+    format!(
+        "\n🫁 *Air quality:* {}\\(US EPA {}, DEFRA {}\\)\n   PM2\\.5 {} \\| PM10 {} \\| O₃ {} \\| NO₂ {} µg/m³",
+        markdown::escape(&level),
+        index(air_quality.us_epa_index),
+        index(air_quality.gb_defra_index),
+        markdown::escape(&value(air_quality.pm2_5)),
+        markdown::escape(&value(air_quality.pm10)),
+        markdown::escape(&value(air_quality.o3)),
+        markdown::escape(&value(air_quality.no2))
+    )
+}
+
+/// Блок действующих официальных предупреждений для format_current_weather и format_forecast (MarkdownV2)
+fn format_weather_warnings(warnings: &WeatherWarnings) -> String {
+    let active = warnings.active(chrono::Utc::now());
+    if active.is_empty() {
+        return String::new();
+    }
+    
+    let mut block = String::from("\n\n🚨 *Official warnings:*");
+    for warning in active {
+        block.push_str(&format!("\n• *{}*", markdown::escape(warning.title())));
+        if !warning.severity.is_empty() {
+            block.push_str(&format!(" \\({}\\)", markdown::escape(&warning.severity)));
+        }
+        if !warning.expires.is_empty() {
+            block.push_str(&format!("\n   until {}", markdown::escape(&warning.expires)));
+        }
+    }
+    block
+}
+
 pub fn format_forecast(forecast: &ForecastResponse) -> String {
     // Создаем URL-friendly версию названия города
     let city_url = urlencoding::encode(&forecast.location.name).to_string();
@@ -164,10 +485,59 @@ pub fn format_forecast(forecast: &ForecastResponse) -> String {
         ));
     }
 
+    let warnings = format_weather_warnings(&forecast.alerts);
+    if !warnings.is_empty() {
+        message.push_str(warnings.trim_start());
+        message.push_str("\n\n");
+    }
+
     message.push_str(&format!(
         "\\-\\-\\-\n🔗 *More weather info:*\n• [WeatherAPI\\.com](https://www.weatherapi.com/weather/q/{})\n• [Яндекс\\.Погода](https://yandex.ru/pogoda/search?text={})\n• [Google Weather](https://www.google.com/search?q=weather+{})\n• [OpenWeatherMap](https://openweathermap.org/find?q={})",
         city_url, city_url, city_url, city_url
     ));
 
     message
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn warning(headline: &str, expires: &str) -> WeatherWarning {
+        WeatherWarning {
+            headline: headline.to_string(),
+            severity: "Severe".to_string(),
+            urgency: String::new(),
+            areas: String::new(),
+            event: "Wind".to_string(),
+            effective: String::new(),
+            expires: expires.to_string(),
+            desc: String::new(),
+            instruction: String::new(),
+        }
+    }
+
+    #[test]
+    fn current_weather_shows_only_active_warnings() {
+        let weather: CurrentWeather = serde_json::from_str(r#"{
+            "location": {"name": "Kyiv", "region": "Kyiv", "country": "Ukraine"},
+            "current": {
+                "temp_c": 3.0, "feelslike_c": -1.0,
+                "condition": {"text": "Overcast", "icon": ""},
+                "wind_kph": 30.0, "wind_dir": "NW", "humidity": 80
+            }
+        }"#).unwrap();
+        let warnings = WeatherWarnings {
+            alert: vec![
+                warning("Storm warning for Kyiv region", "2999-01-01T00:00:00+00:00"),
+                warning("Fog advisory", "2000-01-01T00:00:00+00:00"),
+            ],
+        };
+
+        let text = format_current_weather(&weather, &warnings);
+        assert!(text.contains("Official warnings"));
+        assert!(text.contains("Storm warning for Kyiv region"));
+        assert!(!text.contains("Fog advisory"));
+
+        assert!(!format_current_weather(&weather, &WeatherWarnings::default()).contains("Official warnings"));
+    }
+}
@@ -405,6 +405,43 @@ async fn main() {
         println!("Help response: {}", message.text().unwrap_or("(no text)"));
     }
 
+    // === TEST /ALERT COMMAND ===
+    println!("\n=== TESTING ONE-LINE /ALERT COMMAND ===");
+
+    let alert_commands = [
+        // Без аргументов - справка по синтаксису
+        "/alert",
+        "/alert Kyiv temp<-5 or wind>50 within 12h every 30m cooldown 2h",
+        // Город совпадает с ключевым словом - берется в кавычки
+        "/alert \"Frost\" frost<=-1 within 24h",
+        "/alert Lviv not (rain>=60 or gust>40) and temp>15",
+        // Ошибка должна указывать на неизвестный показатель
+        "/alert Kyiv temp<-5 or wnd>50",
+    ];
+    for command in alert_commands {
+        println!("\n--> User sends: {}", command);
+        bot.update(MockMessageText::new().text(command).from(alice.clone()));
+        bot.dispatch().await;
+
+        let alert_responses = bot.get_responses();
+        if let Some(message) = alert_responses.sent_messages.last() {
+            println!("Response: {}", message.text().unwrap_or("(no text)"));
+        }
+    }
+
+    println!("\n--> User presses button: Alerts menu (should list the three created alerts)");
+    let my_alerts_callback = MockCallbackQuery::new()
+        .data("alerts_menu")
+        .message(start_message.clone());
+    bot.update(my_alerts_callback);
+    bot.dispatch().await;
+
+    let my_alerts_responses = bot.get_responses();
+    if let Some(message) = my_alerts_responses.sent_messages.last() {
+        println!("Alerts menu: {}", message.text().unwrap_or("(no text)"));
+        print_buttons(message);
+    }
+
     println!("\n--- All tests finished ---");
 
     // === TEST DATA CLEANUP ON /START ===
@@ -488,20 +525,14 @@ async fn main() {
 /// Clean up test database files after tests
 fn cleanup_test_databases() {
     if let Ok(entries) = fs::read_dir(".") {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if let Some(name) = path.file_name() {
-                    if let Some(name_str) = name.to_str() {
-                        if name_str.starts_with("test_weather_bot_data_") {
-                            if path.is_dir() {
-                                if let Err(e) = fs::remove_dir_all(&path) {
-                                    println!("❌ Failed to remove {}: {}", name_str, e);
-                                } else {
-                                    println!("🗑️  Removed: {}", name_str);
-                                }
-                            }
-                        }
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if let Some(name_str) = path.file_name().and_then(|n| n.to_str()) {
+                if name_str.starts_with("test_weather_bot_data_") && path.is_dir() {
+                    if let Err(e) = fs::remove_dir_all(&path) {
+                        println!("❌ Failed to remove {}: {}", name_str, e);
+                    } else {
+                        println!("🗑️  Removed: {}", name_str);
                     }
                 }
             }